@@ -0,0 +1,259 @@
+//! Byte transport used by a SerialInterface: a real serial port when opened from a path, a TCP
+//! stream to a serial gateway (see `SerialInterface::connect_tcp()`), or any other implementation
+//! passed with `SerialInterface::backend()`.
+
+use serial::SerialPort;
+use std::any::Any;
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpStream};
+use std::time::Duration;
+
+#[cfg(any(test, feature = "test-util"))]
+use std::collections::VecDeque;
+#[cfg(any(test, feature = "test-util"))]
+use std::sync::{Arc, Mutex};
+#[cfg(any(test, feature = "test-util"))]
+use std::time::Instant;
+
+/// Byte transport of a SerialInterface. Reads must not block: when no data is available, a read
+/// returns Ok(0) (or an `Operation timed out` error, as serial ports do).
+/// Implemented for any `Read + Write + Send` type, the real serial port (`serial::SystemPort`)
+/// included. As `Box<dyn SerialBackend>` is such a type too, the methods of a boxed backend must be
+/// called on the trait object (e.g. through `Option::as_deref_mut()`), not on the box.
+pub trait SerialBackend: Read + Write + Send {
+    /// Returns the backend as Any, used to reach the API of a real serial port behind the trait
+    /// object.
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+
+    /// Sets the read timeout of a real serial port or of a TcpBackend, no-op for the other backends.
+    fn set_read_timeout(&mut self, timeout: Duration) -> std::io::Result<()>;
+}
+
+impl<T: Read + Write + Send + 'static> SerialBackend for T {
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn set_read_timeout(&mut self, timeout: Duration) -> std::io::Result<()> {
+        let any = self.as_any_mut();
+        if let Some(port) = any.downcast_mut::<serial::SystemPort>() {
+            Ok(SerialPort::set_timeout(port, timeout)?)
+        } else if let Some(tcp) = any.downcast_mut::<TcpBackend>() {
+            // a zero timeout is rejected by TcpStream
+            tcp.stream
+                .set_read_timeout(Some(timeout.max(Duration::from_millis(1))))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// TCP stream to a serial gateway (Modbus RTU over TCP): the RTU frames are exchanged as is, with
+/// the same framing and CRC as on a serial line, see `SerialInterface::connect_tcp()`.
+/// A connection closed by the peer is reported as a read error (instead of a read of 0 bytes,
+/// meaning no data), so it can trigger a reconnection, see `SerialInterface::auto_reconnect()`.
+pub struct TcpBackend {
+    stream: TcpStream,
+}
+
+impl TcpBackend {
+    /// Connect to `addr`, waiting at most `timeout` for the connection to be established.
+    pub fn connect(addr: &SocketAddr, timeout: Duration) -> std::io::Result<Self> {
+        let stream = TcpStream::connect_timeout(addr, timeout)?;
+        // frames are small and latency bound
+        stream.set_nodelay(true)?;
+        Ok(TcpBackend { stream })
+    }
+}
+
+impl Read for TcpBackend {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        match self.stream.read(buf) {
+            Ok(0) if !buf.is_empty() => Err(std::io::Error::new(
+                std::io::ErrorKind::ConnectionAborted,
+                "connection closed by peer",
+            )),
+            read => read,
+        }
+    }
+}
+
+impl Write for TcpBackend {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.stream.write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.stream.flush()
+    }
+}
+
+/// Test only (`test-util` feature): one end of an in-memory full-duplex link, see `loopback_pair()`.
+#[cfg(any(test, feature = "test-util"))]
+pub struct LoopbackBackend {
+    rx: Arc<Mutex<VecDeque<u8>>>,
+    tx: Arc<Mutex<VecDeque<u8>>>,
+}
+
+/// Test only (`test-util` feature): returns the two ends of an in-memory full-duplex link, the bytes
+/// written on one end are read on the other one. Used to link two SerialInterface (e.g. a Master and
+/// a Slave) and exercise a real request/response round trip without hardware:
+///
+/// ```
+/// # #[cfg(feature = "test-util")]
+/// # fn main() -> Result<(), serial_thread::SerialInterfaceError> {
+/// use serial_thread::backend::loopback_pair;
+/// use serial_thread::SerialInterface;
+/// use std::io::Write;
+/// use std::time::Duration;
+///
+/// let (mut a, b) = loopback_pair();
+/// let mut slave = SerialInterface::with_backend(Box::new(b))?.modbus_id(1);
+/// let request = serial_thread::modbus::build_read_holding_registers(1, 0, 1);
+/// a.write_all(&request).unwrap();
+/// let mut frame = Vec::new();
+/// slave.read_frame_into(&mut frame, &Duration::from_millis(5), &Duration::from_secs(1))?;
+/// assert_eq!(frame, request);
+/// # Ok(())
+/// # }
+/// # #[cfg(not(feature = "test-util"))]
+/// # fn main() {}
+/// ```
+#[cfg(any(test, feature = "test-util"))]
+pub fn loopback_pair() -> (LoopbackBackend, LoopbackBackend) {
+    let a = Arc::new(Mutex::new(VecDeque::new()));
+    let b = Arc::new(Mutex::new(VecDeque::new()));
+    (
+        LoopbackBackend {
+            rx: a.clone(),
+            tx: b.clone(),
+        },
+        LoopbackBackend { rx: b, tx: a },
+    )
+}
+
+#[cfg(any(test, feature = "test-util"))]
+impl Read for LoopbackBackend {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let mut rx = self.rx.lock().expect("poisoned");
+        let len = buf.len().min(rx.len());
+        for (byte, data) in buf.iter_mut().zip(rx.drain(..len)) {
+            *byte = data;
+        }
+        Ok(len)
+    }
+}
+
+#[cfg(any(test, feature = "test-util"))]
+impl Write for LoopbackBackend {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.tx.lock().expect("poisoned").extend(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Test only (`test-util` feature): backend fed with a scripted byte stream, each byte being readable
+/// after its own delay (counted from the previous byte), e.g. to simulate inter-byte gaps and
+/// silence without hardware. Written bytes are recorded. Clones share the script and the record, so
+/// a clone kept aside can feed more bytes and inspect the writes once the backend is passed to a
+/// SerialInterface:
+///
+/// ```
+/// # #[cfg(feature = "test-util")]
+/// # fn main() -> Result<(), serial_thread::SerialInterfaceError> {
+/// use serial_thread::backend::MockBackend;
+/// use serial_thread::SerialInterface;
+/// use std::time::Duration;
+///
+/// let response = [0x01, 0x03, 0x02, 0x00, 0x0A, 0x38, 0x43];
+/// let mock = MockBackend::new().script(&response, Duration::from_millis(1));
+/// let mut si = SerialInterface::with_backend(Box::new(mock.clone()))?;
+/// let mut frame = Vec::new();
+/// si.read_frame_into(&mut frame, &Duration::from_millis(20), &Duration::from_secs(1))?;
+/// assert_eq!(frame, response);
+/// assert!(mock.written().lock().unwrap().is_empty());
+/// # Ok(())
+/// # }
+/// # #[cfg(not(feature = "test-util"))]
+/// # fn main() {}
+/// ```
+#[cfg(any(test, feature = "test-util"))]
+#[derive(Clone, Default)]
+pub struct MockBackend {
+    script: Arc<Mutex<VecDeque<(Duration, u8)>>>,
+    written: Arc<Mutex<Vec<u8>>>,
+    next_at: Option<Instant>,
+}
+
+#[cfg(any(test, feature = "test-util"))]
+impl MockBackend {
+    /// Creates a backend with an empty script.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends data to the script, each byte being readable `delay` after the previous one.
+    /// Returns the modified instance of the MockBackend for method chaining.
+    pub fn script(self, data: &[u8], delay: Duration) -> Self {
+        self.feed(data, delay);
+        self
+    }
+
+    /// Appends data to the script, each byte being readable `delay` after the previous one.
+    pub fn feed(&self, data: &[u8], delay: Duration) {
+        self.script
+            .lock()
+            .expect("poisoned")
+            .extend(data.iter().map(|byte| (delay, *byte)));
+    }
+
+    /// Appends a single byte to the script, readable `delay` after the previous one, e.g. to
+    /// simulate a gap in the middle of a frame.
+    pub fn feed_byte(&self, byte: u8, delay: Duration) {
+        self.script.lock().expect("poisoned").push_back((delay, byte));
+    }
+
+    /// Returns the bytes written to the backend.
+    pub fn written(&self) -> Arc<Mutex<Vec<u8>>> {
+        self.written.clone()
+    }
+}
+
+#[cfg(any(test, feature = "test-util"))]
+impl Read for MockBackend {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let mut script = self.script.lock().expect("poisoned");
+        let mut len = 0;
+        while len < buf.len() {
+            let Some((delay, byte)) = script.front().copied() else {
+                break;
+            };
+            let now = Instant::now();
+            let next_at = *self.next_at.get_or_insert(now + delay);
+            if now < next_at {
+                break;
+            }
+            script.pop_front();
+            self.next_at = None;
+            buf[len] = byte;
+            len += 1;
+        }
+        Ok(len)
+    }
+}
+
+#[cfg(any(test, feature = "test-util"))]
+impl Write for MockBackend {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.written.lock().expect("poisoned").extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
@@ -0,0 +1,137 @@
+//! Capture file format, used to record a serial session and replay it later.
+//!
+//! A capture is a text file containing one frame per line: `<elapsed> <data>`, where `<elapsed>`
+//! is the time since the start of the capture in microseconds and `<data>` the frame bytes, hex
+//! encoded, e.g. `1520 0103000A0001A408`.
+//! Captures are recorded in Sniff mode with `SerialInterface::capture_to()`.
+
+use crate::{SIError, SerialMessage};
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "async-channel")]
+use async_channel::Sender;
+#[cfg(not(feature = "async-channel"))]
+use std::sync::mpsc::Sender;
+
+/// Parse a capture line, returns None if the line is malformed.
+pub fn parse_line(line: &str) -> Option<(Duration, Vec<u8>)> {
+    // only the line ending is trimmed, so an empty frame keeps its separator
+    let (elapsed, data) = line.trim_end_matches(['\r', '\n']).split_once(' ')?;
+    let elapsed = Duration::from_micros(elapsed.parse().ok()?);
+    if data.len() % 2 != 0 || !data.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    let data = (0..data.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(data.get(i..i + 2)?, 16).ok())
+        .collect::<Option<Vec<u8>>>()?;
+    Some((elapsed, data))
+}
+
+/// Format a capture line, see parse_line().
+pub fn format_line(elapsed: Duration, data: &[u8]) -> String {
+    let hex: String = data.iter().map(|b| format!("{:02X}", b)).collect();
+    format!("{} {}", elapsed.as_micros(), hex)
+}
+
+/// Writer of a capture file, appending one line per frame.
+pub struct CaptureWriter {
+    file: File,
+    start: Instant,
+}
+
+impl CaptureWriter {
+    /// Open the capture file in append mode, creating it if needed. The elapsed time of the frames
+    /// is counted from this call.
+    pub fn open(path: &PathBuf) -> Result<Self, SIError> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| SIError::CannotWriteCapture(e.to_string()))?;
+        Ok(CaptureWriter {
+            file,
+            start: Instant::now(),
+        })
+    }
+
+    /// Append a frame, flushed to the file so a crash does not lose the recorded frames.
+    pub fn write_frame(&mut self, data: &[u8]) -> Result<(), SIError> {
+        writeln!(self.file, "{}", format_line(self.start.elapsed(), data))
+            .and_then(|_| self.file.flush())
+            .map_err(|e| SIError::CannotWriteCapture(e.to_string()))
+    }
+}
+
+/// Read all the frames of a capture file.
+fn read_capture(path: &PathBuf) -> Result<Vec<(Duration, Vec<u8>)>, SIError> {
+    let file = File::open(path).map_err(|e| SIError::CannotReadCapture(e.to_string()))?;
+    let mut frames = Vec::new();
+    for line in BufReader::new(file).lines() {
+        let line = line.map_err(|e| SIError::CannotReadCapture(e.to_string()))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let frame = parse_line(&line)
+            .ok_or_else(|| SIError::CannotReadCapture(format!("malformed line: {}", line)))?;
+        frames.push(frame);
+    }
+    Ok(frames)
+}
+
+/// Returns the frames of a capture file with their elapsed time, e.g. to feed a recorded session
+/// through tests without timing.
+pub fn replay_capture(
+    path: PathBuf,
+) -> Result<impl Iterator<Item = (Duration, Vec<u8>)>, SIError> {
+    Ok(read_capture(&path)?.into_iter())
+}
+
+/// Replay a capture file: every recorded frame is sent as a SerialMessage::Receive() through
+/// `to_sender`, as if it was received live, honoring the recorded inter-frame timing divided
+/// by `speed` (1.0 replay at the recorded pace, 2.0 twice as fast, f64::INFINITY without delay).
+/// Blocking until the whole capture has been sent.
+#[cfg(not(feature = "async-channel"))]
+pub fn replay(path: PathBuf, to_sender: Sender<SerialMessage>, speed: f64) -> Result<(), SIError> {
+    if speed.is_nan() || speed <= 0.0 {
+        return Err(SIError::WrongReadArguments);
+    }
+    let frames = read_capture(&path)?;
+    let start = Instant::now();
+    for (elapsed, data) in frames {
+        let at = elapsed.div_f64(speed);
+        std::thread::sleep(at.saturating_sub(start.elapsed()));
+        to_sender
+            .send(SerialMessage::Receive(data))
+            .map_err(|_| SIError::CannotSendMessage)?;
+    }
+    Ok(())
+}
+
+/// Replay a capture file: every recorded frame is sent as a SerialMessage::Receive() through
+/// `to_sender`, as if it was received live, honoring the recorded inter-frame timing divided
+/// by `speed` (1.0 replay at the recorded pace, 2.0 twice as fast, f64::INFINITY without delay).
+#[cfg(feature = "async-channel")]
+pub async fn replay(
+    path: PathBuf,
+    to_sender: Sender<SerialMessage>,
+    speed: f64,
+) -> Result<(), SIError> {
+    if speed.is_nan() || speed <= 0.0 {
+        return Err(SIError::WrongReadArguments);
+    }
+    let frames = read_capture(&path)?;
+    let start = Instant::now();
+    for (elapsed, data) in frames {
+        let at = elapsed.div_f64(speed);
+        tokio::time::sleep(at.saturating_sub(start.elapsed())).await;
+        to_sender
+            .send(SerialMessage::Receive(data))
+            .await
+            .map_err(|_| SIError::CannotSendMessage)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,65 @@
+//! RS-485 direction control through a GPIO line (`gpio` feature), for boards where the DE/RE pins
+//! of the transceiver are wired to a GPIO instead of RTS (e.g. Raspberry Pi).
+//! Linux only: the line is driven through the sysfs GPIO interface (`/sys/class/gpio`), no extra
+//! dependency is needed but the process must have write access to it.
+
+use std::path::PathBuf;
+
+/// Root of the sysfs GPIO interface.
+const SYSFS_GPIO: &str = "/sys/class/gpio";
+
+/// DE/RE line driven by a SerialInterface around each write, see `SerialInterface::de_gpio()`.
+#[derive(Debug, Clone)]
+pub struct DeGpio {
+    number: Option<u32>,
+    root: PathBuf,
+    value: PathBuf,
+    active_high: bool,
+}
+
+impl DeGpio {
+    /// `gpio` is either a GPIO number (e.g. "17", exported on first use) or the path of the value
+    /// file of an already configured line (e.g. "/sys/class/gpio/gpio17/value").
+    /// If `active_high`, the line is high while transmitting, otherwise low.
+    pub fn new(gpio: &str, active_high: bool) -> Self {
+        Self::with_root(gpio, active_high, SYSFS_GPIO)
+    }
+
+    /// Same as new(), a GPIO number being resolved (and exported) under `root` instead of
+    /// `/sys/class/gpio`, e.g. a sysfs mounted elsewhere or a directory standing in for it.
+    pub fn with_root(gpio: &str, active_high: bool, root: impl Into<PathBuf>) -> Self {
+        let root = root.into();
+        match gpio.parse::<u32>() {
+            Ok(number) => DeGpio {
+                number: Some(number),
+                value: root.join(format!("gpio{}", number)).join("value"),
+                root,
+                active_high,
+            },
+            Err(_) => DeGpio {
+                number: None,
+                value: PathBuf::from(gpio),
+                root,
+                active_high,
+            },
+        }
+    }
+
+    /// Drive the line to enable (`transmit`) or disable the transceiver driver.
+    pub fn set(&self, transmit: bool) -> std::io::Result<()> {
+        if !self.value.exists() {
+            self.export()?;
+        }
+        let level = if transmit == self.active_high { "1" } else { "0" };
+        std::fs::write(&self.value, level)
+    }
+
+    /// Export the line by its number and configure it as output.
+    fn export(&self) -> std::io::Result<()> {
+        let number = self.number.ok_or_else(|| {
+            std::io::Error::new(std::io::ErrorKind::NotFound, "GPIO value file not found")
+        })?;
+        std::fs::write(self.root.join("export"), number.to_string())?;
+        std::fs::write(self.root.join(format!("gpio{}", number)).join("direction"), "out")
+    }
+}
@@ -1,9 +1,26 @@
+pub mod backend;
+pub mod capture;
+#[cfg(feature = "gpio")]
+pub mod gpio;
+pub mod modbus;
+#[cfg(feature = "serde")]
+mod serde_remote;
+#[cfg(test)]
+mod tests;
+
 pub use tokio;
 pub use serial;
-use serial::{BaudRate, CharSize, FlowControl, Parity, SerialPort, StopBits, SystemPort};
+use backend::SerialBackend;
+use serial::{BaudRate, CharSize, FlowControl, Parity, PortSettings, SerialPort, StopBits};
 use serialport::available_ports;
+use std::collections::{HashMap, VecDeque};
 use std::io::{Read, Write};
-use std::time::{Duration, Instant};
+use std::net::{SocketAddr, ToSocketAddrs};
+use std::borrow::Cow;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
 use tokio::time::sleep;
 
 #[cfg(feature = "async-channel")]
@@ -15,6 +32,7 @@ use std::sync::mpsc::{Receiver, Sender};
 
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum SerialInterfaceError {
     CannotListPorts,
     StopToChangeSettings,
@@ -22,6 +40,8 @@ pub enum SerialInterfaceError {
     CannotReadPort(Option<String>),
     WrongReadArguments,
     CannotOpenPort(String),
+    PortBusy(String),
+    PortPermissionDenied(String),
     PortNotOpened,
     SlaveModeNeedModbusID,
     PortAlreadyOpen,
@@ -35,10 +55,32 @@ pub enum SerialInterfaceError {
     StopModeBeforeChange,
     WaitingForResponse,
     CannotSetTimeout,
+    CannotReadCapture(String),
+    SenderMissing,
+    ReceiverMissing,
+    NoPorts,
+    MultiplePorts(Vec<String>),
+    UnsupportedBaudRate(u32),
+    InvalidFrame,
+    InvalidCrc,
+    UnexpectedFunction(u8),
+    UnsupportedFunction(u8),
+    SilenceExceedsTimeout,
+    NoResponse,
+    FrameTooLarge,
+    CannotDriveGpio,
+    MinFrameSizeTooSmall,
+    CannotWriteCapture(String),
+    IncompatibleCharSize,
+    CannotSetControlLine,
+    ControlLinesUnsupported,
+    BreakUnsupported,
+    RequestDropped(Vec<u8>),
 }
 
 /// Represents the status of the SerialInterface, indicating its current operation or state.
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Status {
     Read,
     Receipt,
@@ -47,8 +89,139 @@ pub enum Status {
     None,
 }
 
+/// Defines the order in which requests are served in Master/MasterStream modes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Schedule {
+    /// Requests are served in the order they are received.
+    Fifo,
+    /// Requests are queued and served in round robin over the slave id (first byte of the
+    /// request), so a slave flooded with requests cannot starve the others. Requests
+    /// received while waiting for a response are queued instead of being rejected. Requests still
+    /// queued when the mode is stopped are answered with SIError::RequestDropped.
+    RoundRobinBySlave,
+}
+
+/// Defines the on-wire byte order of the CRC16 appended to a frame.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CrcByteOrder {
+    /// Low byte first, Modbus RTU standard.
+    LittleEndian,
+    /// High byte first.
+    BigEndian,
+}
+
+/// Defines which frame try_decode_buffer() returns first in MasterStream mode when a buffer
+/// contains several CRC-valid frames of the same length.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ScanPreference {
+    /// The frame nearest to the start of the buffer.
+    ForwardFirst,
+    /// The frame nearest to the end of the buffer (the most recent one).
+    ReverseFirst,
+}
+
+/// Direction of a sniffed frame, guessed from the slave id, see `SerialInterface::sniff_slave_id()`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FrameDirection {
+    /// Frame sent by the master to the slave.
+    Request,
+    /// Frame sent by the slave in response to the previous request.
+    Response,
+}
+
+/// Defines what happens to a message received on the receiver channel that is not handled in the
+/// current mode (e.g. a `Send` in Stop mode), see `SerialInterface::on_unhandled()`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum UnhandledPolicy {
+    /// The message is silently dropped.
+    Ignore,
+    /// The message is dropped and logged as a warning.
+    Log,
+    /// The message is dropped and a `SerialMessage::Error(SIError::WrongMode)` is sent back.
+    Error,
+}
+
+/// Set of failures triggering a retransmission of the request in Master mode, see
+/// `SerialInterface::retry_on()`. Triggers are combined with `|`, e.g.
+/// `RetryTrigger::TIMEOUT | RetryTrigger::CRC_ERROR`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryTrigger(u8);
+
+impl RetryTrigger {
+    /// No response received before timeout.
+    pub const TIMEOUT: Self = Self(1);
+    /// The response has an invalid CRC.
+    pub const CRC_ERROR: Self = Self(1 << 1);
+    /// The response comes from another slave (only detected if check_response_id is enabled).
+    pub const WRONG_SLAVE: Self = Self(1 << 2);
+    /// No failure triggers a retry.
+    pub const NONE: Self = Self(0);
+    /// All failures trigger a retry.
+    pub const ALL: Self = Self(Self::TIMEOUT.0 | Self::CRC_ERROR.0 | Self::WRONG_SLAVE.0);
+
+    /// Returns whether all the triggers of `other` are in self.
+    pub fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl std::ops::BitOr for RetryTrigger {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+/// Framing of the Modbus frames, orthogonal to the Mode, see `SerialInterface::frame_format()`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FrameFormat {
+    /// Modbus RTU: binary frames ending with a CRC16, delimited by silence.
+    Rtu,
+    /// Modbus ASCII: hex-encoded frames starting with `:`, ending with an LRC and CRLF.
+    Ascii,
+}
+
+/// Checksum algorithms tried by the frame scanner in MasterStream mode, see
+/// `SerialInterface::multi_checksum()`.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ChecksumAlgo {
+    /// Modbus CRC16, in the configured CRC byte order.
+    CrcModbus,
+    /// CRC-16/CCITT-FALSE, big-endian.
+    CrcCcitt,
+    /// Block check character: XOR of the data bytes.
+    Bcc,
+    /// Longitudinal redundancy check: two's complement of the sum of the data bytes.
+    Lrc,
+}
+
+impl ChecksumAlgo {
+    /// All the algorithms, in the order they are tried.
+    pub const ALL: [ChecksumAlgo; 4] = [
+        ChecksumAlgo::CrcModbus,
+        ChecksumAlgo::CrcCcitt,
+        ChecksumAlgo::Bcc,
+        ChecksumAlgo::Lrc,
+    ];
+}
+
+/// Describes the length field of a length-prefixed response, see `SerialInterface::length_field()`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LengthField {
+    /// Length of the header, containing the length field.
+    pub header_len: usize,
+    /// Index of the length field in the header.
+    pub index: usize,
+    /// Length of the trailer following the data (e.g. 2 for the CRC16).
+    pub trailer_len: usize,
+}
+
 /// Defines the operating modes of the SerialInterface.
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Mode {
     /// Operating as a master in a master-slave configuration.
     Master,
@@ -59,11 +232,20 @@ pub enum Mode {
     Slave,
     /// Listening on the serial line without interfering.
     Sniff,
+    /// Raw bytes console (AT-command modems, serial consoles): no framing nor CRC is applied.
+    Terminal,
     /// Stopped or inactive state.
     Stop,
 }
 
+/// Messages exchanged with a SerialInterface over its channels.
+/// With the `serde` feature, SerialMessage (and Mode, Status, SIError) can be (de)serialized, e.g. to
+/// drive the interface from another process. The serde default externally tagged representation is
+/// used: unit variants as a string (`"Ping"`), other variants as a single-key map (`{"Send":[1,3]}`,
+/// `{"SetMode":"Master"}`). The `serial` crate types are mapped in the `serde_remote` module: BaudRate
+/// as its raw speed (`9600`), the other enums by variant name (`"Bits8"`, `"ParityNone"`).
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum SerialMessage {
     // Settings / Flow control (handled when Mode = Stop)
 
@@ -83,36 +265,49 @@ pub enum SerialMessage {
     /// Request: Sets the baud rate for the serial communication.
     /// Type: BaudRate.
     /// Handled in 'Stop' mode. Updates baud rate settings for the serial interface.
-    SetBauds(BaudRate),
+    SetBauds(#[cfg_attr(feature = "serde", serde(with = "serde_remote::baud_rate"))] BaudRate),
+
+    /// Request: Sets the baud rate for the serial communication from a raw integer value.
+    /// Type: u32, standard values are mapped to the matching BaudRate, others to BaudRate::BaudOther.
+    /// Handled in 'Stop' mode. Response: an `Error` message if the value cannot be mapped.
+    SetBaudsRaw(u32),
 
     /// Request: Sets the character size for the serial communication.
     /// Type: CharSize.
     /// Handled in 'Stop' mode. Updates character size settings for the serial interface.
-    SetCharSize(CharSize),
+    SetCharSize(#[cfg_attr(feature = "serde", serde(with = "serde_remote::CharSizeDef"))] CharSize),
 
     /// Request: Sets the parity for the serial communication.
     /// Type: Parity.
     /// Handled in 'Stop' mode. Updates parity settings for the serial interface.
-    SetParity(Parity),
+    SetParity(#[cfg_attr(feature = "serde", serde(with = "serde_remote::ParityDef"))] Parity),
     /// Request: Sets the stop bits for the serial communication.
     /// Type: StopBits.
     /// Handled in 'Stop' mode. Updates stop bits settings for the serial interface.
-    SetStopBits(StopBits),
+    SetStopBits(#[cfg_attr(feature = "serde", serde(with = "serde_remote::StopBitsDef"))] StopBits),
 
     /// Request: Sets the flow control for the serial communication.
     /// Type: FlowControl.
     /// Handled in 'Stop' mode. Updates flow control settings for the serial interface.
-    SetFlowControl(FlowControl),
+    SetFlowControl(
+        #[cfg_attr(feature = "serde", serde(with = "serde_remote::FlowControlDef"))] FlowControl,
+    ),
 
     /// Request: Sets the timeout for the serial communication.
     /// Type: Duration.
-    /// Handled in all modes. Updates timeout settings for the serial interface.
+    /// Handled in all modes. Updates timeout settings for the serial interface, and the read timeout
+    /// of the port if opened.
     SetTimeout(Duration),
 
     /// Request: Establishes a connection using the current serial port settings.
     /// Handled in 'Stop' mode. Response: `Connected(true)` on success, or an `Error` message on failure.
     Connect,
 
+    /// Request: Establishes a connection on the only available serial port, see `open_single()`.
+    /// Handled in 'Stop' mode. Response: `Connected(true)` on success, or `Connected(false)` and an `Error`
+    /// message on failure.
+    OpenSingle,
+
     /// Request: Disconnects the current serial connection.
     /// Handled in all modes. Response: `Connected(false)` after disconnection.
     Disconnect,
@@ -124,6 +319,18 @@ pub enum SerialMessage {
     /// Handled when mode is not 'Stop'. Response: `DataSent` with the sent data upon successful transmission.
     Send(Vec<u8>),
 
+    /// Request: Sends data over the serial connection as is, bypassing auto_crc().
+    /// Type: Vec<u8> representing the data to be sent.
+    /// Handled when mode is not 'Stop'. Response: `DataSent` with the sent data upon successful transmission.
+    SendRaw(Vec<u8>),
+
+    /// Request: Sends data over the serial connection as `Send` does, tagged with an id chosen by
+    /// the app, so the response can be matched to its request when several requests are pipelined.
+    /// Type: the id and Vec<u8> representing the data to be sent.
+    /// Handled in 'Master' and 'MasterStream' modes. Response: `DataSent`, then `ReceiveWithId` or
+    /// `NoResponseWithId` carrying the same id.
+    SendWithId { id: u64, data: Vec<u8> },
+
     /// Response: Indicates that data has been sent over the serial connection.
     /// Type: Vec<u8> representing the sent data.
     DataSent(Vec<u8>),
@@ -132,9 +339,55 @@ pub enum SerialMessage {
     /// Type: Vec<u8> representing the received data.
     Receive(Vec<u8>),
 
+    /// Response: Indicates several data received over the serial connection, emitted instead of
+    /// `Receive` when receives batching is enabled, see `SerialInterface::batch_receives()`.
+    /// Type: Vec<Vec<u8>> representing the received data, in reception order.
+    ReceiveBatch(Vec<Vec<u8>>),
+
+    /// Response: Indicates that the echo of data injected with `Send` in Sniff mode has been read back
+    /// from the serial line, emitted instead of `Receive` so it's not mistaken for bus traffic.
+    /// Type: Vec<u8> representing the injected data.
+    Injected(Vec<u8>),
+
+    /// Response: Indicates that data has been received over the serial connection in MasterStream mode,
+    /// emitted instead of `Receive` when `SerialInterface::multi_checksum()` is enabled.
+    /// Type: the received data, and the checksum algorithm that matched.
+    ReceiveWithChecksum { data: Vec<u8>, algo: ChecksumAlgo },
+
+    /// Response: Indicates the bytes dropped before a frame decoded in MasterStream mode, emitted
+    /// before the frame when `SerialInterface::report_discarded()` is enabled.
+    /// Type: Vec<u8> representing the discarded bytes.
+    Discarded(Vec<u8>),
+
     /// Response: Indicates that data has been sent over the serial connection but no response 
     /// from the peer.
-    NoResponse,
+    /// Type: the unanswered request and the duration waited for the response.
+    NoResponse { request: Vec<u8>, waited: Duration },
+
+    /// Response: Indicates the response to a `SendWithId` request, emitted instead of `Receive` (not
+    /// batched, and `ReceiveWithChecksum` is emitted as is in MasterStream mode).
+    /// Type: the id of the request and Vec<u8> representing the received data.
+    ReceiveWithId { id: u64, data: Vec<u8> },
+
+    /// Response: Indicates that a `SendWithId` request has not been answered, emitted instead of
+    /// `NoResponse`.
+    /// Type: the id of the request.
+    NoResponseWithId { id: u64 },
+
+    /// Response: Indicates that a CRC-valid frame from another slave id has been received while waiting
+    /// for a response in Master mode, the master keeps waiting for the response.
+    /// Emitted only if `SerialInterface::check_response_id()` is enabled.
+    /// Type: the slave id of the request and the slave id of the received frame.
+    WrongSlaveResponse { expected: u8, got: u8 },
+
+    /// Response: Indicates that a slave didn't respond to the last N consecutive requests in Master mode,
+    /// see `SerialInterface::offline_threshold()`.
+    /// Type: u8 representing the slave id.
+    SlaveOffline(u8),
+
+    /// Response: Indicates that a slave previously reported by `SlaveOffline` responds again.
+    /// Type: u8 representing the slave id.
+    SlaveOnline(u8),
 
     // General messages (always handled)
 
@@ -167,16 +420,293 @@ pub enum SerialMessage {
     /// Type: SIError enum.
     Error(SIError),
 
+    /// Response: Indicates that the first bytes received after the port has been opened look like
+    /// garbage (mostly 0x00/0xFF), the baud rate may be wrong. This is a heuristic, see
+    /// `SerialInterface::detect_baud_mismatch()`.
+    PossibleBaudMismatch,
+
+    /// Response: Indicates that the settings applied by the OS differ from the requested ones (e.g. a
+    /// baud rate quantized to the nearest supported value), emitted after `Connected(true)`.
+    /// See `SerialInterface::actual_settings()`.
+    /// Type: the requested settings and the settings actually applied.
+    SettingsQuantized {
+        #[cfg_attr(feature = "serde", serde(with = "serde_remote::PortSettingsDef"))]
+        requested: PortSettings,
+        #[cfg_attr(feature = "serde", serde(with = "serde_remote::PortSettingsDef"))]
+        actual: PortSettings,
+    },
+
     /// Request: Ping message for connection testing.
     /// Response: Generates a `Pong` message in response.
     Ping,
 
     /// Response: Pong message as a response to a Ping.
     Pong,
+
+    /// Request: Stops the main loop, `start()` returns after closing the port.
+    /// Handled in all modes.
+    Shutdown,
+
+    /// Request: Discards the data pending in the read buffer and the partial frame kept from a previous
+    /// read, e.g. to recover from a desynced slave without reconnecting.
+    /// Handled in all modes. Response: `Flushed`, or `Error(PortNotOpened)` if the port is not opened.
+    FlushInput,
+
+    /// Request: Drains the write side, waiting for the pending data to be transmitted.
+    /// Handled in all modes. Response: `Flushed`, or `Error(PortNotOpened)` if the port is not opened.
+    FlushOutput,
+
+    /// Request: FlushInput and FlushOutput at once.
+    /// Handled in all modes. Response: `Flushed`, or `Error(PortNotOpened)` if the port is not opened.
+    FlushAll,
+
+    /// Response: Confirms a FlushInput, FlushOutput or FlushAll request.
+    Flushed,
+
+    /// Request: Asks for the full current configuration of the interface, e.g. to restore a GUI state.
+    /// Handled in all modes. Response: `Settings`.
+    GetSettings,
+
+    /// Response: Current configuration of the interface, in response to `GetSettings`.
+    Settings {
+        path: Option<String>,
+        #[cfg_attr(feature = "serde", serde(with = "serde_remote::baud_rate"))]
+        baud_rate: BaudRate,
+        #[cfg_attr(feature = "serde", serde(with = "serde_remote::CharSizeDef"))]
+        char_size: CharSize,
+        #[cfg_attr(feature = "serde", serde(with = "serde_remote::ParityDef"))]
+        parity: Parity,
+        #[cfg_attr(feature = "serde", serde(with = "serde_remote::StopBitsDef"))]
+        stop_bits: StopBits,
+        #[cfg_attr(feature = "serde", serde(with = "serde_remote::FlowControlDef"))]
+        flow_control: FlowControl,
+        timeout: Duration,
+        silence: Option<Duration>,
+        mode: Mode,
+    },
+
+    /// Response: Frame received in Sniff mode, in place of `Receive` when
+    /// `SerialInterface::timestamped()` is enabled.
+    /// Type: the frame, the time its last byte has been received, and its direction if
+    /// `SerialInterface::sniff_slave_id()` is set and the frame comes from/to this slave.
+    ReceiveTimed {
+        data: Vec<u8>,
+        at: SystemTime,
+        direction: Option<FrameDirection>,
+    },
+
+    /// Request: Closes the port (if opened) and re-opens it from the path with the current settings,
+    /// e.g. after a USB adapter has been unplugged and replugged.
+    /// Handled in all modes. Response: `Connected(true)` on success, or `Connected(false)` and an
+    /// `Error` message on failure.
+    Reconnect,
+
+    /// Response: Indicates the bytes received in MasterStream mode in which no frame with a valid CRC
+    /// has been found before timeout, emitted before `NoResponse` when
+    /// `SerialInterface::report_crc_errors()` is enabled.
+    /// Type: Vec<u8> representing the raw buffer.
+    CrcError(Vec<u8>),
+
+    /// Request: Sends data over the serial connection as `Send` does, waiting up to `timeout` for
+    /// the response instead of the global timeout, e.g. for a slow request (flash erase). With
+    /// retries, the timeout applies to each attempt, and is capped by the deadline if any.
+    /// Type: Vec<u8> representing the data to be sent, and the response timeout.
+    /// Handled in 'Master' and 'MasterStream' modes. Response: `DataSent`, then `Receive` or
+    /// `NoResponse`.
+    SendWithTimeout { data: Vec<u8>, timeout: Duration },
+
+    /// Request: Sends data over the serial connection without waiting for a response, e.g. a
+    /// broadcast (slave address 0) that no slave answers. Only the inter-frame silence is waited
+    /// after the write, instead of the whole timeout.
+    /// Type: Vec<u8> representing the data to be sent.
+    /// Handled in 'Master' and 'MasterStream' modes. Response: `DataSent`.
+    SendNoResponse(Vec<u8>),
+
+    /// Request: Sets the level of the RTS control line, e.g. for manual RS-485 direction control.
+    /// Type: bool, the line level.
+    /// Handled in all modes, the port must be opened. Response: an `Error` message on failure,
+    /// `Error(ControlLinesUnsupported)` for TCP and custom backends.
+    SetRts(bool),
+
+    /// Request: Sets the level of the DTR control line, e.g. to reset a board using DTR for
+    /// auto-reset.
+    /// Type: bool, the line level.
+    /// Handled in all modes, the port must be opened. Response: an `Error` message on failure,
+    /// `Error(ControlLinesUnsupported)` for TCP and custom backends.
+    SetDtr(bool),
+
+    /// Request: Asserts a break condition on the line for the given duration, then releases it.
+    /// Type: Duration of the break.
+    /// Handled in all modes, the port must be opened. Response: an `Error` message on failure,
+    /// `Error(BreakUnsupported)` on windows and for custom backends.
+    SendBreak(Duration),
+
+    /// Request: Reads the input control lines of the port, without disturbing the traffic.
+    /// Handled in all modes, the port must be opened. Response: `ModemStatus`, or an `Error`
+    /// message on failure.
+    GetModemStatus,
+
+    /// Response: Level of the input control lines, in response to `GetModemStatus`, see
+    /// `SerialInterface::modem_status()`.
+    ModemStatus {
+        cts: bool,
+        dsr: bool,
+        cd: bool,
+        ri: bool,
+    },
+
+    /// Request: Lists available serial ports with their metadata, see
+    /// `SerialInterface::list_ports_detailed()`.
+    /// Handled in 'Stop' mode. Response: Triggers `AvailablePortsDetailed` message with port list.
+    ListPortsDetailed,
+
+    /// Response: Provides a list of available serial ports with their metadata.
+    /// Type: Vec<PortInfo>.
+    AvailablePortsDetailed(Vec<PortInfo>),
+
+    /// Request: Sets the address of a serial-to-TCP gateway to be used instead of the serial port,
+    /// see `SerialInterface::connect_tcp()`.
+    /// Type: String in `host:port` form.
+    /// Handled in 'Stop' mode. Affects subsequent `Connect` commands, a `SetPort` switches back to
+    /// the serial port.
+    SetTcp(String),
+}
+
+/// Events reported to the metrics hook, see `SerialInterface::metrics_hook()`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MetricEvent {
+    /// A frame of the given length has been written to the port.
+    FrameSent(usize),
+    /// A frame of the given length has been received and forwarded to the app.
+    FrameReceived(usize),
+    /// No response received before timeout.
+    Timeout,
+    /// A received frame has been dropped because of an invalid CRC.
+    CrcError,
+    /// The port has been (re)opened.
+    PortOpened,
+}
+
+/// Callback invoked on MetricEvent, see `SerialInterface::metrics_hook()`.
+pub type MetricsHook = Arc<dyn Fn(MetricEvent) + Send + Sync>;
+
+/// Transform applied to the payload of every SerialMessage::Receive, see
+/// `SerialInterface::receive_transform()`.
+pub type ReceiveTransform = Arc<dyn Fn(Vec<u8>) -> Vec<u8> + Send + Sync>;
+
+/// Callback invoked on mode transitions, see `SerialInterface::on_enter_mode()`.
+pub type ModeHook = Arc<dyn Fn(&Mode) + Send + Sync>;
+
+/// How a serial port is connected, see `PortInfo`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PortKind {
+    Usb,
+    Pci,
+    Bluetooth,
+    Unknown,
+}
+
+/// A serial port and its metadata, see `SerialInterface::list_ports_detailed()`. The USB fields are
+/// only set for USB ports, and only if reported by the device.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PortInfo {
+    /// Port path, to be used with `SerialMessage::SetPort`.
+    pub name: String,
+    pub kind: PortKind,
+    pub vid: Option<u16>,
+    pub pid: Option<u16>,
+    pub serial_number: Option<String>,
+    pub manufacturer: Option<String>,
+    pub product: Option<String>,
+}
+
+/// Level of the input control lines of the port, see `SerialInterface::modem_status()`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ModemStatus {
+    /// Clear To Send.
+    pub cts: bool,
+    /// Data Set Ready.
+    pub dsr: bool,
+    /// Carrier Detect.
+    pub cd: bool,
+    /// Ring Indicator.
+    pub ri: bool,
+}
+
+/// Features compiled in the active build, see `capabilities()`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Capabilities {
+    /// Channels are async_channel ones, and the API is async (`async-channel` feature).
+    pub async_channel: bool,
+    /// Test helpers are exposed (`test-util` feature).
+    pub test_util: bool,
+    /// SerialMessage can be (de)serialized (`serde` feature).
+    pub serde: bool,
+    /// Capture replay, see the `capture` module.
+    pub capture: bool,
+    /// Modbus response decoding, see the `modbus` module.
+    pub modbus_helpers: bool,
+    /// RS-485 direction control through a GPIO line (`gpio` feature).
+    pub gpio: bool,
+}
+
+/// Returns the features supported by the active build, e.g. to gray out unavailable options in a
+/// GUI or to report them in a bug report.
+pub fn capabilities() -> Capabilities {
+    Capabilities {
+        async_channel: cfg!(feature = "async-channel"),
+        test_util: cfg!(feature = "test-util"),
+        serde: cfg!(feature = "serde"),
+        capture: true,
+        modbus_helpers: true,
+        gpio: cfg!(feature = "gpio"),
+    }
+}
+
+/// App side of the channels of a SerialInterface spawned with `SerialInterface::spawn_dedicated()`.
+pub struct SerialHandle {
+    /// Send requests to the serial thread.
+    pub sender: Sender<SerialMessage>,
+    /// Receive responses from the serial thread.
+    pub receiver: Receiver<SerialMessage>,
 }
 
 type SIError = SerialInterfaceError;
 
+/// Id of the next SerialInterface, used as default name in logs.
+static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// Log through the `log` crate, prefixing the message with the name of the interface, see
+/// `SerialInterface::name()`.
+macro_rules! si_log {
+    ($level:ident, $si:expr, $($arg:tt)+) => {
+        log::$level!("[{}] {}", $si.log_name(), format_args!($($arg)+))
+    };
+}
+
+/// A request served in Master/MasterStream modes.
+struct Request {
+    /// Id of the request, see SerialMessage::SendWithId.
+    id: Option<u64>,
+    data: Vec<u8>,
+    /// Timeout override, see SerialMessage::SendWithTimeout.
+    timeout: Option<Duration>,
+    /// Whether a response is expected, see SerialMessage::SendNoResponse.
+    response: bool,
+}
+
+/// Number of bytes observed by the baud rate mismatch heuristic after the port is opened.
+const BAUD_CHECK_BYTES: usize = 64;
+
+/// Bounds of the delay between two reconnection attempts, see `SerialInterface::auto_reconnect()`.
+const RECONNECT_BACKOFF_MIN: Duration = Duration::from_millis(100);
+const RECONNECT_BACKOFF_MAX: Duration = Duration::from_secs(5);
+
+/// Number of consecutive idle polls (no data, no message) before the idle sleep starts to grow.
+const IDLE_SPINS: u32 = 1000;
+
 /// Represents a serial interface with various modes and functionalities.
 /// It handles serial communication, including reading, writing, and managing port settings.
 /// It operates in different modes such as Master, Slave, and Sniff.
@@ -190,19 +720,97 @@ pub struct SerialInterface {
     parity: Parity,
     stop_bits: StopBits,
     flow_control: FlowControl,
-    port: Option<SystemPort>,
+    port: Option<Box<dyn SerialBackend>>,
     silence: Option<Duration>,
+    sniff_gap: Option<Duration>,
     timeout: Duration,
     receiver: Option<Receiver<SerialMessage>>,
     sender: Option<Sender<SerialMessage>>,
     last_byte_time: Option<Instant>,
+    log_transactions: bool,
+    schedule: Schedule,
+    requests: VecDeque<Request>,
+    last_slave: Option<u8>,
+    crc_byte_order: CrcByteOrder,
+    idle_wait: Duration,
+    write_retries: u8,
+    write_retry_delay: Duration,
+    batch_window: Option<Duration>,
+    batch_max: usize,
+    batch: Vec<Vec<u8>>,
+    batch_start: Option<Instant>,
+    exclusive: bool,
+    shutdown: bool,
+    filter_by_id: bool,
+    length_field: Option<LengthField>,
+    metrics_hook: Option<MetricsHook>,
+    strip_leading_nulls: bool,
+    turnaround: Duration,
+    timeout_margin: f64,
+    monitor_sender: Option<Sender<SerialMessage>>,
+    max_tx_frame: usize,
+    auto_crc: bool,
+    min_bytes_before_silence: usize,
+    drain_on_mode_change: bool,
+    pending: VecDeque<SerialMessage>,
+    check_response_id: bool,
+    on_enter_mode: Option<ModeHook>,
+    on_exit_mode: Option<ModeHook>,
+    scan_preference: ScanPreference,
+    silence_jitter_margin: Duration,
+    injected: Option<Vec<u8>>,
+    on_unhandled: UnhandledPolicy,
+    max_drain: usize,
+    register_order: modbus::WordOrder,
+    offline_threshold: u32,
+    timeouts: HashMap<u8, u32>,
+    retries: u8,
+    retry_delay: Duration,
+    deadline: Option<Duration>,
+    detect_baud_mismatch: bool,
+    baud_check: (usize, usize),
+    baud_mismatch: bool,
+    terminal_window: Duration,
+    multi_checksum: bool,
+    report_discarded: bool,
+    discarded: Option<Vec<u8>>,
+    #[cfg(feature = "async-channel")]
+    oneshot: Option<tokio::sync::oneshot::Sender<SerialMessage>>,
+    retry_on: RetryTrigger,
+    #[cfg(feature = "gpio")]
+    de_gpio: Option<gpio::DeGpio>,
+    post_frame_guard: Duration,
+    actual_settings: Option<PortSettings>,
+    name: Option<String>,
+    id: usize,
+    stop_wait: Duration,
+    receive_transform: Option<ReceiveTransform>,
+    silence_set: bool,
+    frame_format: FrameFormat,
+    min_frame_size: usize,
+    max_buffer_size: usize,
+    transaction_id: Option<u64>,
+    max_idle_sleep: Duration,
+    idle_sleep: Duration,
+    idle_spins: u32,
+    timestamped: bool,
+    sniff_slave_id: Option<u8>,
+    sniff_request: Option<u8>,
+    capture_path: Option<PathBuf>,
+    capture: Option<capture::CaptureWriter>,
+    auto_reconnect: bool,
+    report_crc_errors: bool,
+    crc_error: Option<Vec<u8>>,
+    turnaround_delay: Duration,
+    read_buffer: Vec<u8>,
+    tcp_host: Option<String>,
 }
 
 impl SerialInterface {
     /// Creates a new instance of the SerialInterface with default settings.
     /// Returns a SerialInterface object encapsulated in a Result, with an error if initialization fails.
     pub fn new() -> Result<Self, SIError> {
-        Ok(SerialInterface {
+        let mut si = SerialInterface {
             path: None,
             mode: Mode::Stop,
             status: Status::None,
@@ -213,12 +821,92 @@ impl SerialInterface {
             stop_bits: StopBits::Stop2,
             flow_control: FlowControl::FlowNone,
             port: None,
-            silence: Some(Duration::from_nanos(800)), // FIXME: what policy for init silence here?
+            silence: None, // computed from the default baud rate below, see auto_silence()
             timeout: Duration::from_nanos(10000),     // FIXME: what policy for init timeout here?
+            sniff_gap: None,
             receiver: None,
             sender: None,
             last_byte_time: None,
-        })
+            log_transactions: false,
+            schedule: Schedule::Fifo,
+            requests: VecDeque::new(),
+            last_slave: None,
+            crc_byte_order: CrcByteOrder::LittleEndian,
+            idle_wait: Duration::ZERO,
+            write_retries: 0,
+            write_retry_delay: Duration::from_millis(1),
+            batch_window: None,
+            batch_max: 256,
+            batch: Vec::new(),
+            batch_start: None,
+            exclusive: true,
+            shutdown: false,
+            filter_by_id: true,
+            length_field: None,
+            metrics_hook: None,
+            strip_leading_nulls: false,
+            turnaround: Duration::from_millis(10),
+            timeout_margin: 1.5,
+            monitor_sender: None,
+            max_tx_frame: 256,
+            auto_crc: false,
+            min_bytes_before_silence: 0,
+            drain_on_mode_change: false,
+            pending: VecDeque::new(),
+            check_response_id: false,
+            on_enter_mode: None,
+            on_exit_mode: None,
+            scan_preference: ScanPreference::ForwardFirst,
+            silence_jitter_margin: Duration::ZERO,
+            injected: None,
+            on_unhandled: UnhandledPolicy::Log,
+            max_drain: 4096,
+            register_order: modbus::WordOrder::HighFirst,
+            offline_threshold: 0,
+            timeouts: HashMap::new(),
+            retries: 0,
+            retry_delay: Duration::ZERO,
+            deadline: None,
+            detect_baud_mismatch: false,
+            baud_check: (0, 0),
+            baud_mismatch: false,
+            terminal_window: Duration::from_millis(10),
+            multi_checksum: false,
+            report_discarded: false,
+            discarded: None,
+            #[cfg(feature = "async-channel")]
+            oneshot: None,
+            retry_on: RetryTrigger::ALL,
+            #[cfg(feature = "gpio")]
+            de_gpio: None,
+            post_frame_guard: Duration::ZERO,
+            actual_settings: None,
+            name: None,
+            id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
+            stop_wait: Duration::from_millis(100),
+            receive_transform: None,
+            silence_set: false,
+            frame_format: FrameFormat::Rtu,
+            min_frame_size: 5,
+            max_buffer_size: 256,
+            transaction_id: None,
+            max_idle_sleep: Duration::from_millis(1),
+            idle_sleep: Duration::ZERO,
+            idle_spins: 0,
+            timestamped: false,
+            sniff_slave_id: None,
+            sniff_request: None,
+            capture_path: None,
+            capture: None,
+            auto_reconnect: false,
+            report_crc_errors: false,
+            crc_error: None,
+            turnaround_delay: Duration::ZERO,
+            read_buffer: Vec::new(),
+            tcp_host: None,
+        };
+        si.auto_silence();
+        Ok(si)
     }
 
     /// Sets the path for the serial interface.
@@ -230,9 +918,12 @@ impl SerialInterface {
 
     /// Sets the baud rate for the serial interface.
     /// Returns the modified instance of the SerialInterface for method chaining.
+    /// If silence has not been explicitly set, it's computed from the baud rate, see auto_silence().
     pub fn bauds(mut self, bauds: BaudRate) -> Self {
         self.baud_rate = bauds;
-        // TODO: if self.silence is none => automatic choice
+        if !self.silence_set {
+            self.auto_silence();
+        }
         self
     }
 
@@ -276,223 +967,1268 @@ impl SerialInterface {
     /// Returns the modified instance of the SerialInterface for method chaining.
     pub fn silence(mut self, silence: Duration) -> Self {
         self.silence = Some(silence);
+        self.silence_set = true;
         self
     }
 
-    /// Sets the receiver channel for the serial interface.
+    /// Enables logging of every master transaction in a single `info` line containing the request,
+    /// the response (or NoResponse) and the elapsed time.
     /// Returns the modified instance of the SerialInterface for method chaining.
-    pub fn receiver(mut self, receiver: Receiver<SerialMessage>) -> Self {
-        self.receiver = Some(receiver);
+    pub fn log_transactions(mut self, log_transactions: bool) -> Self {
+        self.log_transactions = log_transactions;
         self
     }
 
-    /// Sets the sender channel for the serial interface.
+    /// Sets the inter-frame gap used to split frames in Sniff mode. Unlike silence, that is the
+    /// Modbus RTU end of frame delimiter, the sniff gap is a generic time-gap framing usable to capture
+    /// any protocol. If not set, Sniff mode use the silence interval.
     /// Returns the modified instance of the SerialInterface for method chaining.
-    pub fn sender(mut self, sender: Sender<SerialMessage>) -> Self {
-        self.sender = Some(sender);
+    pub fn sniff_gap(mut self, gap: Duration) -> Self {
+        self.sniff_gap = Some(gap);
         self
     }
 
-    /// Sets the operating mode of the SerialInterface.
-    /// Can only be set when the current mode is 'Stop'.
-    /// Returns a Result with () or an error if the mode cannot be changed.
-    pub fn set_mode(&mut self, m: Mode) -> Result<(), SIError> {
-        if let Mode::Stop = &self.mode {
-            if self.modbus_id.is_none() {
-                if let Mode::Slave = m {
-                    return Err(SIError::SlaveModeNeedModbusID);
-                }
-            } else if self.port.is_some() {
-                return Err(SIError::DisconnectToChangeSettings);
-            }
-            self.mode = m;
-            log::info!("SerialInterface::switch mode to {:?}", &self.mode);
-            Ok(())
-        } else {
-            Err(SIError::StopToChangeSettings)
-        }
+    /// Sets the scheduling policy of requests in Master/MasterStream modes, default to Schedule::Fifo.
+    /// Returns the modified instance of the SerialInterface for method chaining.
+    pub fn schedule(mut self, schedule: Schedule) -> Self {
+        self.schedule = schedule;
+        self
     }
 
-    /// Retrieves the current operating mode of the SerialInterface.
-    pub fn get_mode(&self) -> &Mode {
-        &self.mode
+    /// Sets the on-wire byte order of the CRC16, default to CrcByteOrder::LittleEndian (Modbus RTU).
+    /// Returns the modified instance of the SerialInterface for method chaining.
+    pub fn crc_byte_order(mut self, order: CrcByteOrder) -> Self {
+        self.crc_byte_order = order;
+        self
     }
 
-    /// Retrieves the current status of the SerialInterface.
-    pub fn get_state(&self) -> &Status {
-        &self.status
+    /// Sets how long the read loops wait for an incoming message between two polls of the serial
+    /// port while no data is being received. The wait is interrupted as soon as a message arrives
+    /// on the receiver channel, so control messages (e.g. SetMode(Stop)) are handled immediately
+    /// while the CPU is not spinning on an idle line. The line is polled again at least every port
+    /// read timeout (one character time, see read_timeout()): the wait is sliced to it, so a frame
+    /// starting during a long idle wait is not delayed. Note that a blocking read on the port itself
+    /// cannot be interrupted, the port read timeout must stay short.
+    /// Default to zero (busy polling).
+    /// Returns the modified instance of the SerialInterface for method chaining.
+    pub fn idle_wait(mut self, idle_wait: Duration) -> Self {
+        self.idle_wait = idle_wait;
+        self
     }
 
-    /// Lists available serial ports.
-    /// Returns a Result containing a list of port names or an error if ports cannot be listed.
-    pub fn list_ports() -> Result<Vec<String>, SIError> {
-        // TODO: get rid of serialport crate dependency
-        if let Ok(ports) = available_ports() {
-            Ok(ports.iter().map(|p| p.port_name.clone()).collect())
-        } else {
-            Err(SerialInterfaceError::CannotListPorts)
-        }
-        // Ok(vec!["/dev/ttyXR0".to_string(), "/dev/ttyXR1".to_string()])
+    /// Sets how many times a write is retried on transient errors (WouldBlock, Interrupted, TimedOut),
+    /// default to 0.
+    /// Returns the modified instance of the SerialInterface for method chaining.
+    pub fn write_retries(mut self, retries: u8) -> Self {
+        self.write_retries = retries;
+        self
     }
 
-    /// CLear data from the read buffer.
-    fn clear_read_buffer(&mut self) -> Result<(), SIError> {
-        let port_open = self.port.is_some();
-        if port_open {
-            let mut buffer = [0u8; 24];
-            loop {
-                let read = self.port.as_mut().unwrap().read(&mut buffer);
-                let ret = match read {
-                    Ok(r) => {
-                        log::debug!("SerialInterface::buffer clear {:?}", buffer.to_vec());
-                        r
-                    }
-                    Err(e) => {
-                        let str_err = e.to_string();
-                        if str_err == *"Operation timed out" {
-                            0
-                        } else {
-                            return Err(SIError::CannotReadPort(Some(str_err)));
-                        }
-                    }
-                };
-                if ret == 0 {
-                    break;
-                };
-            }
-            Ok(())
-        } else {
-            Err(SIError::PortNotOpened)
-        }
+    /// Sets the delay between two write attempts, default to 1ms.
+    /// Returns the modified instance of the SerialInterface for method chaining.
+    pub fn write_retry_delay(mut self, delay: Duration) -> Self {
+        self.write_retry_delay = delay;
+        self
     }
 
-    /// Read 1 bytes of data, return None if no data in buffer.
-    fn read_byte(&mut self) -> Result<Option<u8>, SIError> {
-        let port_open = self.port.is_some();
-        if port_open {
-            let mut buffer = [0u8; 1];
-            let read = self.port.as_mut().unwrap().read(&mut buffer);
-            let l = match read {
-                Ok(r) => r,
-                Err(e) => {
-                    let str_err = e.to_string();
-                    if str_err == *"Operation timed out" {
-                        0
-                    } else {
-                        return Err(SIError::CannotReadPort(Some(str_err)));
-                    }
-                }
-            };
-            if l > 0 {
-                let rcv_time = Instant::now();
-                let from_last = self
-                    .last_byte_time
-                    .map(|last_byte| rcv_time.duration_since(last_byte));
-                log::debug!(
-                    "SerialInterface::read_byte({:?}, from last: {:?})",
-                    buffer,
-                    from_last
-                );
-                self.last_byte_time = Some(rcv_time);
-                Ok(Some(buffer[0]))
-            } else {
-                Ok(None)
-            }
-        } else {
-            Err(SIError::PortNotOpened)
-        }
+    /// Enables batching of received data: instead of one `Receive` message per frame, frames are
+    /// accumulated and emitted in a single `ReceiveBatch` message every `window` (or as soon as
+    /// batch_max frames are pending). This reduces the channel and wakeup overhead at high frame
+    /// rate, at the cost of up to `window` of added latency per frame. Disabled by default.
+    /// Returns the modified instance of the SerialInterface for method chaining.
+    pub fn batch_receives(mut self, window: Duration) -> Self {
+        self.batch_window = Some(window);
+        self
     }
-    
-    /// Generalist read() implementation, polling serial buffer, while not data been received on serial buffer,
-    /// checking received messages on self.receiver , if Send() received, return.
-    /// Error if none of size/silence/timeout passed.
-    #[cfg(not(feature = "async-channel"))]
-    #[allow(unused)]
-    fn read_until_size_or_silence_or_timeout_or_message(
-        &mut self,
-        size: Option<usize>,
-        silence: Option<&Duration>,
-        timeout: Option<&Duration>,
-    ) -> Result<Option<SerialMessage>, SIError> {
-        self.clear_read_buffer()?;
-        let mut buffer: Vec<u8> = Vec::new();
-        let start = Instant::now();
-        let mut last_data = Instant::now();
 
-        if !(size.is_some() || timeout.is_some() || silence.is_some()) {
-            return Err(SIError::WrongReadArguments);
-        }
+    /// Sets the max number of frames in a `ReceiveBatch`, default to 256.
+    /// Returns the modified instance of the SerialInterface for method chaining.
+    pub fn batch_max(mut self, max: usize) -> Self {
+        self.batch_max = max.max(1);
+        self
+    }
 
-        loop {
-            let result = self.read_byte()?;
-            // receive data
-            if let Some(data) = result {
-                // log::debug!("Start receive data: {}", data);
-                self.status = Status::Receipt;
-                buffer.push(data);
-                // reset the silence counter
-                last_data = Instant::now();
+    /// Sets whether the port is opened with exclusive access, default to true.
+    /// On unix the port is locked on open (TIOCEXCL), another process trying to open it then fails
+    /// with EBUSY (SIError::PortBusy), if exclusive is false the lock is released right after
+    /// open (TIOCNXCL). Note that a privileged process can still open a locked port.
+    /// On windows, serial ports are always opened exclusively, this setting has no effect.
+    /// Returns the modified instance of the SerialInterface for method chaining.
+    pub fn exclusive(mut self, exclusive: bool) -> Self {
+        self.exclusive = exclusive;
+        self
+    }
 
-                // check for size reach
-                if let Some(size) = &size {
-                    if &buffer.len() == size {
-                        let result = self
-                            .send_message(SerialMessage::Receive(buffer.clone()));
-                        self.status = Status::None;
-                        return if let Err(e) = result {
-                            Err(e)
-                        } else {
-                            Ok(None)
-                        };
-                    }
-                }
-            } else if let Some(silence) = silence {
-                // we not yet start receive
-                if buffer.is_empty() {
-                    // Wait to receive first data
-                    if let Some(msg) = self.read_message()? {
-                        return Ok(Some(msg));
-                    }
-                    last_data = Instant::now();
-                } else {
-                    // receiving and waiting for silence
-                    let from_last_data = &Instant::now().duration_since(last_data);
-                    // log::debug!("Duration from last data: {:?}", from_last_data);
-                    if from_last_data > silence {
-                        log::debug!("silence reached, data received: {:?}", buffer.to_vec());
-                        let result = self
-                            .send_message(SerialMessage::Receive(buffer.clone()));
-                        self.status = Status::None;
-                        return if let Err(e) = result {
-                            Err(e)
-                        } else {
-                            Ok(None)
-                        };
+    /// Sets whether Slave mode only forwards frames addressed to self.modbus_id (or broadcast) with a
+    /// valid CRC, see accepts_frame(), other frames are silently dropped and listening resumes.
+    /// Default to true, set to false for a promiscuous slave (every frame is forwarded).
+    /// Returns the modified instance of the SerialInterface for method chaining.
+    pub fn filter_by_id(mut self, filter_by_id: bool) -> Self {
+        self.filter_by_id = filter_by_id;
+        self
+    }
+
+    /// Sets the length field used to read responses in Master mode, instead of waiting for silence:
+    /// the response length is computed from the header, see read_with_length_field(). E.g. for Modbus
+    /// read registers responses: length_field(3, 2, 2).
+    /// Note: responses that don't follow the layout (e.g. Modbus exception responses) are only
+    /// returned once timeout is reached.
+    /// Returns the modified instance of the SerialInterface for method chaining.
+    pub fn length_field(mut self, header_len: usize, index: usize, trailer_len: usize) -> Self {
+        self.length_field = Some(LengthField {
+            header_len,
+            index,
+            trailer_len,
+        });
+        self
+    }
+
+    /// Sets a callback invoked on key events (frame sent/received, timeout, CRC error, port opened),
+    /// a cheap integration point for metrics (Prometheus, statsd, ...).
+    /// Note: the hook runs on the serial thread and must not block.
+    /// Returns the modified instance of the SerialInterface for method chaining.
+    pub fn metrics_hook(mut self, hook: MetricsHook) -> Self {
+        self.metrics_hook = Some(hook);
+        self
+    }
+
+    /// Sets a delay waited in Master mode after writing a request, before listening for the response,
+    /// e.g. for a slow RS-485 transceiver still driving the line after the transmission: the bytes
+    /// received during the delay (our own trailing bytes) are discarded. The response timeout starts
+    /// after the delay. Distinct from silence, that delimits the frames on the line, and from
+    /// turnaround(), only used to compute suggested timeouts.
+    /// Default to zero.
+    /// Returns the modified instance of the SerialInterface for method chaining.
+    pub fn turnaround_delay(mut self, delay: Duration) -> Self {
+        self.turnaround_delay = delay;
+        self
+    }
+
+    /// Sets whether 0x00 bytes received before the first non-zero byte of a frame are discarded, for
+    /// transceivers emitting a spurious null byte when the line leaves idle. 0x00 bytes after the
+    /// first non-zero byte of the frame are kept. Default to false.
+    /// Returns the modified instance of the SerialInterface for method chaining.
+    pub fn strip_leading_nulls(mut self, strip: bool) -> Self {
+        self.strip_leading_nulls = strip;
+        self
+    }
+
+    /// Sets the expected slave turnaround (delay between the end of the request and the start of the
+    /// response), used by suggest_timeout(). Default to 10ms.
+    /// Returns the modified instance of the SerialInterface for method chaining.
+    pub fn turnaround(mut self, turnaround: Duration) -> Self {
+        self.turnaround = turnaround;
+        self
+    }
+
+    /// Sets the margin factor applied by suggest_timeout(). Default to 1.5.
+    /// Returns the modified instance of the SerialInterface for method chaining.
+    pub fn timeout_margin(mut self, margin: f64) -> Self {
+        self.timeout_margin = margin;
+        self
+    }
+
+    /// Sets the maximum length of a frame to write, larger SerialMessage::Send() are rejected with a
+    /// SerialMessage::Error(SIError::FrameTooLarge). Default to 256 (Modbus RTU frame limit).
+    /// Returns the modified instance of the SerialInterface for method chaining.
+    pub fn max_tx_frame(mut self, max_tx_frame: usize) -> Self {
+        self.max_tx_frame = max_tx_frame;
+        self
+    }
+
+    /// Sets whether the CRC16 is appended to SerialMessage::Send() payloads in Master/MasterStream modes.
+    /// A payload already ending with a valid CRC (see check_crc()) is sent as is: a bare payload whose
+    /// last 2 bytes happen to be the CRC of the previous ones would not get a CRC appended. To bypass
+    /// the detection, append the CRC with append_crc() and use SerialMessage::SendRaw(). Default to false.
+    /// Returns the modified instance of the SerialInterface for method chaining.
+    pub fn auto_crc(mut self, auto_crc: bool) -> Self {
+        self.auto_crc = auto_crc;
+        self
+    }
+
+    /// Sets the minimum number of bytes of a frame before silence is applied as end of frame delimiter,
+    /// for devices with occasional inter-byte gaps larger than silence within a frame. Timeout still
+    /// applies. Default to 0 (silence applies from the first byte).
+    /// Returns the modified instance of the SerialInterface for method chaining.
+    pub fn min_bytes_before_silence(mut self, min_bytes: usize) -> Self {
+        self.min_bytes_before_silence = min_bytes;
+        self
+    }
+
+    /// Sets whether the messages queued on the receiver channel are drained when start() leaves
+    /// Stop mode: when the SetMode() is dequeued, the stale SerialMessage::Send() /
+    /// SerialMessage::SendRaw() queued before (e.g. left by a stopped master) are discarded, all
+    /// other (control) messages are preserved. The messages sent once the SetMode() is dequeued are
+    /// kept. Default to false.
+    /// Returns the modified instance of the SerialInterface for method chaining.
+    pub fn drain_on_mode_change(mut self, drain: bool) -> Self {
+        self.drain_on_mode_change = drain;
+        self
+    }
+
+    /// Sets whether, in Master mode, a CRC-valid response whose slave id does not match the request one
+    /// is rejected: SerialMessage::WrongSlaveResponse is emitted and the master keeps waiting for the
+    /// response. Default to false.
+    /// Returns the modified instance of the SerialInterface for method chaining.
+    pub fn check_response_id(mut self, check: bool) -> Self {
+        self.check_response_id = check;
+        self
+    }
+
+    /// Sets a callback invoked by start() with the new mode each time a mode is entered, e.g. to
+    /// energize a relay when entering Master mode.
+    /// Note: the hook runs on the serial thread and must not block.
+    /// Returns the modified instance of the SerialInterface for method chaining.
+    pub fn on_enter_mode(mut self, hook: ModeHook) -> Self {
+        self.on_enter_mode = Some(hook);
+        self
+    }
+
+    /// Sets a callback invoked by start() with the old mode each time a mode is exited.
+    /// Note: the hook runs on the serial thread and must not block.
+    /// Returns the modified instance of the SerialInterface for method chaining.
+    pub fn on_exit_mode(mut self, hook: ModeHook) -> Self {
+        self.on_exit_mode = Some(hook);
+        self
+    }
+
+    /// Sets the scan preference used to decode frames in MasterStream mode, ReverseFirst prefers the
+    /// most recent frame when garbage precedes it. When the buffer holds two distinct valid frames of
+    /// the same length, the first one is returned with ForwardFirst and the last one with
+    /// ReverseFirst. Default to ForwardFirst.
+    /// Returns the modified instance of the SerialInterface for method chaining.
+    pub fn scan_preference(mut self, scan: ScanPreference) -> Self {
+        self.scan_preference = scan;
+        self
+    }
+
+    /// Creates a new instance of the SerialInterface with default settings, using `backend` as port,
+    /// see backend().
+    pub fn with_backend(backend: Box<dyn SerialBackend>) -> Result<Self, SIError> {
+        Ok(Self::new()?.backend(backend))
+    }
+
+    /// Creates a new instance of the SerialInterface with default settings, connected to the
+    /// serial-to-TCP gateway at `addr` instead of a serial port. The RTU framing and CRC are the same
+    /// as on a serial line, the serial settings (baud rate, ...) being only used for the timings
+    /// (silence, read timeout). The connection is established again on reconnect().
+    pub fn connect_tcp(addr: SocketAddr) -> Result<Self, SIError> {
+        let mut si = Self::new()?;
+        si.tcp_host = Some(addr.to_string());
+        si.open_port()?;
+        Ok(si)
+    }
+
+    /// Sets the backend used as port instead of opening a serial port from self.path, e.g. a
+    /// `backend::LoopbackBackend` in tests. The port is considered opened.
+    /// Returns the modified instance of the SerialInterface for method chaining.
+    pub fn backend(mut self, backend: Box<dyn SerialBackend>) -> Self {
+        self.port = Some(backend);
+        self.actual_settings = None;
+        self
+    }
+
+    /// Sets a margin added to the silence threshold. Silence is measured from the time read_byte()
+    /// returns (no kernel RX timestamps are used), which includes syscall and scheduling jitter: on a
+    /// busy system a late read can make an inter-byte gap look longer than it was, and falsely detect
+    /// silence. The margin tolerates such delays. Default to 0.
+    /// Returns the modified instance of the SerialInterface for method chaining.
+    pub fn silence_jitter_margin(mut self, margin: Duration) -> Self {
+        self.silence_jitter_margin = margin;
+        self
+    }
+
+    /// Sets what happens to messages not handled in the current mode, e.g. a SerialMessage::Send()
+    /// in Stop mode. Default to UnhandledPolicy::Log.
+    /// Returns the modified instance of the SerialInterface for method chaining.
+    pub fn on_unhandled(mut self, policy: UnhandledPolicy) -> Self {
+        self.on_unhandled = policy;
+        self
+    }
+
+    /// Sets the maximum number of bytes discarded when clearing the read buffer before a read, after
+    /// which the read starts even if data is still arriving (continuously busy line). Default to 4096.
+    /// Returns the modified instance of the SerialInterface for method chaining.
+    pub fn max_drain(mut self, max_drain: usize) -> Self {
+        self.max_drain = max_drain;
+        self
+    }
+
+    /// Sets the order of the registers of 32-bit values, used by decode_response() (and so
+    /// query_registers()) and registers_to_u32()/registers_to_f32().
+    /// Default to WordOrder::HighFirst (standard Modbus, high register first).
+    /// Returns the modified instance of the SerialInterface for method chaining.
+    pub fn register_order(mut self, order: modbus::WordOrder) -> Self {
+        self.register_order = order;
+        self
+    }
+
+    /// Sets the number of consecutive timeouts after which a slave is reported offline in Master mode
+    /// with SerialMessage::SlaveOffline(), SerialMessage::SlaveOnline() is emitted when it responds
+    /// again. Default to 0 (disabled).
+    /// Returns the modified instance of the SerialInterface for method chaining.
+    pub fn offline_threshold(mut self, threshold: u32) -> Self {
+        self.offline_threshold = threshold;
+        self
+    }
+
+    /// Sets the number of times a request is retransmitted in Master mode when no response is received
+    /// (or on another failure, see retry_on()), before giving up with SerialMessage::NoResponse.
+    /// Default to 0.
+    /// Returns the modified instance of the SerialInterface for method chaining.
+    pub fn retries(mut self, retries: u8) -> Self {
+        self.retries = retries;
+        self
+    }
+
+    /// Sets the delay before each retransmission of a request (see retries()), e.g. to let a busy
+    /// slave recover. Default to zero.
+    /// Returns the modified instance of the SerialInterface for method chaining.
+    pub fn retry_delay(mut self, delay: Duration) -> Self {
+        self.retry_delay = delay;
+        self
+    }
+
+    /// Sets which failures trigger a retransmission of the request (see retries()): a timeout, a
+    /// response with an invalid CRC, and/or a response from another slave. When retries are exhausted
+    /// (or the failure is not a trigger), the response is forwarded as without retries.
+    /// Default to RetryTrigger::ALL.
+    /// Returns the modified instance of the SerialInterface for method chaining.
+    pub fn retry_on(mut self, trigger: RetryTrigger) -> Self {
+        self.retry_on = trigger;
+        self
+    }
+
+    /// Sets a GPIO line driving the DE/RE pins of an RS-485 transceiver (`gpio` feature, Linux sysfs,
+    /// see the `gpio` module): the line is asserted before each write, and released once the port has
+    /// been flushed (all bytes transmitted), so the bus is released right after the frame.
+    /// `gpio` is a GPIO number or the path of its value file. If `active_high`, the line is high
+    /// while transmitting. Default to None (no direction control).
+    /// Returns the modified instance of the SerialInterface for method chaining.
+    #[cfg(feature = "gpio")]
+    pub fn de_gpio(mut self, gpio: &str, active_high: bool) -> Self {
+        self.de_gpio = Some(gpio::DeGpio::new(gpio, active_high));
+        self
+    }
+
+    /// Same as de_gpio(), with a line created by the caller, e.g. by gpio::DeGpio::with_root() for
+    /// a sysfs GPIO interface out of `/sys/class/gpio`.
+    /// Returns the modified instance of the SerialInterface for method chaining.
+    #[cfg(feature = "gpio")]
+    pub fn de_gpio_line(mut self, line: gpio::DeGpio) -> Self {
+        self.de_gpio = Some(line);
+        self
+    }
+
+    /// Sets an overall deadline for a Master transaction, retries included: no retry is attempted once
+    /// the deadline has passed, even if retries remain, and the timeout of each attempt is capped to
+    /// the time left before the deadline. The worst-case latency of a transaction is then the deadline
+    /// instead of (retries + 1) * timeout. Default to None.
+    /// Returns the modified instance of the SerialInterface for method chaining.
+    pub fn deadline(mut self, deadline: Duration) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// Sets whether the first bytes received after the port is opened are watched for a wrong baud
+    /// rate, SerialMessage::PossibleBaudMismatch is emitted if more than half of them are 0x00 or
+    /// 0xFF (framing errors are not reported by the serial crate, so they can't be counted). This is a
+    /// heuristic: it can miss a mismatch or flag a legit traffic. Default to false.
+    /// Returns the modified instance of the SerialInterface for method chaining.
+    pub fn detect_baud_mismatch(mut self, detect: bool) -> Self {
+        self.detect_baud_mismatch = detect;
+        self
+    }
+
+    /// Sets the window over which received bytes are coalesced in a SerialMessage::Receive() in
+    /// Terminal mode. Default to 10ms.
+    /// Returns the modified instance of the SerialInterface for method chaining.
+    pub fn terminal_window(mut self, window: Duration) -> Self {
+        self.terminal_window = window;
+        self
+    }
+
+    /// Sets whether the frame scanner of MasterStream mode tries all the ChecksumAlgo instead of the
+    /// Modbus CRC only, frames are then emitted as SerialMessage::ReceiveWithChecksum, identifying the
+    /// algorithm that matched. An analysis aid for unknown protocols: scanning is more expensive, and
+    /// 1 byte checksums (BCC, LRC) are more likely to match garbage. Default to false.
+    /// Returns the modified instance of the SerialInterface for method chaining.
+    pub fn multi_checksum(mut self, multi_checksum: bool) -> Self {
+        self.multi_checksum = multi_checksum;
+        self
+    }
+
+    /// Sets whether the bytes dropped before a frame decoded in MasterStream mode (echo, noise, wrong
+    /// baud rate...) are emitted as SerialMessage::Discarded before the frame. Default to false.
+    /// Returns the modified instance of the SerialInterface for method chaining.
+    pub fn report_discarded(mut self, report: bool) -> Self {
+        self.report_discarded = report;
+        self
+    }
+
+    /// Sets whether the bytes received in MasterStream mode without a valid frame before timeout (bad
+    /// CRC, truncated frame, noise) are emitted as SerialMessage::CrcError before the NoResponse, to
+    /// give visibility on the line quality. Default to false.
+    /// Returns the modified instance of the SerialInterface for method chaining.
+    pub fn report_crc_errors(mut self, report: bool) -> Self {
+        self.report_crc_errors = report;
+        self
+    }
+
+    /// Sets a quiet period following each frame emitted in Sniff mode: the bytes received within this
+    /// guard window (e.g. echo or line ringing after a frame) are discarded instead of being captured
+    /// as a spurious short frame. Default to zero (off).
+    /// Returns the modified instance of the SerialInterface for method chaining.
+    pub fn post_frame_guard(mut self, guard: Duration) -> Self {
+        self.post_frame_guard = guard;
+        self
+    }
+
+    /// Sets the name of the interface, prefixing all its log lines (e.g. `[bus-A] ...`) to tell apart
+    /// the interfaces of a multi-bus app. Default to the port path, or `#<id>` (an id incremented for
+    /// each SerialInterface created) if no path is set.
+    /// Returns the modified instance of the SerialInterface for method chaining.
+    pub fn name(mut self, name: String) -> Self {
+        self.name = Some(name);
+        self
+    }
+
+    /// Sets how long the main loop blocks on the receiver channel in Stop mode, waiting for a message,
+    /// before checking for shutdown. A message is handled as soon as it arrives, so this only bounds
+    /// the idle wake-ups, the loop does not spin while waiting for configuration messages.
+    /// Default to 100ms.
+    /// Returns the modified instance of the SerialInterface for method chaining.
+    pub fn stop_wait(mut self, stop_wait: Duration) -> Self {
+        self.stop_wait = stop_wait;
+        self
+    }
+
+    /// Sets a transform applied to the payload of every SerialMessage::Receive before it's sent on the
+    /// channel (e.g. strip a framing, convert endianness), to centralize the post-processing instead of
+    /// doing it in every consumer. Other messages are not transformed. Default to None (frames are
+    /// emitted as received).
+    /// Note: the transform runs on the serial thread and must be cheap.
+    /// Returns the modified instance of the SerialInterface for method chaining.
+    pub fn receive_transform(mut self, transform: ReceiveTransform) -> Self {
+        self.receive_transform = Some(transform);
+        self
+    }
+
+    /// Sets the framing of the Modbus frames: in ASCII, the end of a frame is detected on the CRLF
+    /// terminator (silence is kept as fallback) and frames are validated with their LRC instead of
+    /// the CRC16. auto_crc() only applies to RTU. Default to FrameFormat::Rtu.
+    /// Returns the modified instance of the SerialInterface for method chaining.
+    pub fn frame_format(mut self, format: FrameFormat) -> Self {
+        self.frame_format = format;
+        self
+    }
+
+    /// Sets the size of the smallest frame searched in MasterStream mode, e.g. to decode shorter
+    /// frames (a function code without data is 4 bytes: id, function code and CRC). Must be at least
+    /// 4 (checked by validate()) as the CRC check requires it. Default to 5.
+    /// Returns the modified instance of the SerialInterface for method chaining.
+    pub fn min_frame_size(mut self, size: usize) -> Self {
+        self.min_frame_size = size;
+        self
+    }
+
+    /// Sets the maximum size of the buffer scanned for a frame in MasterStream mode. When exceeded
+    /// (sustained garbage on the line), the oldest bytes are dropped (sliding window) and a warning is
+    /// logged, bounding both the memory and the scan cost. Default to 256 (the Modbus RTU maximum
+    /// frame size).
+    /// Returns the modified instance of the SerialInterface for method chaining.
+    pub fn max_buffer_size(mut self, size: usize) -> Self {
+        self.max_buffer_size = size;
+        self
+    }
+
+    /// Sets the maximum sleep between two polls of an idle line. After a number of polls without
+    /// data nor message, the poll loops start to sleep, doubling the sleep up to max_idle_sleep, and
+    /// go back to busy polling as soon as a byte or a message is received. This bounds the idle CPU
+    /// usage (e.g. a sniffer on a quiet bus) at the cost of up to max_idle_sleep latency on the first
+    /// byte after idle. Zero disables the backoff (busy polling).
+    /// Default to 1ms.
+    /// Returns the modified instance of the SerialInterface for method chaining.
+    pub fn max_idle_sleep(mut self, max_idle_sleep: Duration) -> Self {
+        self.max_idle_sleep = max_idle_sleep;
+        self
+    }
+
+    /// Sets whether the frames received in Sniff mode are emitted as SerialMessage::ReceiveTimed,
+    /// carrying the time of their last byte, e.g. to measure inter-frame timing and response latency.
+    /// Default to false (SerialMessage::Receive).
+    /// Returns the modified instance of the SerialInterface for method chaining.
+    pub fn timestamped(mut self, timestamped: bool) -> Self {
+        self.timestamped = timestamped;
+        self
+    }
+
+    /// Sets the id of the slave watched in Sniff mode, used to tag the direction of the timestamped
+    /// frames (see timestamped()). As requests and responses carry the same slave id on the line, the
+    /// direction is a heuristic: a frame is a Response if it follows a Request to this slave with the
+    /// same function code (or its exception code), a Request otherwise. Frames of other slaves are not
+    /// tagged.
+    /// Default to None (no direction).
+    /// Returns the modified instance of the SerialInterface for method chaining.
+    pub fn sniff_slave_id(mut self, id: u8) -> Self {
+        self.sniff_slave_id = Some(id);
+        self
+    }
+
+    /// Sets a capture file recording every frame received in Sniff mode with its timestamp, in the
+    /// format of the `capture` module (the file is created if needed, and appended to otherwise). Each
+    /// frame is flushed to the file as it's received. A write failure is reported as
+    /// SerialMessage::Error(CannotWriteCapture) and stops the recording.
+    /// See `capture::replay_capture()` to read it back.
+    /// Default to None (no capture).
+    /// Returns the modified instance of the SerialInterface for method chaining.
+    pub fn capture_to(mut self, path: PathBuf) -> Self {
+        self.capture_path = Some(path);
+        self
+    }
+
+    /// Sets whether a hard port error (e.g. an unplugged USB adapter) in Master, MasterStream, Slave
+    /// and Sniff modes triggers a reconnection instead of dropping to Stop mode (Sniff) or failing
+    /// every transaction (Master, Slave). Connected(false) is emitted, then the port is re-opened from
+    /// the path with a backoff (100ms doubling up to 5s) until it succeeds, emitting Connected(true),
+    /// or a SetMode(Stop) is received. Messages received meanwhile are dropped.
+    /// Default to false.
+    /// Returns the modified instance of the SerialInterface for method chaining.
+    pub fn auto_reconnect(mut self, auto_reconnect: bool) -> Self {
+        self.auto_reconnect = auto_reconnect;
+        self
+    }
+
+    /// Sets the receiver channel for the serial interface.
+    /// Returns the modified instance of the SerialInterface for method chaining.
+    pub fn receiver(mut self, receiver: Receiver<SerialMessage>) -> Self {
+        self.receiver = Some(receiver);
+        self
+    }
+
+    /// Sets the sender channel for the serial interface.
+    /// Returns the modified instance of the SerialInterface for method chaining.
+    pub fn sender(mut self, sender: Sender<SerialMessage>) -> Self {
+        self.sender = Some(sender);
+        self
+    }
+
+    /// Sets a monitor channel, receiving a copy of every message sent through the sender channel, e.g.
+    /// for a diagnostic UI or logger alongside the app. The copy is sent just before the message on the
+    /// sender channel, so the monitor sees messages in the same order. If the monitor channel is full or
+    /// closed, the copy is dropped: the monitor never blocks nor breaks the main flow.
+    /// Returns the modified instance of the SerialInterface for method chaining.
+    pub fn monitor_sender(mut self, monitor: Sender<SerialMessage>) -> Self {
+        self.monitor_sender = Some(monitor);
+        self
+    }
+
+    /// Sets the operating mode of the SerialInterface.
+    /// Can only be set when the current mode is 'Stop'.
+    /// Returns a Result with () or an error if the mode cannot be changed.
+    pub fn set_mode(&mut self, m: Mode) -> Result<(), SIError> {
+        if let Mode::Stop = &self.mode {
+            if self.modbus_id.is_none() {
+                if let Mode::Slave = m {
+                    return Err(SIError::SlaveModeNeedModbusID);
+                }
+            } else if self.port.is_some() {
+                return Err(SIError::DisconnectToChangeSettings);
+            }
+            self.check_silence_timeout(&m)?;
+            self.mode = m;
+            self.read_buffer.clear();
+            si_log!(info, self, "SerialInterface::switch mode to {:?}", &self.mode);
+            Ok(())
+        } else {
+            Err(SIError::StopToChangeSettings)
+        }
+    }
+
+    /// Replaces the sender channel, e.g. when the consumer reconnected.
+    /// Can only be set when the current mode is 'Stop', and before start() or from the thread running
+    /// it: swapping channels while start() runs in another thread is not supported.
+    /// Returns a Result with () or an error if the sender cannot be changed.
+    pub fn set_sender(&mut self, sender: Sender<SerialMessage>) -> Result<(), SIError> {
+        if let Mode::Stop = &self.mode {
+            self.sender = Some(sender);
+            Ok(())
+        } else {
+            Err(SIError::StopToChangeSettings)
+        }
+    }
+
+    /// Replaces the receiver channel, e.g. when the consumer reconnected.
+    /// Can only be set when the current mode is 'Stop', and before start() or from the thread running
+    /// it: swapping channels while start() runs in another thread is not supported.
+    /// Returns a Result with () or an error if the receiver cannot be changed.
+    pub fn set_receiver(&mut self, receiver: Receiver<SerialMessage>) -> Result<(), SIError> {
+        if let Mode::Stop = &self.mode {
+            self.receiver = Some(receiver);
+            Ok(())
+        } else {
+            Err(SIError::StopToChangeSettings)
+        }
+    }
+
+    /// Retrieves the current operating mode of the SerialInterface.
+    pub fn get_mode(&self) -> &Mode {
+        &self.mode
+    }
+
+    /// Retrieves the current status of the SerialInterface.
+    pub fn get_state(&self) -> &Status {
+        &self.status
+    }
+
+    /// Retrieves the silence interval used to detect the end of a frame, see silence() and
+    /// auto_silence().
+    pub fn get_silence(&self) -> Option<Duration> {
+        self.silence
+    }
+
+    /// Retrieves the configured baud rate.
+    pub fn get_baud_rate(&self) -> BaudRate {
+        self.baud_rate
+    }
+
+    /// Retrieves the configured character size.
+    pub fn get_char_size(&self) -> CharSize {
+        self.char_size
+    }
+
+    /// Retrieves the configured parity.
+    pub fn get_parity(&self) -> Parity {
+        self.parity
+    }
+
+    /// Retrieves the configured number of stop bits.
+    pub fn get_stop_bits(&self) -> StopBits {
+        self.stop_bits
+    }
+
+    /// Retrieves the configured flow control.
+    pub fn get_flow_control(&self) -> FlowControl {
+        self.flow_control
+    }
+
+    /// Retrieves the configured timeout.
+    pub fn get_timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    /// Retrieves the configured port path, if any.
+    pub fn get_path(&self) -> Option<&String> {
+        self.path.as_ref()
+    }
+
+    /// Checks that the configuration meets the prerequisites of the current mode:
+    ///   - sender and receiver channels are wired.
+    ///   - modbus_id is set in Slave mode.
+    ///   - silence (or sniff_gap in Sniff mode) is set in Master, Slave and Sniff modes.
+    ///   - silence is shorter than timeout in Master mode.
+    ///   - port is opened (or at least a path or TCP gateway is set) in any mode other than Stop.
+    ///   - min_frame_size is at least 4.
+    ///   - char_size is compatible with the frame format in Modbus modes (Master, MasterStream, Slave).
+    ///
+    /// Returns the error matching the first missing prerequisite.
+    pub fn validate(&self) -> Result<(), SIError> {
+        if self.sender.is_none() {
+            return Err(SIError::SenderMissing);
+        }
+        if self.receiver.is_none() {
+            return Err(SIError::ReceiverMissing);
+        }
+        if self.mode == Mode::Slave && self.modbus_id.is_none() {
+            return Err(SIError::SlaveModeNeedModbusID);
+        }
+        let silence = match self.mode {
+            Mode::Master | Mode::Slave => self.silence,
+            Mode::Sniff => self.sniff_gap.or(self.silence),
+            Mode::MasterStream | Mode::Terminal | Mode::Stop => Some(Duration::ZERO),
+        };
+        if silence.is_none() {
+            return Err(SIError::SilenceMissing);
+        }
+        self.check_silence_timeout(&self.mode)?;
+        self.check_char_size(&self.mode)?;
+        if self.min_frame_size < 4 {
+            return Err(SIError::MinFrameSizeTooSmall);
+        }
+        if self.mode != Mode::Stop && self.port.is_none() {
+            return if self.path.is_none() && self.tcp_host.is_none() {
+                Err(SIError::PathMissing)
+            } else {
+                Err(SIError::PortNotOpened)
+            };
+        }
+        Ok(())
+    }
+
+    /// Returns how to fix a configuration error returned by validate(), logged by start().
+    fn validation_hint(e: &SIError) -> &'static str {
+        match e {
+            SIError::SenderMissing => "set the sender channel with sender()",
+            SIError::ReceiverMissing => "set the receiver channel with receiver()",
+            SIError::SlaveModeNeedModbusID => "set the slave id with modbus_id()",
+            SIError::SilenceMissing => "set the silence with silence() (or sniff_gap() to sniff)",
+            SIError::SilenceExceedsTimeout => "use a silence shorter than the timeout",
+            SIError::IncompatibleCharSize => "use 8 data bits for Modbus RTU, 7 or 8 for ASCII",
+            SIError::MinFrameSizeTooSmall => "use a min_frame_size() of at least 4",
+            SIError::PathMissing => "set the port with path() then open() it, or use connect_tcp()",
+            SIError::PortNotOpened => "open() the port before start()",
+            _ => "",
+        }
+    }
+
+    /// In Master mode, a response is framed by silence but must complete before timeout: if silence
+    /// is not shorter than timeout, the timeout always fires first and frames never complete.
+    fn check_silence_timeout(&self, mode: &Mode) -> Result<(), SIError> {
+        match (mode, self.silence) {
+            (Mode::Master, Some(silence))
+                if self.length_field.is_none() && silence >= self.timeout =>
+            {
+                Err(SIError::SilenceExceedsTimeout)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// In Modbus modes, Modbus RTU requires 8 data bits, Modbus ASCII 7 (or 8 on some devices).
+    /// Returns SIError::IncompatibleCharSize otherwise.
+    fn check_char_size(&self, mode: &Mode) -> Result<(), SIError> {
+        if !matches!(mode, Mode::Master | Mode::MasterStream | Mode::Slave) {
+            return Ok(());
+        }
+        match (self.frame_format, self.char_size) {
+            (FrameFormat::Rtu, CharSize::Bits8)
+            | (FrameFormat::Ascii, CharSize::Bits7 | CharSize::Bits8) => Ok(()),
+            _ => Err(SIError::IncompatibleCharSize),
+        }
+    }
+
+    /// Test only (`test-util` feature): retrieves the reception time of the last byte read.
+    #[cfg(feature = "test-util")]
+    pub fn get_last_byte_time(&self) -> Option<Instant> {
+        self.last_byte_time
+    }
+
+    /// Test only (`test-util` feature): overrides the reception time of the last byte read, allowing
+    /// tests to drive the silence/idle logic deterministically without a real port.
+    #[cfg(feature = "test-util")]
+    pub fn set_last_byte_time(&mut self, last_byte_time: Option<Instant>) {
+        self.last_byte_time = last_byte_time;
+    }
+
+    /// Converts a raw baud rate value to a BaudRate, e.g. read from a config file: standard values
+    /// are mapped to their variant, others to BaudRate::BaudOther (the OS may refuse it when the port
+    /// is configured).
+    /// Returns SIError::UnsupportedBaudRate for 0.
+    pub fn baud_from_u32(n: u32) -> Result<BaudRate, SIError> {
+        if n == 0 {
+            Err(SIError::UnsupportedBaudRate(n))
+        } else {
+            Ok(BaudRate::from_speed(n as usize))
+        }
+    }
+
+    /// Converts a BaudRate to its raw value.
+    pub fn baud_to_u32(baud_rate: BaudRate) -> u32 {
+        baud_rate.speed() as u32
+    }
+
+    /// Returns the time needed to transmit `len` bytes with the configured baud rate and framing
+    /// (start bit, char size, parity and stop bits).
+    pub fn tx_duration(&self, len: usize) -> Duration {
+        let bits = self.bits_per_char() as u64 * len as u64;
+        let bauds = Self::baud_to_u32(self.baud_rate).max(1) as u64;
+        Duration::from_micros(bits * 1_000_000 / bauds)
+    }
+
+    /// Computes the Modbus RTU inter-frame silence from the baud rate and framing: 3.5 character
+    /// times, fixed to 1.75ms above 19200 bauds as per the Modbus specification.
+    /// Called by new() for the default baud rate, then by bauds() (and on SetBauds/SetBaudsRaw) as
+    /// long as silence has not been explicitly set, with the char size, parity and stop bits
+    /// configured at that time.
+    pub fn auto_silence(&mut self) {
+        let bauds = Self::baud_to_u32(self.baud_rate).max(1);
+        self.silence = Some(if bauds > 19200 {
+            Duration::from_micros(1750)
+        } else {
+            Duration::from_secs_f64(3.5 * self.bits_per_char() as f64 / bauds as f64)
+        });
+    }
+
+    /// Returns the number of bits on the line per character: start bit, data bits, parity bit (if
+    /// any) and stop bits, e.g. 11 for 8N2.
+    pub fn bits_per_char(&self) -> u32 {
+        let char_bits = match self.char_size {
+            CharSize::Bits5 => 5,
+            CharSize::Bits6 => 6,
+            CharSize::Bits7 => 7,
+            CharSize::Bits8 => 8,
+        };
+        let parity_bits = match self.parity {
+            Parity::ParityNone => 0,
+            Parity::ParityOdd | Parity::ParityEven => 1,
+        };
+        let stop_bits = match self.stop_bits {
+            StopBits::Stop1 => 1,
+            StopBits::Stop2 => 2,
+        };
+        1 + char_bits + parity_bits + stop_bits
+    }
+
+    /// Returns a recommended timeout for a transaction: (request tx + turnaround + response tx)
+    /// multiplied by the timeout margin, see tx_duration(), turnaround() and timeout_margin().
+    pub fn suggest_timeout(&self, request_len: usize, max_response_len: usize) -> Duration {
+        (self.tx_duration(request_len) + self.turnaround + self.tx_duration(max_response_len))
+            .mul_f64(self.timeout_margin)
+    }
+
+    /// Returns the theoretical maximum number of transactions per second, i.e.
+    /// 1 / (request tx + turnaround + response tx + silence), see tx_duration() and turnaround().
+    /// Useful to size a scan cycle: polling faster than this rate only queues requests.
+    pub fn max_poll_rate(&self, req_len: usize, resp_len: usize) -> f64 {
+        let cycle = self.tx_duration(req_len)
+            + self.turnaround
+            + self.tx_duration(resp_len)
+            + self.silence.unwrap_or_default();
+        if cycle.is_zero() {
+            f64::INFINITY
+        } else {
+            1.0 / cycle.as_secs_f64()
+        }
+    }
+
+    /// Decode a Modbus RTU response to the request of function code `request_fn` in the configured
+    /// register order, see register_order() and modbus::decode_response_with_order(): the registers
+    /// of a ModbusResponse::ReadRegisters are returned high register first.
+    pub fn decode_response(
+        &self,
+        request_fn: u8,
+        response: &[u8],
+    ) -> Result<modbus::ModbusResponse, SIError> {
+        modbus::decode_response_with_order(request_fn, response, self.register_order)
+    }
+
+    /// Combine pairs of raw registers (e.g. from modbus::decode_response(), not from
+    /// decode_response() which already reorders them) into 32-bit values, in the configured
+    /// register order, see register_order().
+    pub fn registers_to_u32(&self, registers: &[u16]) -> Vec<u32> {
+        modbus::registers_to_u32(registers, self.register_order)
+    }
+
+    /// Combine pairs of raw registers (e.g. from modbus::decode_response(), not from
+    /// decode_response() which already reorders them) into 32-bit floats, in the configured
+    /// register order, see register_order().
+    pub fn registers_to_f32(&self, registers: &[u16]) -> Vec<f32> {
+        modbus::registers_to_f32(registers, self.register_order)
+    }
+
+    /// Returns whether a frame received in Slave mode would be forwarded to the app: always in
+    /// promiscuous mode (filter_by_id(false)), otherwise only if its checksum is valid and its slave
+    /// id is self.modbus_id or the broadcast address 0.
+    pub fn accepts_frame(&self, frame: &[u8]) -> bool {
+        if !self.filter_by_id {
+            return true;
+        }
+        match self.frame_id(frame) {
+            Some(id) if id == 0 || Some(id) == self.modbus_id => self.check_frame(frame),
+            _ => false,
+        }
+    }
+
+    /// Returns whether the frame checksum is valid for the configured frame format: CRC16 in RTU,
+    /// LRC in ASCII.
+    fn check_frame(&self, frame: &[u8]) -> bool {
+        match self.frame_format {
+            FrameFormat::Rtu => Self::check_crc(frame, self.crc_byte_order),
+            FrameFormat::Ascii => Self::check_ascii(frame),
+        }
+    }
+
+    /// Returns the slave id of a frame in the configured frame format.
+    fn frame_id(&self, frame: &[u8]) -> Option<u8> {
+        match self.frame_format {
+            FrameFormat::Rtu => frame.first().copied(),
+            FrameFormat::Ascii => Self::decode_ascii(frame)?.first().copied(),
+        }
+    }
+
+    /// Decode a Modbus ASCII frame (`:`, hex-encoded bytes, CRLF) to its bytes, LRC included.
+    /// Returns None if the frame is not well formed.
+    pub fn decode_ascii(frame: &[u8]) -> Option<Vec<u8>> {
+        let hex = frame.strip_prefix(b":")?.strip_suffix(b"\r\n")?;
+        if hex.len() % 2 != 0 {
+            return None;
+        }
+        hex.chunks_exact(2)
+            .map(|pair| u8::from_str_radix(std::str::from_utf8(pair).ok()?, 16).ok())
+            .collect()
+    }
+
+    /// Returns whether a Modbus ASCII frame is well formed and ends with a valid LRC.
+    fn check_ascii(frame: &[u8]) -> bool {
+        Self::decode_ascii(frame).is_some_and(|bytes| {
+            bytes.len() > 1
+                && Self::check_checksum(&bytes, ChecksumAlgo::Lrc, CrcByteOrder::LittleEndian)
+        })
+    }
+
+    /// Lists available serial ports.
+    /// Returns a Result containing a list of port names or an error if ports cannot be listed.
+    pub fn list_ports() -> Result<Vec<String>, SIError> {
+        // TODO: get rid of serialport crate dependency
+        if let Ok(ports) = available_ports() {
+            Ok(ports.iter().map(|p| p.port_name.clone()).collect())
+        } else {
+            Err(SerialInterfaceError::CannotListPorts)
+        }
+        // Ok(vec!["/dev/ttyXR0".to_string(), "/dev/ttyXR1".to_string()])
+    }
+
+    /// Lists the available serial ports with their metadata (connection kind, and USB vendor/product
+    /// id, serial number, manufacturer and product), e.g. to select an adapter by its serial number.
+    pub fn list_ports_detailed() -> Result<Vec<PortInfo>, SIError> {
+        let ports = available_ports().map_err(|_| SIError::CannotListPorts)?;
+        Ok(ports
+            .into_iter()
+            .map(|port| {
+                let mut info = PortInfo {
+                    name: port.port_name,
+                    kind: PortKind::Unknown,
+                    vid: None,
+                    pid: None,
+                    serial_number: None,
+                    manufacturer: None,
+                    product: None,
+                };
+                match port.port_type {
+                    serialport::SerialPortType::UsbPort(usb) => {
+                        info.kind = PortKind::Usb;
+                        info.vid = Some(usb.vid);
+                        info.pid = Some(usb.pid);
+                        info.serial_number = usb.serial_number;
+                        info.manufacturer = usb.manufacturer;
+                        info.product = usb.product;
+                    }
+                    serialport::SerialPortType::PciPort => info.kind = PortKind::Pci,
+                    serialport::SerialPortType::BluetoothPort => info.kind = PortKind::Bluetooth,
+                    serialport::SerialPortType::Unknown => {}
+                }
+                info
+            })
+            .collect())
+    }
+
+    /// Discard the pending input (and the partial frames kept by the reads) and/or drain the pending output,
+    /// see SerialMessage::FlushInput / FlushOutput / FlushAll.
+    fn flush_buffers(&mut self, input: bool, output: bool) -> Result<(), SIError> {
+        if self.port.is_none() {
+            return Err(SIError::PortNotOpened);
+        }
+        if input {
+            self.clear_read_buffer()?;
+            self.read_buffer.clear();
+        }
+        if output {
+            self.flush_output()?;
+        }
+        Ok(())
+    }
+
+    /// Drain the write side: block until the data written to the port has been transmitted.
+    fn flush_output(&mut self) -> Result<(), SIError> {
+        let port = self.port.as_mut().ok_or(SIError::PortNotOpened)?;
+        port.flush().map_err(|e| {
+            si_log!(debug, self, "SerialInterface::flush_output(): {:?}", e);
+            SIError::CannotWritePort
+        })
+    }
+
+    /// Clear the read buffer of the port before a read, unless a partial frame received by a previous
+    /// read is pending in self.read_buffer: the bytes on the port are then its continuation.
+    fn clear_idle_input(&mut self) -> Result<(), SIError> {
+        if self.read_buffer.is_empty() {
+            self.clear_read_buffer()
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Returns the frame accumulated in self.read_buffer, leaving it empty for the next frame.
+    fn take_read_buffer(&mut self) -> Vec<u8> {
+        std::mem::take(&mut self.read_buffer)
+    }
+
+    /// CLear data from the read buffer. Stops after self.max_drain bytes if data is still arriving, to
+    /// not loop forever on a continuously busy line.
+    fn clear_read_buffer(&mut self) -> Result<(), SIError> {
+        let port_open = self.port.is_some();
+        if port_open {
+            let mut buffer = [0u8; 24];
+            let mut drained = 0;
+            loop {
+                if drained >= self.max_drain {
+                    si_log!(
+                        warn,
+                        self,
+                        "SerialInterface::clear_read_buffer(): line continuously busy, {} bytes drained",
+                        drained
+                    );
+                    break;
+                }
+                let read = self.port.as_mut().unwrap().read(&mut buffer);
+                let ret = match read {
+                    Ok(r) => {
+                        si_log!(debug, self, "SerialInterface::buffer clear {:?}", buffer.to_vec());
+                        r
+                    }
+                    Err(e) => {
+                        if Self::is_read_timeout(&e) {
+                            0
+                        } else {
+                            return Err(SIError::CannotReadPort(Some(e.to_string())));
+                        }
+                    }
+                };
+                if ret == 0 {
+                    break;
+                };
+                drained += ret;
+            }
+            Ok(())
+        } else {
+            Err(SIError::PortNotOpened)
+        }
+    }
+
+    /// Baud rate mismatch heuristic: if more than half of the BAUD_CHECK_BYTES first bytes received
+    /// after the port is opened are 0x00 or 0xFF (typical of a wrong baud rate), a
+    /// SerialMessage::PossibleBaudMismatch is emitted with the next message.
+    fn check_baud(&mut self, byte: u8) {
+        if !self.detect_baud_mismatch || self.baud_check.0 >= BAUD_CHECK_BYTES {
+            return;
+        }
+        self.baud_check.0 += 1;
+        if byte == 0x00 || byte == 0xFF {
+            self.baud_check.1 += 1;
+        }
+        if self.baud_check.0 == BAUD_CHECK_BYTES && self.baud_check.1 * 2 > BAUD_CHECK_BYTES {
+            si_log!(warn, self, "SerialInterface::check_baud(): possible baud rate mismatch");
+            self.baud_mismatch = true;
+        }
+    }
+
+    /// Returns the sleep to apply after an idle poll, growing exponentially up to
+    /// self.max_idle_sleep once IDLE_SPINS idle polls are reached, see max_idle_sleep().
+    fn next_idle_sleep(&mut self) -> Duration {
+        if self.idle_spins < IDLE_SPINS {
+            self.idle_spins += 1;
+            return Duration::ZERO;
+        }
+        self.idle_sleep = (self.idle_sleep * 2)
+            .max(Duration::from_micros(1))
+            .min(self.max_idle_sleep);
+        self.idle_sleep
+    }
+
+    /// Sleep after an idle poll, see next_idle_sleep().
+    #[cfg(not(feature = "async-channel"))]
+    fn idle_backoff(&mut self) {
+        let idle_sleep = self.next_idle_sleep();
+        if !idle_sleep.is_zero() {
+            std::thread::sleep(idle_sleep);
+        }
+    }
+
+    /// Sleep after an idle poll, see next_idle_sleep().
+    #[cfg(feature = "async-channel")]
+    async fn idle_backoff(&mut self) {
+        let idle_sleep = self.next_idle_sleep();
+        if !idle_sleep.is_zero() {
+            sleep(idle_sleep).await;
+        }
+    }
+
+    /// Go back to busy polling after some activity (data or message), see max_idle_sleep().
+    fn reset_idle(&mut self) {
+        self.idle_spins = 0;
+        self.idle_sleep = Duration::ZERO;
+    }
+
+    /// Returns whether a read error only means that no data is available (read timeout of the port,
+    /// or WouldBlock for a non-blocking backend), as opposed to a real port error. Matched on the
+    /// error kind, the message is only checked as a fallback for drivers reporting a timeout with
+    /// another kind.
+    fn is_read_timeout(e: &std::io::Error) -> bool {
+        matches!(
+            e.kind(),
+            std::io::ErrorKind::TimedOut | std::io::ErrorKind::WouldBlock
+        ) || e.to_string() == "Operation timed out"
+    }
+
+    /// Read 1 bytes of data, return None if no data in buffer, sleeping on an idle line, see
+    /// max_idle_sleep().
+    #[cfg(not(feature = "async-channel"))]
+    fn read_byte(&mut self) -> Result<Option<u8>, SIError> {
+        self.read_byte_blocking()
+    }
+
+    /// Read 1 bytes of data, return None if no data in buffer, sleeping on an idle line, see
+    /// max_idle_sleep().
+    #[cfg(feature = "async-channel")]
+    async fn read_byte(&mut self) -> Result<Option<u8>, SIError> {
+        let data = self.try_read_byte()?;
+        if data.is_none() {
+            self.idle_backoff().await;
+        }
+        Ok(data)
+    }
+
+    /// Read 1 bytes of data for the read implementations shared by both builds (e.g.
+    /// read_stream()), which block the calling thread anyway: the idle backoff sleeps the thread.
+    fn read_byte_blocking(&mut self) -> Result<Option<u8>, SIError> {
+        let data = self.try_read_byte()?;
+        if data.is_none() {
+            let idle_sleep = self.next_idle_sleep();
+            if !idle_sleep.is_zero() {
+                std::thread::sleep(idle_sleep);
+            }
+        }
+        Ok(data)
+    }
+
+    /// Read 1 bytes of data, return None if no data in buffer, without any idle backoff.
+    fn try_read_byte(&mut self) -> Result<Option<u8>, SIError> {
+        let port_open = self.port.is_some();
+        if port_open {
+            let mut buffer = [0u8; 1];
+            let read = self.port.as_mut().unwrap().read(&mut buffer);
+            let l = match read {
+                Ok(r) => r,
+                Err(e) => {
+                    if Self::is_read_timeout(&e) {
+                        0
+                    } else {
+                        return Err(SIError::CannotReadPort(Some(e.to_string())));
+                    }
+                }
+            };
+            if l > 0 {
+                let rcv_time = Instant::now();
+                let from_last = self
+                    .last_byte_time
+                    .map(|last_byte| rcv_time.duration_since(last_byte));
+                si_log!(
+                    debug,
+                    self,
+                    "SerialInterface::read_byte({:?}, from last: {:?})",
+                    buffer,
+                    from_last
+                );
+                self.last_byte_time = Some(rcv_time);
+                self.check_baud(buffer[0]);
+                self.reset_idle();
+                Ok(Some(buffer[0]))
+            } else {
+                Ok(None)
+            }
+        } else {
+            Err(SIError::PortNotOpened)
+        }
+    }
+    
+    /// Generalist read() implementation, polling serial buffer, while not data been received on serial buffer,
+    /// checking received messages on self.receiver , if Send() received, return.
+    /// Returns SerialMessage::Receive() with the read data or SerialMessage::NoResponse on timeout, the
+    /// caller is in charge of forwarding it. NoResponse is only returned if a timeout is passed, an
+    /// empty buffer is never returned on silence.
+    /// When both size and timeout are passed, the frame length is known and silence is not applied once
+    /// the first byte is received, so an inter-byte gap larger than silence does not truncate the frame:
+    /// it completes at size bytes, or on timeout. Without timeout, silence is kept as the fallback end
+    /// of frame.
+    /// Error if none of size/silence/timeout passed.
+    #[cfg(not(feature = "async-channel"))]
+    #[allow(unused)]
+    fn read_until_size_or_silence_or_timeout_or_message(
+        &mut self,
+        size: Option<usize>,
+        silence: Option<&Duration>,
+        timeout: Option<&Duration>,
+    ) -> Result<Option<SerialMessage>, SIError> {
+        self.clear_idle_input()?;
+        let start = Instant::now();
+        let mut last_data = Instant::now();
+
+        if !(size.is_some() || timeout.is_some() || silence.is_some()) {
+            return Err(SIError::WrongReadArguments);
+        }
+
+        loop {
+            let result = self.read_byte()?;
+            // discard line idle noise before the first byte of the frame
+            let result = result.filter(|data| {
+                !(self.strip_leading_nulls && *data == 0 && self.read_buffer.is_empty())
+            });
+            // receive data
+            if let Some(data) = result {
+                // log::debug!("Start receive data: {}", data);
+                self.status = Status::Receipt;
+                self.read_buffer.push(data);
+                // reset the silence counter
+                last_data = Instant::now();
+
+                // check for size reach
+                if let Some(size) = &size {
+                    if &self.read_buffer.len() == size {
+                        self.status = Status::None;
+                        return Ok(Some(SerialMessage::Receive(self.take_read_buffer())));
+                    }
+                }
+                // Modbus ASCII frames end with CRLF
+                if self.frame_format == FrameFormat::Ascii && self.read_buffer.ends_with(b"\r\n") {
+                    self.status = Status::None;
+                    return Ok(Some(SerialMessage::Receive(self.take_read_buffer())));
+                }
+            } else if let Some(silence) = silence {
+                // we not yet start receive
+                if self.read_buffer.is_empty() {
+                    // Wait to receive first data
+                    let slice = self.idle_wait.min(self.read_timeout());
+                    if let Some(msg) = self.wait_message(slice)? {
+                        return Ok(Some(msg));
+                    }
+                    last_data = Instant::now();
+                } else {
+                    // receiving and waiting for silence
+                    let from_last_data = Instant::now().duration_since(last_data);
+                    // log::debug!("Duration from last data: {:?}", from_last_data);
+                    // silence is ignored until min_bytes_before_silence bytes received, and when
+                    // the frame is size-known and bounded by a timeout
+                    if from_last_data > *silence + self.silence_jitter_margin
+                        && self.read_buffer.len() >= self.min_bytes_before_silence
+                        && !(size.is_some() && timeout.is_some())
+                    {
+                        si_log!(debug, self, "silence reached, data received: {:?}", self.read_buffer);
+                        self.status = Status::None;
+                        return Ok(Some(SerialMessage::Receive(self.take_read_buffer())));
                     }
                 }
             }
             // check timeout
             if let Some(timeout) = timeout {
                 if &Instant::now().duration_since(start) > timeout {
-                    return if !buffer.is_empty() {
-                        let result = self
-                            .send_message(SerialMessage::Receive(buffer.clone()));
-                        self.status = Status::None;
-                        if let Err(e) = result {
-                            Err(e)
-                        } else {
-                            Ok(None)
-                        }
+                    self.status = Status::None;
+                    return if !self.read_buffer.is_empty() {
+                        Ok(Some(SerialMessage::Receive(self.take_read_buffer())))
                     } else {
-                        let result = self
-                            .send_message(SerialMessage::NoResponse);
-                        self.status = Status::None;
-                        if let Err(e) = result {
-                            Err(e)
-                        } else {
-                            Ok(None)
-                        }
-                    }
+                        Ok(Some(SerialMessage::NoResponse {
+                            request: Vec::new(),
+                            waited: Instant::now().duration_since(start),
+                        }))
+                    };
                 }
             }
         }
@@ -500,6 +2236,13 @@ impl SerialInterface {
 
     /// Generalist read() implementation, polling serial buffer, while not data been received on serial buffer,
     /// checking received messages on self.receiver , if Send() received, return.
+    /// Returns SerialMessage::Receive() with the read data or SerialMessage::NoResponse on timeout, the
+    /// caller is in charge of forwarding it. NoResponse is only returned if a timeout is passed, an
+    /// empty buffer is never returned on silence.
+    /// When both size and timeout are passed, the frame length is known and silence is not applied once
+    /// the first byte is received, so an inter-byte gap larger than silence does not truncate the frame:
+    /// it completes at size bytes, or on timeout. Without timeout, silence is kept as the fallback end
+    /// of frame.
     /// Error if none of size/silence/timeout passed.
     #[cfg(feature = "async-channel")]
     #[allow(unused)]
@@ -509,8 +2252,7 @@ impl SerialInterface {
         silence: Option<&Duration>,
         timeout: Option<&Duration>,
     ) -> Result<Option<SerialMessage>, SIError> {
-        self.clear_read_buffer()?;
-        let mut buffer: Vec<u8> = Vec::new();
+        self.clear_idle_input()?;
         let start = Instant::now();
         let mut last_data = Instant::now();
 
@@ -518,172 +2260,809 @@ impl SerialInterface {
             return Err(SIError::WrongReadArguments);
         }
 
-        loop {
-            let result = self.read_byte()?;
-            // receive data
-            if let Some(data) = result {
-                // log::debug!("Start receive data: {}", data);
-                self.status = Status::Receipt;
-                buffer.push(data);
-                // reset the silence counter
-                last_data = Instant::now();
+        loop {
+            let result = self.read_byte().await?;
+            // discard line idle noise before the first byte of the frame
+            let result = result.filter(|data| {
+                !(self.strip_leading_nulls && *data == 0 && self.read_buffer.is_empty())
+            });
+            // receive data
+            if let Some(data) = result {
+                // log::debug!("Start receive data: {}", data);
+                self.status = Status::Receipt;
+                self.read_buffer.push(data);
+                // reset the silence counter
+                last_data = Instant::now();
+
+                // check for size reach
+                if let Some(size) = &size {
+                    if &self.read_buffer.len() == size {
+                        self.status = Status::None;
+                        return Ok(Some(SerialMessage::Receive(self.take_read_buffer())));
+                    }
+                }
+                // Modbus ASCII frames end with CRLF
+                if self.frame_format == FrameFormat::Ascii && self.read_buffer.ends_with(b"\r\n") {
+                    self.status = Status::None;
+                    return Ok(Some(SerialMessage::Receive(self.take_read_buffer())));
+                }
+            } else if let Some(silence) = silence {
+                // we not yet start receive
+                if self.read_buffer.is_empty() {
+                    // Wait to receive first data
+                    let slice = self.idle_wait.min(self.read_timeout());
+                    if let Some(msg) = self.wait_message(slice).await? {
+                        return Ok(Some(msg));
+                    }
+                    last_data = Instant::now();
+                } else {
+                    // receiving and waiting for silence
+                    let from_last_data = Instant::now().duration_since(last_data);
+                    // log::debug!("Duration from last data: {:?}", from_last_data);
+                    // silence is ignored until min_bytes_before_silence bytes received, and when
+                    // the frame is size-known and bounded by a timeout
+                    if from_last_data > *silence + self.silence_jitter_margin
+                        && self.read_buffer.len() >= self.min_bytes_before_silence
+                        && !(size.is_some() && timeout.is_some())
+                    {
+                        si_log!(debug, self, "silence reached, data received: {:?}", self.read_buffer);
+                        self.status = Status::None;
+                        return Ok(Some(SerialMessage::Receive(self.take_read_buffer())));
+                    }
+                }
+            }
+            // check timeout
+            if let Some(timeout) = timeout {
+                if &Instant::now().duration_since(start) > timeout {
+                    self.status = Status::None;
+                    return if !self.read_buffer.is_empty() {
+                        Ok(Some(SerialMessage::Receive(self.take_read_buffer())))
+                    } else {
+                        Ok(Some(SerialMessage::NoResponse {
+                            request: Vec::new(),
+                            waited: Instant::now().duration_since(start),
+                        }))
+                    };
+                }
+            }
+        }
+    }
+
+    /// Computes the Modbus CRC16 of data, returned byte swapped: `crc16(data).to_be_bytes()` gives
+    /// the bytes in the standard Modbus RTU on-wire order (low byte first).
+    // the byte swap is kept as originally written, rather than clippy's rotate_right(8)
+    #[allow(clippy::manual_rotate)]
+    pub fn crc16(data: &[u8]) -> u16 {
+        let crc = Self::crc16_raw(data);
+        crc << 8 | crc >> 8
+    }
+
+    /// Computes the Modbus ASCII LRC of data: the two's complement of the sum of the bytes (mod 256),
+    /// computed on the binary bytes (address, function code and data), before hex encoding.
+    pub fn lrc(data: &[u8]) -> u8 {
+        data.iter()
+            .fold(0u8, |acc, b| acc.wrapping_add(*b))
+            .wrapping_neg()
+    }
+
+    /// Append the CRC16 to a Send payload in Master/MasterStream modes if auto_crc is enabled and the
+    /// payload does not already end with a valid CRC.
+    fn with_auto_crc(&self, mut data: Vec<u8>) -> Vec<u8> {
+        if self.auto_crc
+            && matches!(self.mode, Mode::Master | Mode::MasterStream)
+            && self.frame_format == FrameFormat::Rtu
+            && !Self::check_crc(&data, self.crc_byte_order)
+        {
+            Self::append_crc(&mut data, self.crc_byte_order);
+        }
+        data
+    }
+
+    /// Computes the CRC16 of data and returns the 2 bytes to append to the frame in the given order.
+    pub fn crc_bytes(data: &[u8], order: CrcByteOrder) -> [u8; 2] {
+        let crc = Self::crc16_raw(data);
+        match order {
+            CrcByteOrder::LittleEndian => crc.to_le_bytes(),
+            CrcByteOrder::BigEndian => crc.to_be_bytes(),
+        }
+    }
+
+    /// Appends the CRC16 of frame to itself, in the given order.
+    pub fn append_crc(frame: &mut Vec<u8>, order: CrcByteOrder) {
+        let crc = Self::crc_bytes(frame, order);
+        frame.extend_from_slice(&crc);
+    }
+
+    /// Computes the Modbus CRC16 (polynomial 0xA001, init 0xFFFF) of data.
+    fn crc16_raw(data: &[u8]) -> u16 {
+        let mut crc = 0xFFFF;
+        for x in data {
+            crc ^= u16::from(*x);
+            for _ in 0..8 {
+                // if we followed clippy's suggestion to move out the crc >>= 1, the condition may not be met any more
+                // the recommended action therefore makes no sense and it is better to allow this lint
+                #[allow(clippy::branches_sharing_code)]
+                if (crc & 0x0001) != 0 {
+                    crc >>= 1;
+                    crc ^= 0xA001;
+                } else {
+                    crc >>= 1;
+                }
+            }
+        }
+        crc
+    }
+
+    fn check_crc(frame: &[u8], order: CrcByteOrder) -> bool {
+        // log::debug!("check_crc({:?})", frame);
+        if frame.len() >= 4 {
+            let expected_crc = Self::crc_bytes(&frame[..frame.len()-2], order);
+            // log::debug!("expected crc: {:?}, end_of_frame: {:?}", &expected_crc, &frame[frame.len()-2..]);
+            expected_crc == frame[frame.len()-2..]
+        } else {
+            false
+        }
+
+    }
+
+    /// Returns whether the frame ends with a valid checksum of the given algorithm.
+    fn check_checksum(frame: &[u8], algo: ChecksumAlgo, order: CrcByteOrder) -> bool {
+        let Some((&last, data)) = frame.split_last() else {
+            return false;
+        };
+        match algo {
+            ChecksumAlgo::CrcModbus => Self::check_crc(frame, order),
+            ChecksumAlgo::CrcCcitt => {
+                frame.len() > 2
+                    && Self::crc16_ccitt(&frame[..frame.len() - 2]).to_be_bytes()
+                        == frame[frame.len() - 2..]
+            }
+            ChecksumAlgo::Bcc => data.iter().fold(0u8, |acc, b| acc ^ b) == last,
+            ChecksumAlgo::Lrc => Self::lrc(data) == last,
+        }
+    }
+
+    /// CRC-16/CCITT-FALSE (polynomial 0x1021, initial value 0xFFFF).
+    fn crc16_ccitt(data: &[u8]) -> u16 {
+        let mut crc: u16 = 0xFFFF;
+        for byte in data {
+            crc ^= (*byte as u16) << 8;
+            for _ in 0..8 {
+                if crc & 0x8000 != 0 {
+                    crc = (crc << 1) ^ 0x1021;
+                } else {
+                    crc <<= 1;
+                }
+            }
+        }
+        crc
+    }
+
+    /// Search the smallest frame in buffer with a valid checksum for one of the `validators`.
+    /// Frames shorter than `min_size` bytes are not considered.
+    /// When several valid frames of the same (smallest) length are found, the selection is
+    /// deterministic: with ScanPreference::ForwardFirst the frame nearest to the start of the buffer
+    /// wins, with ScanPreference::ReverseFirst the frame nearest to the end of the buffer wins.
+    /// At a given position, `validators` are tried in order.
+    /// Returns the start index and the length of the frame in buffer, and the algorithm that matched,
+    /// so the caller knows both the leading garbage and the trailing bytes (e.g. the beginning of the
+    /// next frame) to keep.
+    fn try_decode_buffer(
+        buffer: &[u8],
+        order: CrcByteOrder,
+        scan: ScanPreference,
+        validators: &[ChecksumAlgo],
+        min_size: usize,
+    ) -> Option<(usize, usize, ChecksumAlgo)> {
+        let mut window_size = min_size.max(1);
+
+        while window_size <= buffer.len() {
+            let last = buffer.len() - window_size;
+            for i in 0..=last {
+                // positions are scanned in a single direction (covering the whole buffer), so the
+                // preferred match can't be shadowed by one found from the other end
+                let j = match scan {
+                    ScanPreference::ForwardFirst => i,
+                    ScanPreference::ReverseFirst => last - i,
+                };
+                for algo in validators {
+                    if Self::check_checksum(&buffer[j..j + window_size], *algo, order) {
+                        return Some((j, window_size, *algo));
+                    }
+                }
+            }
+            window_size += 1;
+        }
+        None
+    }
+
+
+    /// Modbus ASCII counterpart of try_decode_buffer(): search a frame from a `:` to the next CRLF
+    /// with a valid LRC, the first one from the start of the buffer with ScanPreference::ForwardFirst,
+    /// from the end with ScanPreference::ReverseFirst.
+    /// Returns the start index and the length of the frame in buffer, and ChecksumAlgo::Lrc.
+    fn try_decode_ascii(
+        buffer: &[u8],
+        scan: ScanPreference,
+    ) -> Option<(usize, usize, ChecksumAlgo)> {
+        let starts = buffer.iter().enumerate().filter(|(_, b)| **b == b':').map(|(i, _)| i);
+        let starts: Vec<usize> = match scan {
+            ScanPreference::ForwardFirst => starts.collect(),
+            ScanPreference::ReverseFirst => starts.rev().collect(),
+        };
+        starts.into_iter().find_map(|start| {
+            let end = start + buffer[start..].windows(2).position(|w| w == b"\r\n")? + 2;
+            Self::check_ascii(&buffer[start..end]).then_some((start, end - start, ChecksumAlgo::Lrc))
+        })
+    }
+
+    /// Direct-call read for an external event loop: reads the bytes currently available on the port
+    /// without waiting for more, and returns the frame if it's complete, i.e. no byte has been received
+    /// for `silence` since its last byte. Otherwise returns None immediately, the bytes of an incomplete
+    /// frame being kept for the next call (or the next read, see FlushInput to discard them): it must
+    /// be called again (at least once per silence) to complete the frame.
+    /// Like read_frame_into(), self.receiver is not polled and nothing is sent on self.sender. The
+    /// idle backoff (see max_idle_sleep()) is not applied.
+    pub fn poll_frame(&mut self, silence: &Duration) -> Result<Option<Vec<u8>>, SIError> {
+        self.reset_idle();
+        while let Some(data) = self.try_read_byte()? {
+            self.read_buffer.push(data);
+        }
+        let from_last_data = self
+            .last_byte_time
+            .map(|last_byte| last_byte.elapsed())
+            .unwrap_or_default();
+        if !self.read_buffer.is_empty() && from_last_data > *silence + self.silence_jitter_margin {
+            Ok(Some(std::mem::take(&mut self.read_buffer)))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Direct-call read: read a silence delimited frame into a caller-provided buffer instead of
+    /// allocating a new one for each frame. `buf` is cleared then filled with the received bytes, so the
+    /// same buffer (e.g. created once with `Vec::with_capacity(256)`) can be reused across calls in a
+    /// read loop without any allocation once its capacity is reached.
+    /// Unlike the channel based read() implementations, self.receiver is not polled and nothing is
+    /// sent on self.sender, and the read buffer is not cleared before reading: a partial frame kept by
+    /// poll_frame() is moved to `buf` and completed by the bytes read.
+    /// Returns Ok(true) if a frame has been read, Ok(false) if timeout is reached without any data.
+    pub fn read_frame_into(
+        &mut self,
+        buf: &mut Vec<u8>,
+        silence: &Duration,
+        timeout: &Duration,
+    ) -> Result<bool, SIError> {
+        buf.clear();
+        // moved without reallocating buf, self.read_buffer keeps its capacity
+        buf.append(&mut self.read_buffer);
+        let start = Instant::now();
+        let mut last_data = match self.last_byte_time {
+            Some(last_byte) if !buf.is_empty() => last_byte,
+            _ => Instant::now(),
+        };
+
+        loop {
+            if let Some(data) = self.read_byte_blocking()? {
+                self.status = Status::Receipt;
+                buf.push(data);
+                last_data = Instant::now();
+            } else if !buf.is_empty()
+                && Instant::now().duration_since(last_data) > *silence + self.silence_jitter_margin
+            {
+                self.status = Status::None;
+                return Ok(true);
+            }
+            if &Instant::now().duration_since(start) > timeout {
+                self.status = Status::None;
+                return Ok(!buf.is_empty());
+            }
+        }
+    }
+
+    /// Direct-call bulk polling: sequentially write each request and read its (silence delimited)
+    /// response, waiting self.silence between transactions as inter-frame gap.
+    /// As read_frame_into(), self.receiver is not polled and nothing is sent on self.sender.
+    /// Returns the result of each transaction keyed by the caller's tag, SIError::NoResponse if no
+    /// data received before timeout.
+    #[cfg(not(feature = "async-channel"))]
+    pub fn poll_many(
+        &mut self,
+        requests: Vec<(u64, Vec<u8>)>,
+        timeout: Duration,
+    ) -> HashMap<u64, Result<Vec<u8>, SIError>> {
+        let gap = self.silence.unwrap_or_default();
+        let mut results = HashMap::new();
+        for (i, (tag, request)) in requests.into_iter().enumerate() {
+            if i > 0 {
+                std::thread::sleep(gap);
+            }
+            results.insert(tag, self.poll_one(&request, &gap, &timeout));
+        }
+        results
+    }
+
+    /// Direct-call bulk polling: sequentially write each request and read its (silence delimited)
+    /// response, waiting self.silence between transactions as inter-frame gap.
+    /// As read_frame_into(), self.receiver is not polled and nothing is sent on self.sender.
+    /// Returns the result of each transaction keyed by the caller's tag, SIError::NoResponse if no
+    /// data received before timeout.
+    #[cfg(feature = "async-channel")]
+    pub async fn poll_many(
+        &mut self,
+        requests: Vec<(u64, Vec<u8>)>,
+        timeout: Duration,
+    ) -> HashMap<u64, Result<Vec<u8>, SIError>> {
+        let gap = self.silence.unwrap_or_default();
+        let mut results = HashMap::new();
+        for (i, (tag, request)) in requests.into_iter().enumerate() {
+            if i > 0 {
+                sleep(gap).await;
+            }
+            results.insert(tag, self.poll_one(&request, &gap, &timeout).await);
+        }
+        results
+    }
+
+    /// Direct-call transaction, to use the interface as a plain library without the channels: open the
+    /// port if not yet opened, write the request and read its (silence delimited) response as
+    /// poll_many() does. The channel loop (start()) is not involved.
+    /// Returns Ok(Some(response)) with the received frame, Ok(None) if no data received before timeout.
+    #[cfg(not(feature = "async-channel"))]
+    pub fn transaction(
+        &mut self,
+        data: Vec<u8>,
+        timeout: &Duration,
+    ) -> Result<Option<Vec<u8>>, SIError> {
+        self.transact(&data, timeout)
+    }
+
+    /// Direct-call transaction, to use the interface as a plain library without the channels: open the
+    /// port if not yet opened, write the request and read its (silence delimited) response as
+    /// poll_many() does. The channel loop (start()) is not involved.
+    /// Returns Ok(Some(response)) with the received frame, Ok(None) if no data received before timeout.
+    #[cfg(feature = "async-channel")]
+    pub async fn transaction(
+        &mut self,
+        data: Vec<u8>,
+        timeout: &Duration,
+    ) -> Result<Option<Vec<u8>>, SIError> {
+        self.transact(&data, timeout).await
+    }
+
+    /// Direct-call write transaction (Modbus Write Single Coil/Register 0x05/0x06, Write Multiple
+    /// Coils/Registers 0x0F/0x10): write the request (CRC included) and read its response as
+    /// poll_many() does.
+    /// Returns Ok(true) if the response is CRC-valid and echoes the request header (slave id, function
+    /// code, address and value/count), Ok(false) on mismatch or exception response,
+    /// SIError::NoResponse if no data received before timeout.
+    #[cfg(not(feature = "async-channel"))]
+    pub fn write_confirmed(&mut self, data: Vec<u8>, timeout: Duration) -> Result<bool, SIError> {
+        self.confirm_write(&data, &timeout)
+    }
+
+    /// Direct-call write transaction (Modbus Write Single Coil/Register 0x05/0x06, Write Multiple
+    /// Coils/Registers 0x0F/0x10): write the request (CRC included) and read its response as
+    /// poll_many() does.
+    /// Returns Ok(true) if the response is CRC-valid and echoes the request header (slave id, function
+    /// code, address and value/count), Ok(false) on mismatch or exception response,
+    /// SIError::NoResponse if no data received before timeout.
+    #[cfg(feature = "async-channel")]
+    pub async fn write_confirmed(
+        &mut self,
+        data: Vec<u8>,
+        timeout: Duration,
+    ) -> Result<bool, SIError> {
+        self.confirm_write(&data, &timeout).await
+    }
+
+    /// Direct-call Read Holding Registers (0x03) transaction: write the request to the slave `id`
+    /// and decode its response as decode_response() does, i.e. in the configured register order.
+    /// Returns the decoded response (ModbusResponse::Exception for an exception response),
+    /// SIError::NoResponse if no data received before timeout.
+    #[cfg(not(feature = "async-channel"))]
+    pub fn query_registers(
+        &mut self,
+        id: u8,
+        start: u16,
+        count: u16,
+        timeout: Duration,
+    ) -> Result<modbus::ModbusResponse, SIError> {
+        let request = modbus::build_read_holding_registers(id, start, count);
+        let response = self.transact(&request, &timeout)?.ok_or(SIError::NoResponse)?;
+        self.decode_response(request[1], &response)
+    }
+
+    /// Direct-call Read Holding Registers (0x03) transaction: write the request to the slave `id`
+    /// and decode its response as decode_response() does, i.e. in the configured register order.
+    /// Returns the decoded response (ModbusResponse::Exception for an exception response),
+    /// SIError::NoResponse if no data received before timeout.
+    #[cfg(feature = "async-channel")]
+    pub async fn query_registers(
+        &mut self,
+        id: u8,
+        start: u16,
+        count: u16,
+        timeout: Duration,
+    ) -> Result<modbus::ModbusResponse, SIError> {
+        let request = modbus::build_read_holding_registers(id, start, count);
+        let response = self.transact(&request, &timeout).await?.ok_or(SIError::NoResponse)?;
+        self.decode_response(request[1], &response)
+    }
+
+    /// Direct-call: wait for the line to be idle, reading and discarding bytes until silence elapses
+    /// with no data (returns Ok(true)) or timeout is reached (returns Ok(false)), e.g. before starting
+    /// a poll cycle on a shared bus.
+    #[cfg(not(feature = "async-channel"))]
+    pub fn wait_idle(&mut self, timeout: Duration) -> Result<bool, SIError> {
+        self.idle(&timeout)
+    }
+
+    /// Direct-call: wait for the line to be idle, reading and discarding bytes until silence elapses
+    /// with no data (returns Ok(true)) or timeout is reached (returns Ok(false)), e.g. before starting
+    /// a poll cycle on a shared bus.
+    #[cfg(feature = "async-channel")]
+    pub async fn wait_idle(&mut self, timeout: Duration) -> Result<bool, SIError> {
+        self.idle(&timeout).await
+    }
+
+    /// Implementation of wait_idle().
+    #[cfg(not(feature = "async-channel"))]
+    fn idle(&mut self, timeout: &Duration) -> Result<bool, SIError> {
+        let silence = self.silence.ok_or(SIError::SilenceMissing)?;
+        let start = Instant::now();
+        let mut last_data = Instant::now();
+        loop {
+            if self.read_byte()?.is_some() {
+                last_data = self.last_byte_time.unwrap_or_else(Instant::now);
+            } else if Instant::now().duration_since(last_data) > silence {
+                return Ok(true);
+            }
+            if &Instant::now().duration_since(start) > timeout {
+                return Ok(false);
+            }
+        }
+    }
+
+    /// Implementation of wait_idle(). While the line is quiet, the task sleeps until silence would
+    /// elapse instead of polling the port, the bytes received meanwhile being read on wake up.
+    #[cfg(feature = "async-channel")]
+    async fn idle(&mut self, timeout: &Duration) -> Result<bool, SIError> {
+        let silence = self.silence.ok_or(SIError::SilenceMissing)?;
+        let start = Instant::now();
+        let mut last_data = Instant::now();
+        loop {
+            let data = self.try_read_byte()?.is_some();
+            if data {
+                last_data = self.last_byte_time.unwrap_or_else(Instant::now);
+            } else if Instant::now().duration_since(last_data) > silence {
+                return Ok(true);
+            }
+            let elapsed = Instant::now().duration_since(start);
+            if &elapsed > timeout {
+                return Ok(false);
+            }
+            if data {
+                tokio::task::yield_now().await;
+            } else {
+                // the task sleeps instead of polling the port
+                let quiet = Instant::now().duration_since(last_data);
+                let wait = silence
+                    .saturating_sub(quiet)
+                    .min(timeout.saturating_sub(elapsed))
+                    .max(Duration::from_millis(1));
+                sleep(wait).await;
+            }
+        }
+    }
+
+    /// Transaction of write_confirmed().
+    #[cfg(not(feature = "async-channel"))]
+    fn confirm_write(&mut self, request: &[u8], timeout: &Duration) -> Result<bool, SIError> {
+        if request.len() < 8 {
+            return Err(SIError::InvalidFrame);
+        }
+        let silence = self.silence.unwrap_or_default();
+        let response = self.poll_one(request, &silence, timeout)?;
+        Ok(Self::is_write_ack(request, &response))
+    }
+
+    /// Transaction of write_confirmed().
+    #[cfg(feature = "async-channel")]
+    async fn confirm_write(&mut self, request: &[u8], timeout: &Duration) -> Result<bool, SIError> {
+        if request.len() < 8 {
+            return Err(SIError::InvalidFrame);
+        }
+        let silence = self.silence.unwrap_or_default();
+        let response = self.poll_one(request, &silence, timeout).await?;
+        Ok(Self::is_write_ack(request, &response))
+    }
 
-                // check for size reach
-                if let Some(size) = &size {
-                    if &buffer.len() == size {
-                        let result = self
-                            .send_message(SerialMessage::Receive(buffer.clone()))
-                            .await;
-                        self.status = Status::None;
-                        return if let Err(e) = result {
-                            Err(e)
-                        } else {
-                            Ok(None)
-                        };
-                    }
-                }
-            } else if let Some(silence) = silence {
-                // we not yet start receive
-                if buffer.is_empty() {
-                    // Wait to receive first data
-                    if let Some(msg) = self.read_message().await? {
-                        return Ok(Some(msg));
-                    }
-                    last_data = Instant::now();
-                } else {
-                    // receiving and waiting for silence
-                    let from_last_data = &Instant::now().duration_since(last_data);
-                    // log::debug!("Duration from last data: {:?}", from_last_data);
-                    if from_last_data > silence {
-                        log::debug!("silence reached, data received: {:?}", buffer.to_vec());
-                        let result = self
-                            .send_message(SerialMessage::Receive(buffer.clone()))
-                            .await;
-                        self.status = Status::None;
-                        return if let Err(e) = result {
-                            Err(e)
-                        } else {
-                            Ok(None)
-                        };
-                    }
-                }
-            }
-            // check timeout
-            if let Some(timeout) = timeout {
-                if &Instant::now().duration_since(start) > timeout {
-                    return if !buffer.is_empty() {
-                        let result = self
-                            .send_message(SerialMessage::Receive(buffer.clone()))
-                            .await;
-                        self.status = Status::None;
-                        if let Err(e) = result {
-                            Err(e)
-                        } else {
-                            Ok(None)
-                        }
-                    } else {
-                        let result = self
-                            .send_message(SerialMessage::NoResponse)
-                            .await;
-                        self.status = Status::None;
-                        if let Err(e) = result {
-                            Err(e)
-                        } else {
-                            Ok(None)
-                        }
-                    }
-                }
+    /// Returns whether a write response is CRC-valid and echoes the request header, see
+    /// write_confirmed().
+    fn is_write_ack(request: &[u8], response: &[u8]) -> bool {
+        if response.first() != request.first() {
+            return false;
+        }
+        let expected = (
+            u16::from_be_bytes([request[2], request[3]]),
+            u16::from_be_bytes([request[4], request[5]]),
+        );
+        match modbus::decode_response(request[1], response) {
+            Ok(modbus::ModbusResponse::WriteAck { addr, value }) => (addr, value) == expected,
+            Ok(modbus::ModbusResponse::WriteMultipleAck { addr, count }) => {
+                (addr, count) == expected
             }
+            _ => false,
         }
     }
 
-    pub fn crc16(data: &[u8]) -> u16 {
-        let mut crc = 0xFFFF;
-        for x in data {
-            crc ^= u16::from(*x);
-            for _ in 0..8 {
-                // if we followed clippy's suggestion to move out the crc >>= 1, the condition may not be met any more
-                // the recommended action therefore makes no sense and it is better to allow this lint
-                #[allow(clippy::branches_sharing_code)]
-                if (crc & 0x0001) != 0 {
-                    crc >>= 1;
-                    crc ^= 0xA001;
-                } else {
-                    crc >>= 1;
-                }
-            }
+    /// Implementation of transaction().
+    #[cfg(not(feature = "async-channel"))]
+    fn transact(&mut self, request: &[u8], timeout: &Duration) -> Result<Option<Vec<u8>>, SIError> {
+        if self.port.is_none() {
+            self.open()?;
+        }
+        let silence = self.silence.ok_or(SIError::SilenceMissing)?;
+        match self.poll_one(request, &silence, timeout) {
+            Ok(response) => Ok(Some(response)),
+            Err(SIError::NoResponse) => Ok(None),
+            Err(e) => Err(e),
         }
-        crc << 8 | crc >> 8
     }
 
-    fn check_crc(frame: &[u8]) -> bool {
-        // log::debug!("check_crc({:?})", frame);
-        if frame.len() > 4 {
-            let crc = Self::crc16(&frame[..frame.len()-2]);
-            let expected_crc = [((crc & 0xff00) >> 8) as u8, (crc & 0x00ff) as u8];
-            // log::debug!("expected crc: {:?}, end_of_frame: {:?}", &expected_crc, &frame[frame.len()-2..]);
-            expected_crc == frame[frame.len()-2..]
+    /// Implementation of transaction().
+    #[cfg(feature = "async-channel")]
+    async fn transact(
+        &mut self,
+        request: &[u8],
+        timeout: &Duration,
+    ) -> Result<Option<Vec<u8>>, SIError> {
+        if self.port.is_none() {
+            self.open()?;
+        }
+        let silence = self.silence.ok_or(SIError::SilenceMissing)?;
+        match self.poll_one(request, &silence, timeout).await {
+            Ok(response) => Ok(Some(response)),
+            Err(SIError::NoResponse) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Single transaction of poll_many().
+    #[cfg(not(feature = "async-channel"))]
+    fn poll_one(
+        &mut self,
+        request: &[u8],
+        silence: &Duration,
+        timeout: &Duration,
+    ) -> Result<Vec<u8>, SIError> {
+        if request.len() > self.max_tx_frame {
+            return Err(SIError::FrameTooLarge);
+        }
+        self.clear_read_buffer()?;
+        self.write_port(request)?;
+        let mut response = Vec::new();
+        if self.read_frame_into(&mut response, silence, timeout)? {
+            Ok(response)
         } else {
-            false
+            Err(SIError::NoResponse)
         }
+    }
 
+    /// Single transaction of poll_many().
+    #[cfg(feature = "async-channel")]
+    async fn poll_one(
+        &mut self,
+        request: &[u8],
+        silence: &Duration,
+        timeout: &Duration,
+    ) -> Result<Vec<u8>, SIError> {
+        if request.len() > self.max_tx_frame {
+            return Err(SIError::FrameTooLarge);
+        }
+        self.clear_read_buffer()?;
+        self.write_port(request).await?;
+        let mut response = Vec::new();
+        if self.read_frame_into(&mut response, silence, timeout)? {
+            Ok(response)
+        } else {
+            Err(SIError::NoResponse)
+        }
     }
 
-    fn try_decode_buffer(buffer: Vec<u8>) -> Option<Vec<u8>> {
-        let mut window_size = 5;
+    /// Format bytes as an hexadecimal string, used for logging.
+    fn to_hex(data: &[u8]) -> String {
+        data.iter()
+            .map(|b| format!("{:02X}", b))
+            .collect::<Vec<String>>()
+            .join(" ")
+    }
 
-        while window_size <= buffer.len() {
-            for i in 0..=buffer.len() - window_size {
-                // Forward direction
-                if Self::check_crc(&buffer[i..i + window_size]) {
-                    return Some(buffer[i..i + window_size].to_vec());
-                }
-                
-                if buffer.len() == window_size {
-                    return None;
-                }
-                // Reverse direction
-                let j = buffer.len() - i - window_size;
-                if Self::check_crc(&buffer[j..j + window_size]) {
-                    return Some(buffer[j..j + window_size].to_vec());
-                }
+    /// Fill the unanswered request in a SerialMessage::NoResponse, other messages are returned as is.
+    fn with_request(msg: SerialMessage, request: &[u8]) -> SerialMessage {
+        if let SerialMessage::NoResponse { waited, .. } = msg {
+            SerialMessage::NoResponse {
+                request: request.to_vec(),
+                waited,
             }
-            window_size += 1;
+        } else {
+            msg
         }
-        None
     }
 
+    /// Returns the name prefixing log lines, see name().
+    fn log_name(&self) -> Cow<'_, str> {
+        match (&self.name, &self.path) {
+            (Some(name), _) | (None, Some(name)) => Cow::Borrowed(name),
+            (None, None) => Cow::Owned(format!("#{}", self.id)),
+        }
+    }
+
+    /// Log a whole master transaction (request, response and elapsed time) in a single line.
+    fn log_transaction(&self, request: &[u8], response: &SerialMessage, start: Instant) {
+        let response = if let SerialMessage::Receive(data) = response {
+            format!("[{}]", Self::to_hex(data))
+        } else {
+            "NoResponse".to_string()
+        };
+        si_log!(
+            info,
+            self,
+            "SerialInterface::transaction(request: [{}], response: {}, elapsed: {:?})",
+            Self::to_hex(request),
+            response,
+            start.elapsed()
+        );
+    }
 
     /// Stream read() implementation, buffering the read data, and `screening` until we find 
-    /// a frame w/ valid CRC
+    /// a frame w/ valid CRC. If report_discarded is enabled, the bytes preceding the frame are kept in
+    /// self.discarded.
+    /// The bytes received after the frame are kept in self.read_buffer and seed the next call (in this
+    /// case the read buffer of the port is not cleared), so a frame arriving right behind the previous
+    /// one is not lost.
+    /// The residual bytes are dropped on timeout, and kept in self.crc_error if report_crc_errors is
+    /// enabled.
     #[allow(unused)]
     fn read_stream(&mut self, timeout: &Duration) -> Result<SerialMessage, SIError> {
-        self.clear_read_buffer()?;
-        let mut buffer: Vec<u8> = Vec::new();
+        let mut buffer = self.take_read_buffer();
+        if buffer.is_empty() {
+            self.clear_read_buffer()?;
+        }
+        // the residual of the previous call can already contain a full frame
+        let mut received = !buffer.is_empty();
         let start = Instant::now();
 
         loop {
-            let result = self.read_byte()?;
-            // receive data
-            if let Some(data) = result {
+            if received {
+                received = false;
+                let validators: &[ChecksumAlgo] = if self.multi_checksum {
+                    &ChecksumAlgo::ALL
+                } else {
+                    &[ChecksumAlgo::CrcModbus]
+                };
+                let decoded = match self.frame_format {
+                    FrameFormat::Rtu => Self::try_decode_buffer(
+                        &buffer,
+                        self.crc_byte_order,
+                        self.scan_preference,
+                        validators,
+                        self.min_frame_size,
+                    ),
+                    FrameFormat::Ascii => Self::try_decode_ascii(&buffer, self.scan_preference),
+                };
+                // log::debug!("try_decode_buffer({:?}) = {:?}", &buffer, decoded);
+                if let Some((position, len, algo)) = decoded {
+                    if self.report_discarded && position > 0 {
+                        self.discarded = Some(buffer[..position].to_vec());
+                    }
+                    // keep the bytes following the frame for the next read
+                    self.read_buffer = buffer.split_off(position + len);
+                    let data = buffer.split_off(position);
+                    return Ok(if self.multi_checksum {
+                        SerialMessage::ReceiveWithChecksum { data, algo }
+                    } else {
+                        SerialMessage::Receive(data)
+                    });
+                }
+            }
+            // read the whole burst already available (bounded, so a flood can't delay the timeout
+            // check) before decoding: the bytes following the frame are kept for the next call
+            // instead of being dropped by its clear of the read buffer
+            let max = self.max_buffer_size.max(1);
+            let mut read = 0;
+            while read < max {
+                let Some(data) = self.read_byte_blocking()? else {
+                    break;
+                };
+                read += 1;
+                // discard line idle noise before the first byte of the frame
+                if self.strip_leading_nulls && data == 0 && buffer.is_empty() {
+                    continue;
+                }
                 // log::debug!("Start receive data: {}", data);
                 self.status = Status::Receipt;
-                buffer.push(data);
-                let decoded = Self::try_decode_buffer(buffer.clone());
-                // log::debug!("try_decode_buffer({:?}) = {:?}", &buffer, decoded);
-                if let Some(frame) = decoded {
-                    return Ok(SerialMessage::Receive(frame));
+                if buffer.len() >= max {
+                    // sliding window: drop the oldest bytes
+                    let dropped: Vec<u8> = buffer.drain(..buffer.len() + 1 - max).collect();
+                    si_log!(
+                        warn,
+                        self,
+                        "SerialInterface::read_stream(): buffer full, drop {:?}",
+                        dropped
+                    );
                 }
+                buffer.push(data);
+                received = true;
             }
             // check timeout
             if &Instant::now().duration_since(start) > timeout {
-                return Ok(SerialMessage::NoResponse);
+                if self.report_crc_errors && !buffer.is_empty() {
+                    self.crc_error = Some(buffer);
+                }
+                return Ok(SerialMessage::NoResponse {
+                    request: Vec::new(),
+                    waited: Instant::now().duration_since(start),
+                });
             }
             
         }
     }
         
     
+    /// Length-prefixed read() implementation: reads `header_len` bytes, then as many bytes as the value of
+    /// the length field at `length_field_index` in the header, plus `trailer_len` bytes (e.g. CRC).
+    /// For a Modbus read registers response: header_len = 3, length_field_index = 2, trailer_len = 2.
+    /// Returns SerialMessage::Receive(), or SerialMessage::NoResponse if timeout is reached before
+    /// receiving any data (the partial data is returned otherwise).
+    fn read_with_length_field(
+        &mut self,
+        header_len: usize,
+        length_field_index: usize,
+        trailer_len: usize,
+        timeout: &Duration,
+    ) -> Result<SerialMessage, SIError> {
+        if length_field_index >= header_len {
+            return Err(SIError::WrongReadArguments);
+        }
+        self.clear_idle_input()?;
+        let start = Instant::now();
+
+        loop {
+            if let Some(data) = self.read_byte_blocking()? {
+                self.status = Status::Receipt;
+                self.read_buffer.push(data);
+            }
+            // a partial frame kept from a previous call can already hold the header
+            let expected = match self.read_buffer.get(length_field_index) {
+                Some(length) if self.read_buffer.len() >= header_len => {
+                    header_len + *length as usize + trailer_len
+                }
+                _ => header_len,
+            };
+            if self.read_buffer.len() >= expected {
+                self.status = Status::None;
+                let mut frame = self.take_read_buffer();
+                // keep the bytes following the frame for the next read
+                self.read_buffer = frame.split_off(expected);
+                return Ok(SerialMessage::Receive(frame));
+            }
+            // check timeout
+            if &Instant::now().duration_since(start) > timeout {
+                self.status = Status::None;
+                return if !self.read_buffer.is_empty() {
+                    Ok(SerialMessage::Receive(self.take_read_buffer()))
+                } else {
+                    Ok(SerialMessage::NoResponse {
+                        request: Vec::new(),
+                        waited: Instant::now().duration_since(start),
+                    })
+                };
+            }
+        }
+    }
+
     /// Read <s> bytes of data, blocking until get the <s> number of bytes.
     #[cfg(not(feature = "async-channel"))]
     #[allow(unused)]
@@ -769,297 +3148,1355 @@ impl SerialInterface {
     }
 
     
-    /// Open the serial port.
-    pub fn open(&mut self) -> Result<(), SIError> {
-        if self.port.is_some() || self.mode != Mode::Stop {
-            Err(SIError::PortAlreadyOpen)
-            // TODO => SlaveModeNeedModbusID move this Mode::Slave
-            // } else if self.modbus_id.is_none() {
-            //     Err(SIError::SlaveModeNeedModbusID)
-            // } else if self.mode != Mode::Master && self.silence.is_none() {
-            //     Err(SIError::SilenceMissing)
-        } else if self.path.is_none() {
-            Err(SIError::PathMissing)
+    /// Open the serial port.
+    pub fn open(&mut self) -> Result<(), SIError> {
+        if self.port.is_some() || self.mode != Mode::Stop {
+            Err(SIError::PortAlreadyOpen)
+            // TODO => SlaveModeNeedModbusID move this Mode::Slave
+            // } else if self.modbus_id.is_none() {
+            //     Err(SIError::SlaveModeNeedModbusID)
+            // } else if self.mode != Mode::Master && self.silence.is_none() {
+            //     Err(SIError::SilenceMissing)
+        } else {
+            self.open_port()
+        }
+    }
+
+    /// Open the serial port from self.path with the configured settings (or the TCP connection if
+    /// self.tcp_host is set), regardless of the mode.
+    fn open_port(&mut self) -> Result<(), SIError> {
+        if let Some(host) = self.tcp_host.clone() {
+            return self.open_tcp(&host);
+        }
+        if self.path.is_none() {
+            return Err(SIError::PathMissing);
+        }
+        let path = self.path.clone().unwrap();
+        let mut port = serial::open(&path).map_err(Self::open_error)?;
+        #[cfg(unix)]
+        if !self.exclusive {
+            use std::os::unix::io::AsRawFd;
+            // SAFETY: the fd is owned by port and valid until it's dropped.
+            if unsafe { libc::ioctl(port.as_raw_fd(), libc::TIOCNXCL) } < 0 {
+                return Err(SIError::CannotOpenPort(
+                    std::io::Error::last_os_error().to_string(),
+                ));
+            }
+        }
+        let settings = self.requested_settings();
+        port.configure(&settings).unwrap();
+        self.actual_settings = Self::read_settings(&mut port, &settings)
+            .map_err(|e| si_log!(debug, self, "SerialInterface::read_settings(): {:?}", e))
+            .ok();
+        port.set_timeout(self.read_timeout())
+            .map_err(|_| SIError::CannotSetTimeout)?;
+        self.port = Some(Box::new(port));
+        self.baud_check = (0, 0);
+        self.metric(MetricEvent::PortOpened);
+        Ok(())
+    }
+
+    /// Connect to the serial-to-TCP gateway at `host` (`host:port`), trying each resolved address in
+    /// turn, see connect_tcp().
+    fn open_tcp(&mut self, host: &str) -> Result<(), SIError> {
+        let addrs = host
+            .to_socket_addrs()
+            .map_err(|e| SIError::CannotOpenPort(e.to_string()))?;
+        let mut error = SIError::CannotOpenPort(format!("{}: no address", host));
+        for addr in addrs {
+            match backend::TcpBackend::connect(&addr, self.timeout) {
+                Ok(mut tcp) => {
+                    tcp.set_read_timeout(self.read_timeout())
+                        .map_err(|_| SIError::CannotSetTimeout)?;
+                    let port: Box<dyn SerialBackend> = Box::new(tcp);
+                    si_log!(info, self, "SerialInterface::open_tcp(): connected to {}", addr);
+                    self.port = Some(port);
+                    self.actual_settings = None;
+                    self.baud_check = (0, 0);
+                    self.metric(MetricEvent::PortOpened);
+                    return Ok(());
+                }
+                Err(e) => error = SIError::CannotOpenPort(format!("{}: {}", addr, e)),
+            }
+        }
+        Err(error)
+    }
+
+    /// Returns the read timeout of the serial port: one character time, bounded by self.timeout. A read
+    /// blocks at most this long when no data is available, keeping the poll loops responsive.
+    fn read_timeout(&self) -> Duration {
+        self.tx_duration(1).min(self.timeout)
+    }
+
+    /// Push the read timeout derived from the configuration to the opened serial port, see
+    /// read_timeout(). No-op if the port is not opened or if it's a custom backend (see backend()).
+    fn apply_read_timeout(&mut self) -> Result<(), SIError> {
+        let timeout = self.read_timeout();
+        if let Some(port) = self.port.as_deref_mut() {
+            port.set_read_timeout(timeout)
+                .map_err(|_| SIError::CannotSetTimeout)?;
+        }
+        Ok(())
+    }
+
+    /// Returns SerialMessage::Settings with the current configuration, in response to GetSettings.
+    fn settings(&self) -> SerialMessage {
+        SerialMessage::Settings {
+            path: self.path.clone(),
+            baud_rate: self.baud_rate,
+            char_size: self.char_size,
+            parity: self.parity,
+            stop_bits: self.stop_bits,
+            flow_control: self.flow_control,
+            timeout: self.timeout,
+            silence: self.silence,
+            mode: self.mode.clone(),
+        }
+    }
+
+    /// Returns the settings configured on the interface, applied to the port when opened.
+    fn requested_settings(&self) -> PortSettings {
+        PortSettings {
+            baud_rate: self.baud_rate,
+            char_size: self.char_size,
+            parity: self.parity,
+            stop_bits: self.stop_bits,
+            flow_control: self.flow_control,
+        }
+    }
+
+    /// Read back the settings applied by the OS (termios on unix). A setting the OS reports in a way
+    /// the serial crate can't map (e.g. a non standard baud rate on some platforms) is reported as
+    /// requested, except the baud rate that is reported as BaudOther(0).
+    fn read_settings(
+        port: &mut serial::SystemPort,
+        requested: &PortSettings,
+    ) -> serial::Result<PortSettings> {
+        let actual = std::cell::Cell::new(*requested);
+        port.reconfigure(&|settings| {
+            actual.set(PortSettings {
+                baud_rate: settings.baud_rate().unwrap_or(BaudRate::BaudOther(0)),
+                char_size: settings.char_size().unwrap_or(requested.char_size),
+                parity: settings.parity().unwrap_or(requested.parity),
+                stop_bits: settings.stop_bits().unwrap_or(requested.stop_bits),
+                flow_control: settings.flow_control().unwrap_or(requested.flow_control),
+            });
+            Ok(())
+        })?;
+        Ok(actual.get())
+    }
+
+    /// Returns the settings actually applied by the OS when the port has been opened, that can differ
+    /// from the requested ones, e.g. a baud rate of 250000 quantized to 230400 by the driver.
+    /// Read back via termios on unix, and the serial port API on windows.
+    /// Returns SIError::PortNotOpened if the port is not opened or if it's a custom backend (see
+    /// backend()), as there are no OS settings to read.
+    pub fn actual_settings(&self) -> Result<PortSettings, SIError> {
+        self.actual_settings.ok_or(SIError::PortNotOpened)
+    }
+
+    /// Returns SerialMessage::SettingsQuantized if the settings applied by the OS differ from the
+    /// requested ones.
+    fn settings_quantized(&self) -> Option<SerialMessage> {
+        let requested = self.requested_settings();
+        let actual = self.actual_settings().ok()?;
+        let differ = requested.baud_rate.speed() != actual.baud_rate.speed()
+            || PortSettings {
+                baud_rate: requested.baud_rate,
+                ..actual
+            } != requested;
+        differ.then_some(SerialMessage::SettingsQuantized { requested, actual })
+    }
+
+    /// Map a serial::open() error to a SIError. The serial crate report both `port in use` and
+    /// `permission denied` as serial::ErrorKind::NoDevice, so the OS error is inspected to tell them apart,
+    /// see open_os_error() and open_error_kind().
+    fn open_error(e: serial::Error) -> SIError {
+        let os_error = match e.kind() {
+            serial::ErrorKind::NoDevice => Self::open_os_error(&e),
+            _ => None,
+        };
+        Self::open_error_kind(os_error, e.to_string())
+    }
+
+    /// Map the OS error of a failing open to a SIError:
+    ///   - unix: EBUSY => PortBusy, EACCES => PortPermissionDenied.
+    ///   - windows: ERROR_ACCESS_DENIED is what the OS returns when the port is already opened by another
+    ///     process, it's mapped to PortBusy.
+    ///
+    /// Any other failure is mapped to CannotOpenPort.
+    fn open_error_kind(os_error: Option<std::io::ErrorKind>, msg: String) -> SIError {
+        match os_error {
+            Some(std::io::ErrorKind::ResourceBusy) => SIError::PortBusy(msg),
+            #[cfg(windows)]
+            Some(std::io::ErrorKind::PermissionDenied) => SIError::PortBusy(msg),
+            #[cfg(not(windows))]
+            Some(std::io::ErrorKind::PermissionDenied) => SIError::PortPermissionDenied(msg),
+            _ => SIError::CannotOpenPort(msg),
+        }
+    }
+
+    /// Returns the kind of the OS error of a failing serial::open(). The serial crate does not keep
+    /// the error code, but its description is the OS message of the code (strerror() on unix), so it's
+    /// matched against the messages of the codes told apart by open_error_kind(). The port is not
+    /// opened again to find out, as closing it could have side effects (e.g. dropping DTR with HUPCL).
+    fn open_os_error(e: &serial::Error) -> Option<std::io::ErrorKind> {
+        #[cfg(unix)]
+        let codes = [libc::EBUSY, libc::EACCES];
+        // ERROR_ACCESS_DENIED
+        #[cfg(windows)]
+        let codes = [5];
+        #[cfg(not(any(unix, windows)))]
+        let codes: [i32; 0] = [];
+        let description = e.to_string();
+        codes
+            .into_iter()
+            .map(std::io::Error::from_raw_os_error)
+            .find(|os_error| {
+                // std formats the OS message as `<message> (os error <code>)`
+                let message = os_error.to_string();
+                message
+                    .rsplit_once(" (os error ")
+                    .is_some_and(|(message, _)| message == description.trim_end())
+            })
+            .map(|os_error| os_error.kind())
+    }
+
+    /// Lists available ports, and if there is exactly one, sets it as the path and opens it.
+    /// Returns SIError::NoPorts or SIError::MultiplePorts otherwise.
+    /// Note: all the ports returned by list_ports() are considered, there is no USB only filtering, so
+    /// on a system exposing onboard UARTs (e.g. /dev/ttyS*) several ports will likely be found.
+    pub fn open_single(mut self) -> Result<Self, SIError> {
+        self.select_single_port()?;
+        self.open()?;
+        Ok(self)
+    }
+
+    /// Sets the path to the only available port.
+    fn select_single_port(&mut self) -> Result<(), SIError> {
+        let mut ports = Self::list_ports()?;
+        match ports.len() {
+            0 => Err(SIError::NoPorts),
+            1 => {
+                self.path = ports.pop();
+                Ok(())
+            }
+            _ => Err(SIError::MultiplePorts(ports)),
+        }
+    }
+
+    /// Close the serial port.
+    pub fn close(&mut self) -> Result<(), SIError> {
+        if let Some(port) = self.port.take() {
+            drop(port);
+            self.actual_settings = None;
+            Ok(())
+        } else {
+            Err(SIError::NoPortToClose)
+        }
+    }
+
+    /// Sets the level of the RTS control line. Returns SIError::PortNotOpened if the port is not
+    /// opened, SIError::ControlLinesUnsupported for a TCP gateway or a custom backend (see backend()),
+    /// as there are no control lines to drive.
+    pub fn set_rts(&mut self, level: bool) -> Result<(), SIError> {
+        self.set_control_line(|port| port.set_rts(level))
+    }
+
+    /// Sets the level of the DTR control line. Returns SIError::PortNotOpened if the port is not
+    /// opened, SIError::ControlLinesUnsupported for a TCP gateway or a custom backend (see backend()),
+    /// as there are no control lines to drive.
+    pub fn set_dtr(&mut self, level: bool) -> Result<(), SIError> {
+        self.set_control_line(|port| port.set_dtr(level))
+    }
+
+    /// Asserts a break condition on the line for `duration`, then releases it, blocking meanwhile.
+    /// Unix only (TIOCSBRK/TIOCCBRK), returns SIError::BreakUnsupported on other platforms and for
+    /// custom backends (see backend()), and SIError::PortNotOpened if the port is not opened.
+    #[cfg(not(feature = "async-channel"))]
+    pub fn send_break(&mut self, duration: Duration) -> Result<(), SIError> {
+        self.set_break(true)?;
+        std::thread::sleep(duration);
+        self.set_break(false)
+    }
+
+    /// Asserts a break condition on the line for `duration`, then releases it.
+    /// Unix only (TIOCSBRK/TIOCCBRK), returns SIError::BreakUnsupported on other platforms and for
+    /// custom backends (see backend()), and SIError::PortNotOpened if the port is not opened.
+    #[cfg(feature = "async-channel")]
+    pub async fn send_break(&mut self, duration: Duration) -> Result<(), SIError> {
+        self.set_break(true)?;
+        sleep(duration).await;
+        self.set_break(false)
+    }
+
+    /// Asserts (`on`) or releases the break condition on the line, see send_break().
+    fn set_break(&mut self, on: bool) -> Result<(), SIError> {
+        let port = self.port.as_deref_mut().ok_or(SIError::PortNotOpened)?;
+        #[cfg(unix)]
+        if let Some(port) = port.as_any_mut().downcast_mut::<serial::SystemPort>() {
+            use std::os::unix::io::AsRawFd;
+            let request = if on { libc::TIOCSBRK } else { libc::TIOCCBRK };
+            // SAFETY: the fd is owned by port and valid until it's dropped.
+            if unsafe { libc::ioctl(port.as_raw_fd(), request) } < 0 {
+                return Err(SIError::CannotWritePort);
+            }
+            return Ok(());
+        }
+        #[cfg(not(unix))]
+        let _ = (port, on);
+        Err(SIError::BreakUnsupported)
+    }
+
+    /// Reads the input control lines of the port (CTS, DSR, CD, RI). Returns SIError::PortNotOpened if
+    /// the port is not opened or if it's a custom backend (see backend()), as there are no control
+    /// lines to read.
+    pub fn modem_status(&mut self) -> Result<ModemStatus, SIError> {
+        let port = self
+            .port
+            .as_deref_mut()
+            .and_then(|port| port.as_any_mut().downcast_mut::<serial::SystemPort>())
+            .ok_or(SIError::PortNotOpened)?;
+        Self::read_modem_status(port).map_err(|e| SIError::CannotReadPort(Some(e.to_string())))
+    }
+
+    /// Read the input control lines of a serial port, see modem_status().
+    fn read_modem_status(port: &mut serial::SystemPort) -> serial::Result<ModemStatus> {
+        Ok(ModemStatus {
+            cts: port.read_cts()?,
+            dsr: port.read_dsr()?,
+            cd: port.read_cd()?,
+            ri: port.read_ri()?,
+        })
+    }
+
+    /// Apply `set` to the serial port, see set_rts() and set_dtr().
+    fn set_control_line(
+        &mut self,
+        set: impl FnOnce(&mut serial::SystemPort) -> serial::Result<()>,
+    ) -> Result<(), SIError> {
+        let port = self
+            .port
+            .as_deref_mut()
+            .ok_or(SIError::PortNotOpened)?
+            .as_any_mut()
+            .downcast_mut::<serial::SystemPort>()
+            .ok_or(SIError::ControlLinesUnsupported)?;
+        set(port).map_err(|e| {
+            si_log!(error, self, "SerialInterface::set_control_line(): {:?}", e);
+            SIError::CannotSetControlLine
+        })
+    }
+
+    /// Close the port if opened, and re-open it from self.path (or the TCP gateway, see connect_tcp()),
+    /// see SerialMessage::Reconnect.
+    pub fn reconnect(&mut self) -> Result<(), SIError> {
+        let _ = self.close();
+        self.open_port()
+    }
+
+    /// Returns whether an error is a hard port error that should trigger a reconnection, see
+    /// auto_reconnect().
+    fn should_reconnect(&self, e: &SIError) -> bool {
+        self.auto_reconnect
+            && matches!(
+                e,
+                SIError::CannotReadPort(_) | SIError::CannotWritePort | SIError::PortNotOpened
+            )
+    }
+
+    /// Reconnect with backoff after a hard port error, see auto_reconnect(). Returns Some(Mode::Stop)
+    /// if a SetMode(Stop) is received meanwhile, None once reconnected.
+    #[cfg(not(feature = "async-channel"))]
+    fn recover(&mut self) -> Result<Option<Mode>, SIError> {
+        self.send_message(SerialMessage::Connected(false))?;
+        let mut backoff = RECONNECT_BACKOFF_MIN;
+        loop {
+            if let Some(SerialMessage::SetMode(Mode::Stop)) = self.wait_message(backoff)? {
+                return Ok(Some(Mode::Stop));
+            }
+            match self.reconnect() {
+                Ok(()) => {
+                    si_log!(info, self, "SerialInterface::recover(): reconnected");
+                    self.send_message(SerialMessage::Connected(true))?;
+                    return Ok(None);
+                }
+                Err(e) => {
+                    si_log!(debug, self, "SerialInterface::recover(): {:?}", e);
+                    backoff = (backoff * 2).min(RECONNECT_BACKOFF_MAX);
+                }
+            }
+        }
+    }
+
+    /// Reconnect with backoff after a hard port error, see auto_reconnect(). Returns Some(Mode::Stop)
+    /// if a SetMode(Stop) is received meanwhile, None once reconnected.
+    #[cfg(feature = "async-channel")]
+    async fn recover(&mut self) -> Result<Option<Mode>, SIError> {
+        self.send_message(SerialMessage::Connected(false)).await?;
+        let mut backoff = RECONNECT_BACKOFF_MIN;
+        loop {
+            if let Some(SerialMessage::SetMode(Mode::Stop)) = self.wait_message(backoff).await? {
+                return Ok(Some(Mode::Stop));
+            }
+            match self.reconnect() {
+                Ok(()) => {
+                    si_log!(info, self, "SerialInterface::recover(): reconnected");
+                    self.send_message(SerialMessage::Connected(true)).await?;
+                    return Ok(None);
+                }
+                Err(e) => {
+                    si_log!(debug, self, "SerialInterface::recover(): {:?}", e);
+                    backoff = (backoff * 2).min(RECONNECT_BACKOFF_MAX);
+                }
+            }
+        }
+    }
+    
+
+    /// Add data to the pending batch, returns the batch if it should be emitted now.
+    fn batch(&mut self, data: Vec<u8>) -> Option<Vec<Vec<u8>>> {
+        let start = *self.batch_start.get_or_insert_with(Instant::now);
+        self.batch.push(data);
+        let window = self.batch_window.unwrap_or_default();
+        if self.batch.len() >= self.batch_max || Instant::now().duration_since(start) >= window {
+            self.batch_start = None;
+            Some(std::mem::take(&mut self.batch))
         } else {
-            let mut port = serial::open(&self.path.as_ref().unwrap())
-                .map_err(|e| SIError::CannotOpenPort(e.to_string()))?;
-            let settings = serial::PortSettings {
-                baud_rate: self.baud_rate,
-                char_size: self.char_size,
-                parity: self.parity,
-                stop_bits: self.stop_bits,
-                flow_control: self.flow_control,
-            };
-            port.configure(&settings).unwrap();
-            port.set_timeout(Duration::from_nanos(10))
-                .map_err(|_| SIError::CannotSetTimeout)?;
-            self.port = Some(port);
-            Ok(())
+            None
         }
     }
 
-    /// Close the serial port.
-    pub fn close(&mut self) -> Result<(), SIError> {
-        if let Some(port) = self.port.take() {
-            drop(port);
-            Ok(())
+    /// Returns the pending batch if its window has elapsed.
+    fn due_batch(&mut self) -> Option<Vec<Vec<u8>>> {
+        let window = self.batch_window?;
+        if Instant::now().duration_since(self.batch_start?) >= window {
+            self.batch_start = None;
+            Some(std::mem::take(&mut self.batch))
         } else {
-            Err(SIError::NoPortToClose)
+            None
         }
     }
-    
 
-    /// Try to send a message trough self.sender
+    /// Emit the pending batch if its window has elapsed.
+    #[cfg(not(feature = "async-channel"))]
+    fn flush_batch(&mut self) -> Result<(), SIError> {
+        if let Some(batch) = self.due_batch() {
+            self.send_message(SerialMessage::ReceiveBatch(batch))?;
+        }
+        Ok(())
+    }
+
+    /// Emit the pending batch if its window has elapsed.
+    #[cfg(feature = "async-channel")]
+    async fn flush_batch(&mut self) -> Result<(), SIError> {
+        if let Some(batch) = self.due_batch() {
+            self.send_message(SerialMessage::ReceiveBatch(batch)).await?;
+        }
+        Ok(())
+    }
+
+    /// Try to send a message trough self.sender, `Receive` messages are batched if enabled.
     #[cfg(not(feature = "async-channel"))]
     fn send_message(&mut self, msg: SerialMessage) -> Result<(), SIError> {
-        log::debug!("SerialInterface.send_message({:?})", msg);
+        match &msg {
+            SerialMessage::Receive(data)
+            | SerialMessage::ReceiveWithId { data, .. }
+            | SerialMessage::ReceiveTimed { data, .. } => {
+                self.metric(MetricEvent::FrameReceived(data.len()))
+            }
+            SerialMessage::NoResponse { .. } | SerialMessage::NoResponseWithId { .. } => {
+                self.metric(MetricEvent::Timeout)
+            }
+            _ => {}
+        }
+        let msg = match msg {
+            SerialMessage::Receive(data) if self.batch_window.is_some() => {
+                let data = self.transform(data);
+                match self.batch(data) {
+                    Some(batch) => SerialMessage::ReceiveBatch(batch),
+                    None => return Ok(()),
+                }
+            }
+            SerialMessage::Receive(data) => SerialMessage::Receive(self.transform(data)),
+            SerialMessage::ReceiveWithId { id, data } => SerialMessage::ReceiveWithId {
+                id,
+                data: self.transform(data),
+            },
+            SerialMessage::ReceiveTimed {
+                data,
+                at,
+                direction,
+            } => SerialMessage::ReceiveTimed {
+                data: self.transform(data),
+                at,
+                direction,
+            },
+            msg => msg,
+        };
+        si_log!(debug, self, "SerialInterface.send_message({:?})", msg);
+        if std::mem::take(&mut self.baud_mismatch) {
+            self.emit(SerialMessage::PossibleBaudMismatch)?;
+        }
+        self.emit(msg)
+    }
+
+    /// Send a message trough self.sender, and a copy trough self.monitor_sender if any.
+    #[cfg(not(feature = "async-channel"))]
+    fn emit(&mut self, msg: SerialMessage) -> Result<(), SIError> {
+        if let Some(monitor) = &self.monitor_sender {
+            // never block nor fail on the monitor, the copy is dropped if the channel is closed
+            let _ = monitor.send(msg.clone());
+        }
         if let Some(sender) = self.sender.clone() {
-            log::debug!("SerialInterface::Send {:?}", &msg);
+            si_log!(debug, self, "SerialInterface::Send {:?}", &msg);
             sender
                 .send(msg)
                 .map_err(|_| SIError::CannotSendMessage)?;
             Ok(())
         } else {
-            log::debug!("SerialInterface::SIError::CannotSendMessage");
+            si_log!(debug, self, "SerialInterface::SIError::CannotSendMessage");
             Err(SIError::CannotSendMessage)
         }
     }
 
-    /// Try to send a message trough self.sender
+    /// Try to send a message trough self.sender, `Receive` messages are batched if enabled.
     #[cfg(feature = "async-channel")]
     async fn send_message(&mut self, msg: SerialMessage) -> Result<(), SIError> {
+        match &msg {
+            SerialMessage::Receive(data)
+            | SerialMessage::ReceiveWithId { data, .. }
+            | SerialMessage::ReceiveTimed { data, .. } => {
+                self.metric(MetricEvent::FrameReceived(data.len()))
+            }
+            SerialMessage::NoResponse { .. } | SerialMessage::NoResponseWithId { .. } => {
+                self.metric(MetricEvent::Timeout)
+            }
+            _ => {}
+        }
+        let msg = match msg {
+            SerialMessage::Receive(data) if self.batch_window.is_some() => {
+                let data = self.transform(data);
+                match self.batch(data) {
+                    Some(batch) => SerialMessage::ReceiveBatch(batch),
+                    None => return Ok(()),
+                }
+            }
+            SerialMessage::Receive(data) => SerialMessage::Receive(self.transform(data)),
+            SerialMessage::ReceiveWithId { id, data } => SerialMessage::ReceiveWithId {
+                id,
+                data: self.transform(data),
+            },
+            SerialMessage::ReceiveTimed {
+                data,
+                at,
+                direction,
+            } => SerialMessage::ReceiveTimed {
+                data: self.transform(data),
+                at,
+                direction,
+            },
+            msg => msg,
+        };
+        if std::mem::take(&mut self.baud_mismatch) {
+            self.emit(SerialMessage::PossibleBaudMismatch).await?;
+        }
+        self.emit(msg).await
+    }
+
+    /// Send a message trough self.sender, and a copy trough self.monitor_sender if any.
+    #[cfg(feature = "async-channel")]
+    async fn emit(&mut self, msg: SerialMessage) -> Result<(), SIError> {
+        if let Some(monitor) = &self.monitor_sender {
+            // never block nor fail on the monitor, the copy is dropped if the channel is full/closed
+            let _ = monitor.try_send(msg.clone());
+        }
         if let Some(sender) = self.sender.clone() {
-            log::debug!("SerialInterface::Send {:?}", &msg);
+            si_log!(debug, self, "SerialInterface::Send {:?}", &msg);
             sender
                 .send(msg)
                 .await
                 .map_err(|_| SIError::CannotSendMessage)?;
             Ok(())
         } else {
-            log::debug!("SerialInterface::SIError::CannotSendMessage");
+            si_log!(debug, self, "SerialInterface::SIError::CannotSendMessage");
             Err(SIError::CannotSendMessage)
         }
     }
     
 
-    /// Poll self.receiver channel and handle if there is one message. Return the message if it should be
-    /// handled externally. Two kind messages can be returned:
-    /// - SerialMessage::SetMode()
-    /// - SerialMessage::Send()
+    /// Poll self.receiver channel and handle if there is one message, see self.handle_message().
     #[cfg(not(feature = "async-channel"))]
     fn read_message(&mut self) -> Result<Option<SerialMessage>, SIError> {
+        self.flush_batch()?;
+        // control messages preserved by drain_stale_messages() are handled first
+        if let Some(message) = self.pending.pop_front() {
+            return self.handle_message(message);
+        }
         if let Some(receiver) = &mut self.receiver {
             if let Ok(message) = receiver.try_recv() {
-                log::debug!("SerialInterface::read_message({:?})", &message);
-                // general case, message to handle in any situation
-                match &message {
-                    SerialMessage::GetConnectionStatus => {
-                        if let Some(_port) = &self.port {
-                            self.send_message(SerialMessage::Connected(true))?;
-                        } else {
-                            self.send_message(SerialMessage::Connected(false))?;
+                return self.handle_message(message);
+            }
+        } else {
+            si_log!(debug, self, "No receiver!");
+        }
+        Ok(None)
+    }
+
+    /// Wait up to `timeout` for a message on self.receiver, waking up as soon as a message arrives,
+    /// then handle it as self.read_message() does.
+    #[cfg(not(feature = "async-channel"))]
+    fn wait_message(&mut self, timeout: Duration) -> Result<Option<SerialMessage>, SIError> {
+        if timeout.is_zero() || !self.pending.is_empty() {
+            return self.read_message();
+        }
+        self.flush_batch()?;
+        if let Some(receiver) = &mut self.receiver {
+            if let Ok(message) = receiver.recv_timeout(timeout) {
+                return self.handle_message(message);
+            }
+        } else {
+            si_log!(debug, self, "No receiver!");
+        }
+        Ok(None)
+    }
+
+    /// Switch to `mode` from the main loop, calling the exit/enter mode hooks if any.
+    fn switch_mode(&mut self, mode: Mode) {
+        if mode == self.mode {
+            return;
+        }
+        if let Some(hook) = &self.on_exit_mode {
+            hook(&self.mode);
+        }
+        self.mode = mode;
+        // a partial frame of the previous mode must not seed the first read of the new one
+        self.read_buffer.clear();
+        if let Some(hook) = &self.on_enter_mode {
+            hook(&self.mode);
+        }
+    }
+
+    /// Apply self.on_unhandled to a message not handled in the current mode.
+    #[cfg(not(feature = "async-channel"))]
+    fn unhandled(&mut self, message: SerialMessage) -> Result<(), SIError> {
+        match self.on_unhandled {
+            UnhandledPolicy::Ignore => {}
+            UnhandledPolicy::Log => {
+                si_log!(warn, self, "SerialInterface::unhandled {:?} in {:?} mode", message, self.mode)
+            }
+            UnhandledPolicy::Error => self.send_message(SerialMessage::Error(SIError::WrongMode))?,
+        }
+        Ok(())
+    }
+
+    /// Apply self.on_unhandled to a message not handled in the current mode.
+    #[cfg(feature = "async-channel")]
+    async fn unhandled(&mut self, message: SerialMessage) -> Result<(), SIError> {
+        match self.on_unhandled {
+            UnhandledPolicy::Ignore => {}
+            UnhandledPolicy::Log => {
+                si_log!(warn, self, "SerialInterface::unhandled {:?} in {:?} mode", message, self.mode)
+            }
+            UnhandledPolicy::Error => {
+                self.send_message(SerialMessage::Error(SIError::WrongMode))
+                    .await?
+            }
+        }
+        Ok(())
+    }
+
+    /// Drain the messages queued on self.receiver when a SetMode() leaving Stop mode is dequeued:
+    /// data messages (SerialMessage::Send() / SerialMessage::SendRaw()) queued for the previous mode
+    /// are discarded, control messages are preserved and handled first by self.read_message().
+    fn drain_stale_messages(&mut self) {
+        if let Some(receiver) = &self.receiver {
+            while let Ok(message) = receiver.try_recv() {
+                match message {
+                    SerialMessage::Send(_)
+                    | SerialMessage::SendRaw(_)
+                    | SerialMessage::SendWithId { .. }
+                    | SerialMessage::SendWithTimeout { .. }
+                    | SerialMessage::SendNoResponse(_) => {
+                        si_log!(warn, self, "SerialInterface::drain_stale_messages(): drop {:?}", message);
+                    }
+                    message => self.pending.push_back(message),
+                }
+            }
+        }
+    }
+
+    /// Handle a message received on self.receiver. Return the message if it should be
+    /// handled externally. Two kind messages can be returned:
+    /// - SerialMessage::SetMode()
+    /// - SerialMessage::Send()
+    #[cfg(not(feature = "async-channel"))]
+    fn handle_message(
+        &mut self,
+        message: SerialMessage,
+    ) -> Result<Option<SerialMessage>, SIError> {
+        si_log!(debug, self, "SerialInterface::read_message({:?})", &message);
+        self.reset_idle();
+        // general case, message to handle in any situation
+        match &message {
+            SerialMessage::GetConnectionStatus => {
+                if let Some(_port) = &self.port {
+                    self.send_message(SerialMessage::Connected(true))?;
+                } else {
+                    self.send_message(SerialMessage::Connected(false))?;
+                }
+                return Ok(None);
+            }
+            SerialMessage::GetStatus => {
+                self.send_message(SerialMessage::Status(self.status.clone()))?;
+                return Ok(None);
+            }
+            SerialMessage::GetSettings => {
+                self.send_message(self.settings())?;
+                return Ok(None);
+            }
+            SerialMessage::SetRts(level) | SerialMessage::SetDtr(level) => {
+                let result = if let SerialMessage::SetRts(_) = message {
+                    self.set_rts(*level)
+                } else {
+                    self.set_dtr(*level)
+                };
+                if let Err(e) = result {
+                    self.send_message(SerialMessage::Error(e))?;
+                }
+                return Ok(None);
+            }
+            SerialMessage::SendBreak(duration) => {
+                if let Err(e) = self.send_break(*duration) {
+                    self.send_message(SerialMessage::Error(e))?;
+                }
+                return Ok(None);
+            }
+            SerialMessage::GetModemStatus => {
+                let msg = match self.modem_status() {
+                    Ok(status) => SerialMessage::ModemStatus {
+                        cts: status.cts,
+                        dsr: status.dsr,
+                        cd: status.cd,
+                        ri: status.ri,
+                    },
+                    Err(e) => SerialMessage::Error(e),
+                };
+                self.send_message(msg)?;
+                return Ok(None);
+            }
+            SerialMessage::Reconnect => {
+                if let Err(e) = self.reconnect() {
+                    si_log!(debug, self, "Reconnect::{:?}", e);
+                    self.send_message(SerialMessage::Connected(false))?;
+                    self.send_message(SerialMessage::Error(e))?;
+                } else {
+                    self.send_message(SerialMessage::Connected(true))?;
+                    if let Some(msg) = self.settings_quantized() {
+                        self.send_message(msg)?;
+                    }
+                }
+                return Ok(None);
+            }
+            // If ask for change mode, we return message to caller in order it can handle it.
+            SerialMessage::SetMode(mode) => {
+                // drained as soon as the SetMode() is dequeued, the messages sent after it are kept
+                if self.drain_on_mode_change && self.mode == Mode::Stop && *mode != Mode::Stop {
+                    self.drain_stale_messages();
+                }
+                return Ok(Some(SerialMessage::SetMode(mode.clone())));
+            }
+            SerialMessage::SetTimeout(timeout) => {
+                self.timeout = *timeout;
+                self.apply_read_timeout()?;
+                return Ok(None);
+            }
+            SerialMessage::Ping => {
+                self.send_message(SerialMessage::Pong)?;
+                return Ok(None);
+            }
+            SerialMessage::FlushInput | SerialMessage::FlushOutput | SerialMessage::FlushAll => {
+                let input = !matches!(message, SerialMessage::FlushOutput);
+                let output = !matches!(message, SerialMessage::FlushInput);
+                let msg = match self.flush_buffers(input, output) {
+                    Ok(()) => SerialMessage::Flushed,
+                    Err(e) => SerialMessage::Error(e),
+                };
+                self.send_message(msg)?;
+                return Ok(None);
+            }
+            // Unwind the current mode loop as for SetMode(Stop), start() then returns.
+            SerialMessage::Shutdown => {
+                self.shutdown = true;
+                return Ok(Some(SerialMessage::SetMode(Mode::Stop)));
+            }
+            _ => {}
+        }
+
+        // Stop case: Settings / Flow control
+        if self.mode == Mode::Stop {
+            match message {
+                SerialMessage::ListPorts => {
+                    self.send_message(SerialMessage::AvailablePorts(
+                        SerialInterface::list_ports()?,
+                    ))?;
+                    return Ok(None);
+                }
+                SerialMessage::ListPortsDetailed => {
+                    self.send_message(SerialMessage::AvailablePortsDetailed(
+                        SerialInterface::list_ports_detailed()?,
+                    ))?;
+                    return Ok(None);
+                }
+                SerialMessage::SetPort(port) => {
+                    self.path = Some(port);
+                    self.tcp_host = None;
+                    return Ok(None);
+                }
+                SerialMessage::SetTcp(host) => {
+                    self.tcp_host = Some(host);
+                    return Ok(None);
+                }
+                SerialMessage::SetBauds(bauds) => {
+                    self.baud_rate = bauds;
+                    if !self.silence_set {
+                        self.auto_silence();
+                    }
+                    return Ok(None);
+                }
+                SerialMessage::SetBaudsRaw(bauds) => {
+                    match Self::baud_from_u32(bauds) {
+                        Ok(baud_rate) => {
+                            self.baud_rate = baud_rate;
+                            if !self.silence_set {
+                                self.auto_silence();
+                            }
                         }
-                        return Ok(None);
+                        Err(e) => self.send_message(SerialMessage::Error(e))?,
                     }
-                    SerialMessage::GetStatus => {
-                        self.send_message(SerialMessage::Status(self.status.clone()))?;
-                        return Ok(None);
+                    return Ok(None);
+                }
+                SerialMessage::SetCharSize(char_size) => {
+                    self.char_size = char_size;
+                    return Ok(None);
+                }
+                SerialMessage::SetParity(parity) => {
+                    self.parity = parity;
+                    return Ok(None);
+                }
+                SerialMessage::SetStopBits(stop_bits) => {
+                    self.stop_bits = stop_bits;
+                    return Ok(None);
+                }
+                SerialMessage::SetFlowControl(flow_control) => {
+                    self.flow_control = flow_control;
+                    return Ok(None);
+                }
+                SerialMessage::Connect => {
+                    if let Err(e) = self.open() {
+                        si_log!(debug, self, "Connect::{:?}", e);
+                        self.send_message(SerialMessage::Connected(false))?;
+                        self.send_message(SerialMessage::Error(e))?;
+                    } else {
+                        self.send_message(SerialMessage::Connected(true))?;
+                        if let Some(msg) = self.settings_quantized() {
+                            self.send_message(msg)?;
+                        }
                     }
-                    // If ask for change mode, we return message to caller in order it can handle it.
-                    SerialMessage::SetMode(mode) => {
-                        return Ok(Some(SerialMessage::SetMode(mode.clone())));
+                    return Ok(None);
+                }
+                SerialMessage::OpenSingle => {
+                    if let Err(e) = self.select_single_port().and_then(|_| self.open()) {
+                        si_log!(debug, self, "OpenSingle::{:?}", e);
+                        self.send_message(SerialMessage::Connected(false))?;
+                        self.send_message(SerialMessage::Error(e))?;
+                    } else {
+                        self.send_message(SerialMessage::Connected(true))?;
+                        if let Some(msg) = self.settings_quantized() {
+                            self.send_message(msg)?;
+                        }
                     }
-                    SerialMessage::SetTimeout(timeout) => {
-                        self.timeout = *timeout;
-                        return Ok(None);
+                    return Ok(None);
+                }
+                SerialMessage::Disconnect => {
+                    let result = self.close();
+                    self.send_message(SerialMessage::Connected(false))?;
+                    if let Err(e) = result {
+                        self.send_message(SerialMessage::Error(e))?;
                     }
-                    SerialMessage::Ping => {
-                        self.send_message(SerialMessage::Pong)?;
-                        return Ok(None);
+                }
+                message => self.unhandled(message)?,
+            }
+        } else if let SerialMessage::Send(data) = message {
+            return Ok(Some(SerialMessage::Send(self.with_auto_crc(data))));
+        } else if let SerialMessage::SendRaw(data) = message {
+            return Ok(Some(SerialMessage::Send(data)));
+        } else if let (SerialMessage::SendWithId { id, data }, Mode::Master | Mode::MasterStream) =
+            (&message, &self.mode)
+        {
+            return Ok(Some(SerialMessage::SendWithId {
+                id: *id,
+                data: self.with_auto_crc(data.clone()),
+            }));
+        } else if let (
+            SerialMessage::SendWithTimeout { data, timeout },
+            Mode::Master | Mode::MasterStream,
+        ) = (&message, &self.mode)
+        {
+            return Ok(Some(SerialMessage::SendWithTimeout {
+                data: self.with_auto_crc(data.clone()),
+                timeout: *timeout,
+            }));
+        } else if let (SerialMessage::SendNoResponse(data), Mode::Master | Mode::MasterStream) =
+            (&message, &self.mode)
+        {
+            return Ok(Some(SerialMessage::SendNoResponse(
+                self.with_auto_crc(data.clone()),
+            )));
+        } else {
+            self.unhandled(message)?;
+        }
+        Ok(None)
+    }
+
+    /// Poll self.receiver channel and handle if there is one message, see self.handle_message().
+    #[cfg(feature = "async-channel")]
+    async fn read_message(&mut self) -> Result<Option<SerialMessage>, SIError> {
+        self.flush_batch().await?;
+        // control messages preserved by drain_stale_messages() are handled first
+        if let Some(message) = self.pending.pop_front() {
+            return self.handle_message(message).await;
+        }
+        if let Some(receiver) = self.receiver.clone() {
+            if let Ok(message) = receiver.try_recv() {
+                return self.handle_message(message).await;
+            }
+        } else {
+            si_log!(debug, self, "No receiver!");
+        }
+        Ok(None)
+    }
+
+    /// Wait up to `timeout` for a message on self.receiver, waking up as soon as a message arrives
+    /// (select over the receiver and a timer), then handle it as self.read_message() does.
+    #[cfg(feature = "async-channel")]
+    async fn wait_message(&mut self, timeout: Duration) -> Result<Option<SerialMessage>, SIError> {
+        if timeout.is_zero() || !self.pending.is_empty() {
+            return self.read_message().await;
+        }
+        self.flush_batch().await?;
+        if let Some(receiver) = self.receiver.clone() {
+            tokio::select! {
+                message = receiver.recv() => {
+                    if let Ok(message) = message {
+                        return self.handle_message(message).await;
+                    }
+                }
+                _ = sleep(timeout) => {}
+            }
+        } else {
+            si_log!(debug, self, "No receiver!");
+        }
+        Ok(None)
+    }
+
+    /// Handle a message received on self.receiver. Return the message if it should be
+    /// handled externally. Two kind messages can be returned:
+    /// - SerialMessage::SetMode()
+    /// - SerialMessage::Send()
+    #[cfg(feature = "async-channel")]
+    async fn handle_message(
+        &mut self,
+        message: SerialMessage,
+    ) -> Result<Option<SerialMessage>, SIError> {
+        si_log!(debug, self, "SerialInterface::Receive !!! {:?}", &message);
+        self.reset_idle();
+        // general case, message to handle in any situation
+        match &message {
+            SerialMessage::GetConnectionStatus => {
+                if let Some(_port) = &self.port {
+                    self.send_message(SerialMessage::Connected(true)).await?;
+                } else {
+                    self.send_message(SerialMessage::Connected(false)).await?;
+                }
+                return Ok(None);
+            }
+            SerialMessage::GetStatus => {
+                self.send_message(SerialMessage::Status(self.status.clone()))
+                    .await?;
+                return Ok(None);
+            }
+            SerialMessage::GetSettings => {
+                self.send_message(self.settings()).await?;
+                return Ok(None);
+            }
+            SerialMessage::SetRts(level) | SerialMessage::SetDtr(level) => {
+                let result = if let SerialMessage::SetRts(_) = message {
+                    self.set_rts(*level)
+                } else {
+                    self.set_dtr(*level)
+                };
+                if let Err(e) = result {
+                    self.send_message(SerialMessage::Error(e)).await?;
+                }
+                return Ok(None);
+            }
+            SerialMessage::SendBreak(duration) => {
+                if let Err(e) = self.send_break(*duration).await {
+                    self.send_message(SerialMessage::Error(e)).await?;
+                }
+                return Ok(None);
+            }
+            SerialMessage::GetModemStatus => {
+                let msg = match self.modem_status() {
+                    Ok(status) => SerialMessage::ModemStatus {
+                        cts: status.cts,
+                        dsr: status.dsr,
+                        cd: status.cd,
+                        ri: status.ri,
+                    },
+                    Err(e) => SerialMessage::Error(e),
+                };
+                self.send_message(msg).await?;
+                return Ok(None);
+            }
+            SerialMessage::Reconnect => {
+                if let Err(e) = self.reconnect() {
+                    si_log!(debug, self, "Reconnect::{:?}", e);
+                    self.send_message(SerialMessage::Connected(false)).await?;
+                    self.send_message(SerialMessage::Error(e)).await?;
+                } else {
+                    self.send_message(SerialMessage::Connected(true)).await?;
+                    if let Some(msg) = self.settings_quantized() {
+                        self.send_message(msg).await?;
                     }
-                    _ => {}
                 }
+                return Ok(None);
+            }
+            // If ask for change mode, we return message to caller in order it can handle it.
+            SerialMessage::SetMode(mode) => {
+                // drained as soon as the SetMode() is dequeued, the messages sent after it are kept
+                if self.drain_on_mode_change && self.mode == Mode::Stop && *mode != Mode::Stop {
+                    self.drain_stale_messages();
+                }
+                return Ok(Some(SerialMessage::SetMode(mode.clone())));
+            }
+            SerialMessage::SetTimeout(timeout) => {
+                self.timeout = *timeout;
+                self.apply_read_timeout()?;
+                return Ok(None);
+            }
+            SerialMessage::Ping => {
+                self.send_message(SerialMessage::Pong).await?;
+                return Ok(None);
+            }
+            SerialMessage::FlushInput | SerialMessage::FlushOutput | SerialMessage::FlushAll => {
+                let input = !matches!(message, SerialMessage::FlushOutput);
+                let output = !matches!(message, SerialMessage::FlushInput);
+                let msg = match self.flush_buffers(input, output) {
+                    Ok(()) => SerialMessage::Flushed,
+                    Err(e) => SerialMessage::Error(e),
+                };
+                self.send_message(msg).await?;
+                return Ok(None);
+            }
+            // Unwind the current mode loop as for SetMode(Stop), start() then returns.
+            SerialMessage::Shutdown => {
+                self.shutdown = true;
+                return Ok(Some(SerialMessage::SetMode(Mode::Stop)));
+            }
+            _ => {}
+        }
 
-                // Stop case: Settings / Flow control
-                if self.mode == Mode::Stop {
-                    match message {
-                        SerialMessage::ListPorts => {
-                            self.send_message(SerialMessage::AvailablePorts(
-                                SerialInterface::list_ports()?,
-                            ))?;
-                            return Ok(None);
-                        }
-                        SerialMessage::SetPort(port) => {
-                            self.path = Some(port);
-                            return Ok(None);
-                        }
-                        SerialMessage::SetBauds(bauds) => {
-                            self.baud_rate = bauds;
-                            // TODO: update silence?
-                            return Ok(None);
-                        }
-                        SerialMessage::SetCharSize(char_size) => {
-                            self.char_size = char_size;
-                            return Ok(None);
-                        }
-                        SerialMessage::SetParity(parity) => {
-                            self.parity = parity;
-                            return Ok(None);
-                        }
-                        SerialMessage::SetStopBits(stop_bits) => {
-                            self.stop_bits = stop_bits;
-                            return Ok(None);
-                        }
-                        SerialMessage::SetFlowControl(flow_control) => {
-                            self.flow_control = flow_control;
-                            return Ok(None);
-                        }
-                        SerialMessage::Connect => {
-                            if let Err(e) = self.open() {
-                                log::debug!("Connect::{:?}", e);
-                                self.send_message(SerialMessage::Connected(false))?;
-                                self.send_message(SerialMessage::Error(e))?;
-                            } else {
-                                self.send_message(SerialMessage::Connected(true))?;
+        // Stop case: Settings / Flow control
+        if self.mode == Mode::Stop {
+            match message {
+                SerialMessage::ListPorts => {
+                    self.send_message(SerialMessage::AvailablePorts(
+                        SerialInterface::list_ports()?,
+                    ))
+                        .await?;
+                    return Ok(None);
+                }
+                SerialMessage::ListPortsDetailed => {
+                    self.send_message(SerialMessage::AvailablePortsDetailed(
+                        SerialInterface::list_ports_detailed()?,
+                    ))
+                    .await?;
+                    return Ok(None);
+                }
+                SerialMessage::SetPort(port) => {
+                    self.path = Some(port);
+                    self.tcp_host = None;
+                    return Ok(None);
+                }
+                SerialMessage::SetTcp(host) => {
+                    self.tcp_host = Some(host);
+                    return Ok(None);
+                }
+                SerialMessage::SetBauds(bauds) => {
+                    self.baud_rate = bauds;
+                    if !self.silence_set {
+                        self.auto_silence();
+                    }
+                    return Ok(None);
+                }
+                SerialMessage::SetBaudsRaw(bauds) => {
+                    match Self::baud_from_u32(bauds) {
+                        Ok(baud_rate) => {
+                            self.baud_rate = baud_rate;
+                            if !self.silence_set {
+                                self.auto_silence();
                             }
-                            return Ok(None);
                         }
-                        SerialMessage::Disconnect => {
-                            let result = self.close();
-                            self.send_message(SerialMessage::Connected(false))?;
-                            if let Err(e) = result {
-                                self.send_message(SerialMessage::Error(e))?;
-                            }
+                        Err(e) => self.send_message(SerialMessage::Error(e)).await?,
+                    }
+                    return Ok(None);
+                }
+                SerialMessage::SetCharSize(char_size) => {
+                    self.char_size = char_size;
+                    return Ok(None);
+                }
+                SerialMessage::SetParity(parity) => {
+                    self.parity = parity;
+                    return Ok(None);
+                }
+                SerialMessage::SetStopBits(stop_bits) => {
+                    self.stop_bits = stop_bits;
+                    return Ok(None);
+                }
+                SerialMessage::SetFlowControl(flow_control) => {
+                    self.flow_control = flow_control;
+                    return Ok(None);
+                }
+                SerialMessage::Connect => {
+                    if let Err(e) = self.open() {
+                        si_log!(debug, self, "Connect::{:?}", e);
+                        self.send_message(SerialMessage::Connected(false)).await?;
+                        self.send_message(SerialMessage::Error(e)).await?;
+                    } else {
+                        self.send_message(SerialMessage::Connected(true)).await?;
+                        if let Some(msg) = self.settings_quantized() {
+                            self.send_message(msg).await?;
+                        }
+                    }
+                    return Ok(None);
+                }
+                SerialMessage::OpenSingle => {
+                    if let Err(e) = self.select_single_port().and_then(|_| self.open()) {
+                        si_log!(debug, self, "OpenSingle::{:?}", e);
+                        self.send_message(SerialMessage::Connected(false)).await?;
+                        self.send_message(SerialMessage::Error(e)).await?;
+                    } else {
+                        self.send_message(SerialMessage::Connected(true)).await?;
+                        if let Some(msg) = self.settings_quantized() {
+                            self.send_message(msg).await?;
                         }
-                        _ => {}
                     }
-                } else if let SerialMessage::Send(data) = message {
-                    return Ok(Some(SerialMessage::Send(data)));
+                    return Ok(None);
+                }
+                SerialMessage::Disconnect => {
+                    let result = self.close();
+                    self.send_message(SerialMessage::Connected(false)).await?;
+                    if let Err(e) = result {
+                        self.send_message(SerialMessage::Error(e)).await?;
+                    }
                 }
+                message => self.unhandled(message).await?,
             }
+        } else if let SerialMessage::Send(data) = message {
+            return Ok(Some(SerialMessage::Send(self.with_auto_crc(data))));
+        } else if let SerialMessage::SendRaw(data) = message {
+            return Ok(Some(SerialMessage::Send(data)));
+        } else if let (SerialMessage::SendWithId { id, data }, Mode::Master | Mode::MasterStream) =
+            (&message, &self.mode)
+        {
+            return Ok(Some(SerialMessage::SendWithId {
+                id: *id,
+                data: self.with_auto_crc(data.clone()),
+            }));
+        } else if let (
+            SerialMessage::SendWithTimeout { data, timeout },
+            Mode::Master | Mode::MasterStream,
+        ) = (&message, &self.mode)
+        {
+            return Ok(Some(SerialMessage::SendWithTimeout {
+                data: self.with_auto_crc(data.clone()),
+                timeout: *timeout,
+            }));
+        } else if let (SerialMessage::SendNoResponse(data), Mode::Master | Mode::MasterStream) =
+            (&message, &self.mode)
+        {
+            return Ok(Some(SerialMessage::SendNoResponse(
+                self.with_auto_crc(data.clone()),
+            )));
         } else {
-            log::debug!("No receiver!");
+            self.unhandled(message).await?;
         }
         Ok(None)
     }
 
-    /// Poll self.receiver channel and handle if there is one message. Return the message if it should be
-    /// handled externally. Two kind messages can be returned:
-    /// - SerialMessage::SetMode()
-    /// - SerialMessage::Send()
+    
+    /// Write the whole data to the port, see write_all(). If a DE/RE GPIO line is configured, it's
+    /// asserted during the write.
+    #[cfg(not(feature = "async-channel"))]
+    fn write_port(&mut self, data: &[u8]) -> Result<(), SIError> {
+        #[cfg(feature = "gpio")]
+        if self.de_gpio.is_some() {
+            self.set_de(true)?;
+            let result = self.write_all(data);
+            // wait for the last byte to leave the UART before releasing the bus
+            if let Some(port) = self.port.as_mut() {
+                let _ = port.flush();
+            }
+            self.set_de(false)?;
+            return result;
+        }
+        self.write_all(data)
+    }
+
+    /// Write the whole data to the port, see write_all(). If a DE/RE GPIO line is configured, it's
+    /// asserted during the write.
     #[cfg(feature = "async-channel")]
-    async fn read_message(&mut self) -> Result<Option<SerialMessage>, SIError> {
-        if let Some(receiver) = self.receiver.clone() {
-            if let Ok(message) = receiver.try_recv() {
-                log::debug!("SerialInterface::Receive !!! {:?}", &message);
-                // general case, message to handle in any situation
-                match &message {
-                    SerialMessage::GetConnectionStatus => {
-                        if let Some(_port) = &self.port {
-                            self.send_message(SerialMessage::Connected(true)).await?;
-                        } else {
-                            self.send_message(SerialMessage::Connected(false)).await?;
-                        }
-                        return Ok(None);
-                    }
-                    SerialMessage::GetStatus => {
-                        self.send_message(SerialMessage::Status(self.status.clone()))
-                            .await?;
-                        return Ok(None);
-                    }
-                    // If ask for change mode, we return message to caller in order it can handle it.
-                    SerialMessage::SetMode(mode) => {
-                        return Ok(Some(SerialMessage::SetMode(mode.clone())));
-                    }
-                    SerialMessage::SetTimeout(timeout) => {
-                        self.timeout = *timeout;
-                        return Ok(None);
-                    }
-                    SerialMessage::Ping => {
-                        self.send_message(SerialMessage::Pong).await?;
-                        return Ok(None);
+    async fn write_port(&mut self, data: &[u8]) -> Result<(), SIError> {
+        #[cfg(feature = "gpio")]
+        if self.de_gpio.is_some() {
+            self.set_de(true)?;
+            let result = self.write_all(data).await;
+            // wait for the last byte to leave the UART before releasing the bus
+            if let Some(port) = self.port.as_mut() {
+                let _ = port.flush();
+            }
+            self.set_de(false)?;
+            return result;
+        }
+        self.write_all(data).await
+    }
+
+    /// Drive the DE/RE GPIO line, if any.
+    #[cfg(feature = "gpio")]
+    fn set_de(&self, transmit: bool) -> Result<(), SIError> {
+        if let Some(gpio) = &self.de_gpio {
+            gpio.set(transmit).map_err(|e| {
+                si_log!(error, self, "SerialInterface::set_de({}): {:?}", transmit, e);
+                SIError::CannotDriveGpio
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Write the whole data to the port. Transient errors (WouldBlock, Interrupted, TimedOut, e.g. a
+    /// full buffer with flow control) are retried up to self.write_retries times, waiting
+    /// self.write_retry_delay between attempts, any other error fails immediately.
+    #[cfg(not(feature = "async-channel"))]
+    fn write_all(&mut self, data: &[u8]) -> Result<(), SIError> {
+        let mut written = 0;
+        let mut retries = 0;
+        while written < data.len() {
+            let port = self.port.as_mut().ok_or(SIError::PortNotOpened)?;
+            match port.write(&data[written..]) {
+                Ok(0) => return Err(SIError::CannotWritePort),
+                Ok(n) => written += n,
+                Err(e) => {
+                    let transient = matches!(
+                        e.kind(),
+                        std::io::ErrorKind::WouldBlock
+                            | std::io::ErrorKind::Interrupted
+                            | std::io::ErrorKind::TimedOut
+                    );
+                    if !transient || retries >= self.write_retries {
+                        si_log!(debug, self, "SerialInterface::write_port(): {:?}", e);
+                        return Err(SIError::CannotWritePort);
                     }
-                    _ => {}
+                    retries += 1;
+                    si_log!(
+                        debug,
+                        self,
+                        "SerialInterface::write_port(): {:?}, retry {}",
+                        e,
+                        retries
+                    );
+                    std::thread::sleep(self.write_retry_delay);
                 }
+            }
+        }
+        self.metric(MetricEvent::FrameSent(data.len()));
+        Ok(())
+    }
 
-                // Stop case: Settings / Flow control
-                if self.mode == Mode::Stop {
-                    match message {
-                        SerialMessage::ListPorts => {
-                            self.send_message(SerialMessage::AvailablePorts(
-                                SerialInterface::list_ports()?,
-                            ))
-                                .await?;
-                            return Ok(None);
-                        }
-                        SerialMessage::SetPort(port) => {
-                            self.path = Some(port);
-                            return Ok(None);
-                        }
-                        SerialMessage::SetBauds(bauds) => {
-                            self.baud_rate = bauds;
-                            // TODO: update silence?
-                            return Ok(None);
-                        }
-                        SerialMessage::SetCharSize(char_size) => {
-                            self.char_size = char_size;
-                            return Ok(None);
-                        }
-                        SerialMessage::SetParity(parity) => {
-                            self.parity = parity;
-                            return Ok(None);
-                        }
-                        SerialMessage::SetStopBits(stop_bits) => {
-                            self.stop_bits = stop_bits;
-                            return Ok(None);
-                        }
-                        SerialMessage::SetFlowControl(flow_control) => {
-                            self.flow_control = flow_control;
-                            return Ok(None);
-                        }
-                        SerialMessage::Connect => {
-                            if let Err(e) = self.open() {
-                                log::debug!("Connect::{:?}", e);
-                                self.send_message(SerialMessage::Connected(false)).await?;
-                                self.send_message(SerialMessage::Error(e)).await?;
-                            } else {
-                                self.send_message(SerialMessage::Connected(true)).await?;
-                            }
-                            return Ok(None);
-                        }
-                        SerialMessage::Disconnect => {
-                            let result = self.close();
-                            self.send_message(SerialMessage::Connected(false)).await?;
-                            if let Err(e) = result {
-                                self.send_message(SerialMessage::Error(e)).await?;
-                            }
-                        }
-                        _ => {}
+    /// Write the whole data to the port. Transient errors (WouldBlock, Interrupted, TimedOut, e.g. a
+    /// full buffer with flow control) are retried up to self.write_retries times, waiting
+    /// self.write_retry_delay between attempts, any other error fails immediately.
+    #[cfg(feature = "async-channel")]
+    async fn write_all(&mut self, data: &[u8]) -> Result<(), SIError> {
+        let mut written = 0;
+        let mut retries = 0;
+        while written < data.len() {
+            let port = self.port.as_mut().ok_or(SIError::PortNotOpened)?;
+            match port.write(&data[written..]) {
+                Ok(0) => return Err(SIError::CannotWritePort),
+                Ok(n) => written += n,
+                Err(e) => {
+                    let transient = matches!(
+                        e.kind(),
+                        std::io::ErrorKind::WouldBlock
+                            | std::io::ErrorKind::Interrupted
+                            | std::io::ErrorKind::TimedOut
+                    );
+                    if !transient || retries >= self.write_retries {
+                        si_log!(debug, self, "SerialInterface::write_port(): {:?}", e);
+                        return Err(SIError::CannotWritePort);
                     }
-                } else if let SerialMessage::Send(data) = message {
-                    return Ok(Some(SerialMessage::Send(data)));
+                    retries += 1;
+                    si_log!(
+                        debug,
+                        self,
+                        "SerialInterface::write_port(): {:?}, retry {}",
+                        e,
+                        retries
+                    );
+                    sleep(self.write_retry_delay).await;
                 }
             }
-        }  else {
-            log::debug!("No receiver!");
         }
-        Ok(None)
+        self.metric(MetricEvent::FrameSent(data.len()));
+        Ok(())
     }
 
-    
-    /// Write data to the serial line.
+    /// Retransmit a request in Master mode: wait self.retry_delay, clear the read buffer, then write
+    /// the request again. Unlike write(), no SerialMessage::DataSent is emitted, so a request is
+    /// reported once whatever the number of attempts.
+    #[cfg(not(feature = "async-channel"))]
+    fn retransmit(&mut self, data: &[u8], attempt: u8) -> Result<(), SIError> {
+        si_log!(debug, self, "SerialInterface::retransmit(): attempt {}", attempt + 1);
+        std::thread::sleep(self.retry_delay);
+        self.clear_read_buffer()?;
+        self.write_port(data)
+    }
+
+    /// Retransmit a request in Master mode: wait self.retry_delay, clear the read buffer, then write
+    /// the request again. Unlike write(), no SerialMessage::DataSent is emitted, so a request is
+    /// reported once whatever the number of attempts.
+    #[cfg(feature = "async-channel")]
+    async fn retransmit(&mut self, data: &[u8], attempt: u8) -> Result<(), SIError> {
+        si_log!(debug, self, "SerialInterface::retransmit(): attempt {}", attempt + 1);
+        sleep(self.retry_delay).await;
+        self.clear_read_buffer()?;
+        self.write_port(data).await
+    }
+
+    /// Write data to the serial line, data larger than self.max_tx_frame are rejected before
+    /// transmitting with SIError::FrameTooLarge.
     #[cfg(not(feature = "async-channel"))]
     #[allow(unused)]
     fn write(&mut self, data: Vec<u8>) -> Result<(), SIError> {
-        log::debug!("write({:?})", data.clone());
+        si_log!(debug, self, "write({:?})", data.clone());
+        if data.len() > self.max_tx_frame {
+            return Err(SIError::FrameTooLarge);
+        }
         let port_open = self.port.is_some();
         if port_open {
-            let buffer = &data[0..data.len()];
-            self.port
-                .as_mut()
-                .unwrap()
-                .write(buffer)
-                .map_err(|_| SIError::CannotWritePort)?;
+            self.write_port(&data)?;
             self.send_message(SerialMessage::DataSent(data))?;
             Ok(())
         } else {
@@ -1067,19 +4504,18 @@ impl SerialInterface {
         }
     }
 
-    /// Write data to the serial line.
+    /// Write data to the serial line, data larger than self.max_tx_frame are rejected before
+    /// transmitting with SIError::FrameTooLarge.
     #[cfg(feature = "async-channel")]
     #[allow(unused)]
     async fn write(&mut self, data: Vec<u8>) -> Result<(), SIError> {
-        log::debug!("write({:?})", data.clone());
+        si_log!(debug, self, "write({:?})", data.clone());
+        if data.len() > self.max_tx_frame {
+            return Err(SIError::FrameTooLarge);
+        }
         let port_open = self.port.is_some();
         if port_open {
-            let buffer = &data[0..data.len()];
-            self.port
-                .as_mut()
-                .unwrap()
-                .write(buffer)
-                .map_err(|_| SIError::CannotWritePort)?;
+            self.write_port(&data).await?;
             self.send_message(SerialMessage::DataSent(data)).await?;
             Ok(())
         } else {
@@ -1088,23 +4524,120 @@ impl SerialInterface {
     }
     
     
+    /// Sniff mode: if the frame received after an injection starts with the injected data, this part is
+    /// the echo of the injection: it's emitted as SerialMessage::Injected(), the remaining (if any) as
+    /// SerialMessage::Receive().
+    fn tag_injected(&mut self, mut data: Vec<u8>) -> Vec<SerialMessage> {
+        match self.injected.take() {
+            Some(injected) if !injected.is_empty() && data.starts_with(&injected) => {
+                let rest = data.split_off(injected.len());
+                let mut msgs = vec![SerialMessage::Injected(data)];
+                if !rest.is_empty() {
+                    msgs.push(self.sniffed(rest));
+                }
+                msgs
+            }
+            _ => vec![self.sniffed(data)],
+        }
+    }
+
+    /// Sniff mode: append a received frame to the capture file if any, see capture_to(). On failure,
+    /// the recording is stopped.
+    fn record(&mut self, data: &[u8]) -> Result<(), SIError> {
+        let Some(path) = &self.capture_path else {
+            return Ok(());
+        };
+        let result = match &mut self.capture {
+            Some(writer) => writer.write_frame(data),
+            None => capture::CaptureWriter::open(path).and_then(|mut writer| {
+                let result = writer.write_frame(data);
+                self.capture = Some(writer);
+                result
+            }),
+        };
+        if let Err(e) = &result {
+            si_log!(error, self, "SerialInterface::record(): {:?}", e);
+            self.capture_path = None;
+            self.capture = None;
+        }
+        result
+    }
+
+    /// Sniff mode: returns the message for a received frame, SerialMessage::ReceiveTimed if
+    /// self.timestamped, SerialMessage::Receive otherwise.
+    fn sniffed(&mut self, data: Vec<u8>) -> SerialMessage {
+        if !self.timestamped {
+            return SerialMessage::Receive(data);
+        }
+        let at = SystemTime::now()
+            - self
+                .last_byte_time
+                .map(|last_byte| last_byte.elapsed())
+                .unwrap_or_default();
+        let direction = self.frame_direction(&data);
+        SerialMessage::ReceiveTimed {
+            data,
+            at,
+            direction,
+        }
+    }
+
+    /// Sniff mode: guess the direction of a frame to/from self.sniff_slave_id, see sniff_slave_id().
+    fn frame_direction(&mut self, data: &[u8]) -> Option<FrameDirection> {
+        let slave = self.sniff_slave_id?;
+        let frame = match self.frame_format {
+            FrameFormat::Rtu => Cow::Borrowed(data),
+            FrameFormat::Ascii => Cow::Owned(Self::decode_ascii(data)?),
+        };
+        if *frame.first()? != slave {
+            return None;
+        }
+        let function = *frame.get(1)?;
+        match self.sniff_request.take() {
+            Some(request) if function & 0x7F == request => Some(FrameDirection::Response),
+            _ => {
+                self.sniff_request = Some(function);
+                Some(FrameDirection::Request)
+            }
+        }
+    }
+
     /// Sniffing feature: listen on serial line and send a SerialMessage::Receive() via mpsc channel for every serial
-    /// request received, for every loop iteration, check if a SerialMessage is arrived via mpsc channel.
-    /// If receive a SerialMessage::Send(), pause listen in order to send message then resume listening.
+    /// request received (frames are delimited by self.sniff_gap, or self.silence if not set), for every loop
+    /// iteration, check if a SerialMessage is arrived via mpsc channel.
+    /// If receive a SerialMessage::Send(), pause listen in order to send message then resume listening, the
+    /// echo of the sent data is emitted as SerialMessage::Injected().
+    /// Nothing is emitted on an idle line: no timeout is passed to self.read_until_silence(), so it never
+    /// returns SerialMessage::NoResponse.
     /// Stop listening if receive SerialMessage::SetMode(Stop). Almost SerialMessage are handled silently by self.read_message().
     #[cfg(not(feature = "async-channel"))]
     #[allow(unused)]
     pub fn listen(&mut self) -> Result<Option<Mode>, SIError> {
         loop {
-            if let Some(silence) = &self.silence.clone() {
+            if let Some(silence) = &self.sniff_gap.or(self.silence) {
                 // log::debug!("silence={:?}", silence);
                 self.status = Status::Read;
+                // no timeout: only returns on a frame or a message
                 if let Some(msg) = self.read_until_silence(silence)? {
                     match msg {
+                        SerialMessage::Receive(data) => {
+                            if let Err(e) = self.record(&data) {
+                                self.send_message(SerialMessage::Error(e))?;
+                            }
+                            for msg in self.tag_injected(data) {
+                                self.send_message(msg)?;
+                            }
+                            self.discard_post_frame()?;
+                            self.status = Status::None;
+                            return Ok(None);
+                        }
                         SerialMessage::Send(data) => {
                             self.status = Status::Write;
-                            let write = self.write(data);
+                            let write = self.write(data.clone());
                             self.status = Status::None;
+                            if write.is_ok() {
+                                self.injected = Some(data);
+                            }
                             if let Err(e) = write {
                                 self.send_message(SerialMessage::Error(e))?;
                             }
@@ -1132,22 +4665,41 @@ impl SerialInterface {
     }
 
     /// Sniffing feature: listen on serial line and send a SerialMessage::Receive() via mpsc channel for every serial
-    /// request received, for every loop iteration, check if a SerialMessage is arrived via mpsc channel.
-    /// If receive a SerialMessage::Send(), pause listen in order to send message then resume listening.
+    /// request received (frames are delimited by self.sniff_gap, or self.silence if not set), for every loop
+    /// iteration, check if a SerialMessage is arrived via mpsc channel.
+    /// If receive a SerialMessage::Send(), pause listen in order to send message then resume listening, the
+    /// echo of the sent data is emitted as SerialMessage::Injected().
+    /// Nothing is emitted on an idle line: no timeout is passed to self.read_until_silence(), so it never
+    /// returns SerialMessage::NoResponse.
     /// Stop listening if receive SerialMessage::SetMode(Stop). Almost SerialMessage are handled silently by self.read_message().
     #[cfg(feature = "async-channel")]
     #[allow(unused)]
     pub async fn listen(&mut self) -> Result<Option<Mode>, SIError> {
         loop {
-            if let Some(silence) = &self.silence.clone() {
-                log::debug!("silence={:?}", silence);
+            if let Some(silence) = &self.sniff_gap.or(self.silence) {
+                si_log!(debug, self, "silence={:?}", silence);
                 self.status = Status::Read;
+                // no timeout: only returns on a frame or a message
                 if let Some(msg) = self.read_until_silence(silence).await? {
                     match msg {
+                        SerialMessage::Receive(data) => {
+                            if let Err(e) = self.record(&data) {
+                                self.send_message(SerialMessage::Error(e)).await?;
+                            }
+                            for msg in self.tag_injected(data) {
+                                self.send_message(msg).await?;
+                            }
+                            self.discard_post_frame()?;
+                            self.status = Status::None;
+                            return Ok(None);
+                        }
                         SerialMessage::Send(data) => {
                             self.status = Status::Write;
-                            let write = self.write(data).await;
+                            let write = self.write(data.clone()).await;
                             self.status = Status::None;
+                            if write.is_ok() {
+                                self.injected = Some(data);
+                            }
                             if let Err(e) = write {
                                 self.send_message(SerialMessage::Error(e)).await?;
                             }
@@ -1175,8 +4727,157 @@ impl SerialInterface {
         }
     }
 
-    
+    /// Read and discard the bytes received within self.post_frame_guard, see post_frame_guard().
+    fn discard_post_frame(&mut self) -> Result<(), SIError> {
+        let start = Instant::now();
+        let mut discarded = 0;
+        while Instant::now().duration_since(start) < self.post_frame_guard {
+            if self.read_byte_blocking()?.is_some() {
+                discarded += 1;
+            }
+        }
+        if discarded > 0 {
+            si_log!(debug, self, "SerialInterface::listen(): {} bytes discarded after frame", discarded);
+        }
+        Ok(())
+    }
+
+    /// Apply the receive transform, if any, to a received payload.
+    fn transform(&self, data: Vec<u8>) -> Vec<u8> {
+        match &self.receive_transform {
+            Some(transform) => transform(data),
+            None => data,
+        }
+    }
+
+    /// Call the metrics hook, if any.
+    fn metric(&self, event: MetricEvent) {
+        if let Some(hook) = &self.metrics_hook {
+            hook(event);
+        }
+    }
+
+    /// Returns whether a response is a CRC-valid frame from another slave id than the request one,
+    /// if check_response_id is enabled.
+    fn is_foreign_response(&self, request: &[u8], response: &[u8]) -> bool {
+        self.check_response_id
+            && self.check_frame(response)
+            && self.frame_id(request).is_some_and(|id| self.frame_id(response) != Some(id))
+    }
+
+    /// Returns the timeout of a transaction attempt, capped to the time left before the deadline.
+    fn attempt_timeout(timeout: &Duration, deadline: Option<Instant>) -> Duration {
+        match deadline {
+            Some(deadline) => (*timeout).min(deadline.saturating_duration_since(Instant::now())),
+            None => *timeout,
+        }
+    }
+
+    /// Returns whether a transaction can be retried after `attempt` retries.
+    fn retry_allowed(&self, trigger: RetryTrigger, attempt: u8, deadline: Option<Instant>) -> bool {
+        self.retry_on.contains(trigger)
+            && attempt < self.retries
+            && deadline.is_none_or(|deadline| Instant::now() < deadline)
+    }
+
+    /// Returns the trigger of a retry for the response of a master transaction, if it's a failure.
+    fn failure(&self, msg: &SerialMessage) -> Option<RetryTrigger> {
+        match msg {
+            SerialMessage::NoResponse { .. } => Some(RetryTrigger::TIMEOUT),
+            SerialMessage::Receive(data) if !self.check_frame(data) => {
+                Some(RetryTrigger::CRC_ERROR)
+            }
+            _ => None,
+        }
+    }
+
+    /// Track the consecutive timeouts of the slave of a master transaction, returns
+    /// SerialMessage::SlaveOffline() when the offline threshold is reached, and
+    /// SerialMessage::SlaveOnline() when an offline slave responds again.
+    fn slave_health(&mut self, request: &[u8], msg: &SerialMessage) -> Option<SerialMessage> {
+        if self.offline_threshold == 0 {
+            return None;
+        }
+        let id = self.frame_id(request)?;
+        match msg {
+            SerialMessage::NoResponse { .. } => {
+                let count = self.timeouts.entry(id).or_insert(0);
+                *count = count.saturating_add(1);
+                (*count == self.offline_threshold).then_some(SerialMessage::SlaveOffline(id))
+            }
+            SerialMessage::Receive(_) => {
+                let count = self.timeouts.remove(&id).unwrap_or(0);
+                (count >= self.offline_threshold).then_some(SerialMessage::SlaveOnline(id))
+            }
+            _ => None,
+        }
+    }
+
+    /// Tag the response (or NoResponse) of a master transaction with the id of its request, if it has
+    /// been sent with SerialMessage::SendWithId().
+    fn with_transaction_id(&mut self, msg: SerialMessage) -> SerialMessage {
+        match (self.transaction_id.take(), msg) {
+            (Some(id), SerialMessage::Receive(data)) => SerialMessage::ReceiveWithId { id, data },
+            (Some(id), SerialMessage::NoResponse { .. }) => SerialMessage::NoResponseWithId { id },
+            (_, msg) => msg,
+        }
+    }
+
+    /// Forward the response (or NoResponse) of a master transaction.
+    #[cfg(not(feature = "async-channel"))]
+    fn end_transaction(
+        &mut self,
+        msg: SerialMessage,
+        request: &[u8],
+        start: Instant,
+    ) -> Result<(), SIError> {
+        let msg = Self::with_request(msg, request);
+        if self.log_transactions {
+            self.log_transaction(request, &msg, start);
+        }
+        self.status = Status::None;
+        let health = self.slave_health(request, &msg);
+        let msg = self.with_transaction_id(msg);
+        self.send_message(msg)?;
+        if let Some(health) = health {
+            self.send_message(health)?;
+        }
+        Ok(())
+    }
+
+    /// Forward the response (or NoResponse) of a master transaction.
+    #[cfg(feature = "async-channel")]
+    async fn end_transaction(
+        &mut self,
+        msg: SerialMessage,
+        request: &[u8],
+        start: Instant,
+    ) -> Result<(), SIError> {
+        let msg = Self::with_request(msg, request);
+        if self.log_transactions {
+            self.log_transaction(request, &msg, start);
+        }
+        self.status = Status::None;
+        let health = self.slave_health(request, &msg);
+        let msg = self.with_transaction_id(msg);
+        match self.oneshot.take() {
+            // direct-call transaction, see write_read_oneshot()
+            Some(oneshot) => {
+                let _ = oneshot.send(msg);
+            }
+            None => self.send_message(msg).await?,
+        }
+        if let Some(health) = health {
+            self.send_message(health).await?;
+        }
+        Ok(())
+    }
+
     /// Master feature: write a request, then wait for response, when response received, stop listening.
+    /// If a length field is configured, the response is read with self.read_with_length_field() instead of
+    /// waiting for silence.
+    /// If no response is received (or on another failure in self.retry_on), the request is
+    /// retransmitted up to self.retries times, as long as `deadline` (if any) has not passed.
     /// Returns early if receive SerialMessage::SetMode(Mode::Stop)). Does not accept SerialMessage::Send() as
     /// we already waiting for a response. Almost SerialMessage are handled silently by self.read_message().
     #[cfg(not(feature = "async-channel"))]
@@ -1185,43 +4886,294 @@ impl SerialInterface {
         &mut self,
         data: Vec<u8>,
         timeout: &Duration,
+        deadline: Option<Instant>,
     ) -> Result<Option<SerialMessage>, SIError> {
         if let Some(silence) = &self.silence.clone() {
-            self.status = Status::Write;
-            if let Err(e) = self.write(data) {
-                self.status = Status::None;
-                return Err(e);
-            } else {
-                self.status = Status::WaitingResponse;
+            let request = data.clone();
+            let start = Instant::now();
+            let mut attempt = 0;
+            'attempts: loop {
+                let timeout = &Self::attempt_timeout(timeout, deadline);
+                self.status = Status::Write;
+                let write = if attempt == 0 {
+                    self.write(data.clone())
+                } else {
+                    self.retransmit(&data, attempt)
+                };
+                if let Err(e) = write {
+                    self.status = Status::None;
+                    if let SIError::FrameTooLarge = e {
+                        // rejected before transmitting, the transaction is just dropped
+                        self.send_message(SerialMessage::Error(e))?;
+                        return Ok(None);
+                    }
+                    return Err(e);
+                } else {
+                    self.status = Status::WaitingResponse;
+                }
+                if !self.turnaround_delay.is_zero() {
+                    std::thread::sleep(self.turnaround_delay);
+                }
+
+                if let Some(field) = self.length_field {
+                    let msg = self.read_with_length_field(
+                        field.header_len,
+                        field.index,
+                        field.trailer_len,
+                        timeout,
+                    )?;
+                    if let Some(trigger) = self.failure(&msg) {
+                        if self.retry_allowed(trigger, attempt, deadline) {
+                            attempt += 1;
+                            si_log!(
+                                debug,
+                                self,
+                                "SerialInterface::write_read(): {:?}, retry {}",
+                                trigger,
+                                attempt
+                            );
+                            continue 'attempts;
+                        }
+                    }
+                    self.end_transaction(msg, &request, start)?;
+                    return Ok(None);
+                }
+
+                let read_start = Instant::now();
+                loop {
+                    // a foreign frame or a message does not extend the attempt
+                    let remaining = timeout.saturating_sub(read_start.elapsed());
+                    if let Some(msg) = self.read_until_silence_or_timeout(silence, &remaining)? {
+                        match msg {
+                            SerialMessage::Receive(data) if self.is_foreign_response(&request, &data) => {
+                                si_log!(debug, self, "SerialInterface::write_read(): foreign response {:?}", data);
+                                self.send_message(SerialMessage::WrongSlaveResponse {
+                                    expected: self.frame_id(&request).unwrap_or_default(),
+                                    got: self.frame_id(&data).unwrap_or_default(),
+                                })?;
+                                if self.retry_allowed(RetryTrigger::WRONG_SLAVE, attempt, deadline) {
+                                    attempt += 1;
+                                    si_log!(
+                                        debug,
+                                        self,
+                                        "SerialInterface::write_read(): wrong slave, retry {}",
+                                        attempt
+                                    );
+                                    continue 'attempts;
+                                }
+                                continue;
+                            }
+                            SerialMessage::Receive(_) | SerialMessage::NoResponse { .. }
+                                if self.failure(&msg).is_some_and(|trigger| {
+                                    self.retry_allowed(trigger, attempt, deadline)
+                                }) =>
+                            {
+                                attempt += 1;
+                                if let SerialMessage::Receive(_) = msg {
+                                    self.metric(MetricEvent::CrcError);
+                                }
+                                si_log!(
+                                    debug,
+                                    self,
+                                    "SerialInterface::write_read(): {:?}, retry {}",
+                                    msg,
+                                    attempt
+                                );
+                                continue 'attempts;
+                            }
+                            SerialMessage::Receive(_) | SerialMessage::NoResponse { .. } => {
+                                self.end_transaction(msg, &request, start)?;
+                                return Ok(None);
+                            }
+                            SerialMessage::Send(_)
+                            | SerialMessage::SendWithId { .. }
+                            | SerialMessage::SendWithTimeout { .. }
+                            | SerialMessage::SendNoResponse(_) => {
+                                if self.schedule == Schedule::RoundRobinBySlave {
+                                    // will be served after the current transaction
+                                    self.requests.extend(Self::queued_request(msg));
+                                } else {
+                                    // we already waiting for response cannot send request now.
+                                    self.send_message(SerialMessage::Error(SIError::WaitingForResponse))?;
+                                }
+                                continue;
+                            }
+                            SerialMessage::SetMode(mode) => {
+                                if mode == Mode::Stop {
+                                    self.status = Status::None;
+                                    return Ok(Some(SerialMessage::SetMode(Mode::Stop)));
+                                } else if mode == Mode::Slave || mode == Mode::Sniff {
+                                    self.send_message(SerialMessage::Error(
+                                        SIError::StopModeBeforeChange,
+                                    ))?;
+                                    continue;
+                                }
+                            }
+                            _ => {
+                                continue;
+                            }
+                        }
+                    } else {
+                        // Stop after silence or timeout, return
+                        self.status = Status::None;
+                        return Ok(None);
+                    }
+                }
             }
+        } else {
+            Err(SIError::SilenceMissing)
+        }
+    }
 
-            loop {
-                if let Some(msg) = self.read_until_silence_or_timeout(silence, timeout)? {
-                    match msg {
-                        SerialMessage::Send(_data) => {
-                            // we already waiting for response cannot send request now.
-                            self.send_message(SerialMessage::Error(SIError::WaitingForResponse))?;
-                            continue;
+    /// Master feature: write a request, then wait for response, when response received, stop listening.
+    /// If a length field is configured, the response is read with self.read_with_length_field() instead of
+    /// waiting for silence.
+    /// If no response is received (or on another failure in self.retry_on), the request is
+    /// retransmitted up to self.retries times, as long as `deadline` (if any) has not passed.
+    /// Returns early if receive SerialMessage::SetMode(Mode::Stop)). Does not accept SerialMessage::Send() as
+    /// we already waiting for a response. Almost SerialMessage are handled silently by self.read_message().
+    #[cfg(feature = "async-channel")]
+    #[allow(unused)]
+    pub async fn write_read(
+        &mut self,
+        data: Vec<u8>,
+        timeout: &Duration,
+        deadline: Option<Instant>,
+    ) -> Result<Option<SerialMessage>, SIError> {
+        if let Some(silence) = &self.silence.clone() {
+            let request = data.clone();
+            let start = Instant::now();
+            let mut attempt = 0;
+            'attempts: loop {
+                let timeout = &Self::attempt_timeout(timeout, deadline);
+                self.status = Status::Write;
+                let write = if attempt == 0 {
+                    self.write(data.clone()).await
+                } else {
+                    self.retransmit(&data, attempt).await
+                };
+                if let Err(e) = write {
+                    self.status = Status::None;
+                    if let SIError::FrameTooLarge = e {
+                        // rejected before transmitting, the transaction is just dropped
+                        self.send_message(SerialMessage::Error(e)).await?;
+                        return Ok(None);
+                    }
+                    return Err(e);
+                } else {
+                    self.status = Status::WaitingResponse;
+                }
+                if !self.turnaround_delay.is_zero() {
+                    sleep(self.turnaround_delay).await;
+                }
+
+                if let Some(field) = self.length_field {
+                    let msg = self.read_with_length_field(
+                        field.header_len,
+                        field.index,
+                        field.trailer_len,
+                        timeout,
+                    )?;
+                    if let Some(trigger) = self.failure(&msg) {
+                        if self.retry_allowed(trigger, attempt, deadline) {
+                            attempt += 1;
+                            si_log!(
+                                debug,
+                                self,
+                                "SerialInterface::write_read(): {:?}, retry {}",
+                                trigger,
+                                attempt
+                            );
+                            continue 'attempts;
                         }
-                        SerialMessage::SetMode(mode) => {
-                            if mode == Mode::Stop {
-                                self.status = Status::None;
-                                return Ok(Some(SerialMessage::SetMode(Mode::Stop)));
-                            } else if mode == Mode::Slave || mode == Mode::Sniff {
-                                self.send_message(SerialMessage::Error(
-                                    SIError::StopModeBeforeChange,
-                                ))?;
+                    }
+                    self.end_transaction(msg, &request, start).await?;
+                    return Ok(None);
+                }
+
+                let read_start = Instant::now();
+                loop {
+                    // a foreign frame or a message does not extend the attempt
+                    let remaining = timeout.saturating_sub(read_start.elapsed());
+                    if let Some(msg) =
+                        self.read_until_silence_or_timeout(silence, &remaining).await?
+                    {
+                        match msg {
+                            SerialMessage::Receive(data) if self.is_foreign_response(&request, &data) => {
+                                si_log!(debug, self, "SerialInterface::write_read(): foreign response {:?}", data);
+                                self.send_message(SerialMessage::WrongSlaveResponse {
+                                    expected: self.frame_id(&request).unwrap_or_default(),
+                                    got: self.frame_id(&data).unwrap_or_default(),
+                                }).await?;
+                                if self.retry_allowed(RetryTrigger::WRONG_SLAVE, attempt, deadline) {
+                                    attempt += 1;
+                                    si_log!(
+                                        debug,
+                                        self,
+                                        "SerialInterface::write_read(): wrong slave, retry {}",
+                                        attempt
+                                    );
+                                    continue 'attempts;
+                                }
+                                continue;
+                            }
+                            SerialMessage::Receive(_) | SerialMessage::NoResponse { .. }
+                                if self.failure(&msg).is_some_and(|trigger| {
+                                    self.retry_allowed(trigger, attempt, deadline)
+                                }) =>
+                            {
+                                attempt += 1;
+                                if let SerialMessage::Receive(_) = msg {
+                                    self.metric(MetricEvent::CrcError);
+                                }
+                                si_log!(
+                                    debug,
+                                    self,
+                                    "SerialInterface::write_read(): {:?}, retry {}",
+                                    msg,
+                                    attempt
+                                );
+                                continue 'attempts;
+                            }
+                            SerialMessage::Receive(_) | SerialMessage::NoResponse { .. } => {
+                                self.end_transaction(msg, &request, start).await?;
+                                return Ok(None);
+                            }
+                            SerialMessage::Send(_)
+                            | SerialMessage::SendWithId { .. }
+                            | SerialMessage::SendWithTimeout { .. }
+                            | SerialMessage::SendNoResponse(_) => {
+                                if self.schedule == Schedule::RoundRobinBySlave {
+                                    // will be served after the current transaction
+                                    self.requests.extend(Self::queued_request(msg));
+                                } else {
+                                    // we already waiting for response cannot send request now.
+                                    self.send_message(SerialMessage::Error(SIError::WaitingForResponse))
+                                        .await?;
+                                }
+                                continue;
+                            }
+                            SerialMessage::SetMode(mode) => {
+                                if mode == Mode::Stop {
+                                    self.status = Status::None;
+                                    return Ok(Some(SerialMessage::SetMode(Mode::Stop)));
+                                } else if mode == Mode::Slave || mode == Mode::Sniff {
+                                    self.send_message(SerialMessage::Error(
+                                        SIError::StopModeBeforeChange,
+                                    ))
+                                        .await?;
+                                    continue;
+                                }
+                            }
+                            _ => {
                                 continue;
                             }
                         }
-                        _ => {
-                            continue;
-                        }
+                    } else {
+                        // Stop after silence or timeout, return
+                        self.status = Status::None;
+                        return Ok(None);
                     }
-                } else {
-                    // Stop after silence or timeout, return
-                    self.status = Status::None;
-                    return Ok(None);
                 }
             }
         } else {
@@ -1229,59 +5181,44 @@ impl SerialInterface {
         }
     }
 
-    /// Master feature: write a request, then wait for response, when response received, stop listening.
-    /// Returns early if receive SerialMessage::SetMode(Mode::Stop)). Does not accept SerialMessage::Send() as
-    /// we already waiting for a response. Almost SerialMessage are handled silently by self.read_message().
+    /// Direct-call master transaction: write a request and wait for its response as write_read() does
+    /// (retries and deadline included), but the response is returned instead of being sent on
+    /// self.sender, so other consumers of the channel don't see it. Other messages emitted during the
+    /// transaction (Error, WrongSlaveResponse, SlaveOffline/SlaveOnline...) are still sent on self.sender,
+    /// and a SetMode(Stop) received meanwhile is kept to be handled by the main loop.
+    /// Returns Ok(Some(response)), Ok(None) if no response before timeout, SIError::FrameTooLarge if
+    /// the request exceeds self.max_tx_frame.
     #[cfg(feature = "async-channel")]
-    #[allow(unused)]
-    pub async fn write_read(
+    pub async fn write_read_oneshot(
         &mut self,
         data: Vec<u8>,
-        timeout: &Duration,
-    ) -> Result<Option<SerialMessage>, SIError> {
-        if let Some(silence) = &self.silence.clone() {
-            self.status = Status::Write;
-            if let Err(e) = self.write(data).await {
-                self.status = Status::None;
-                return Err(e);
-            } else {
-                self.status = Status::WaitingResponse;
+        timeout: Duration,
+    ) -> Result<Option<Vec<u8>>, SIError> {
+        if data.len() > self.max_tx_frame {
+            return Err(SIError::FrameTooLarge);
+        }
+        let (sender, mut receiver) = tokio::sync::oneshot::channel();
+        self.oneshot = Some(sender);
+        self.transaction_id = None;
+        let deadline = self.deadline.map(|deadline| Instant::now() + deadline);
+        let result = self.write_read(data, &timeout, deadline).await;
+        self.oneshot = None;
+        if let Some(msg) = result? {
+            self.pending.push_back(msg);
+        }
+        Ok(match receiver.try_recv() {
+            Ok(SerialMessage::Receive(data))
+            | Ok(SerialMessage::ReceiveWithChecksum { data, .. })
+            | Ok(SerialMessage::ReceiveWithId { data, .. }) => {
+                self.metric(MetricEvent::FrameReceived(data.len()));
+                Some(self.transform(data))
             }
-
-            loop {
-                if let Some(msg) = self.read_until_silence_or_timeout(silence, timeout).await? {
-                    match msg {
-                        SerialMessage::Send(_data) => {
-                            // we already waiting for response cannot send request now.
-                            self.send_message(SerialMessage::Error(SIError::WaitingForResponse))
-                                .await?;
-                            continue;
-                        }
-                        SerialMessage::SetMode(mode) => {
-                            if mode == Mode::Stop {
-                                self.status = Status::None;
-                                return Ok(Some(SerialMessage::SetMode(Mode::Stop)));
-                            } else if mode == Mode::Slave || mode == Mode::Sniff {
-                                self.send_message(SerialMessage::Error(
-                                    SIError::StopModeBeforeChange,
-                                ))
-                                    .await?;
-                                continue;
-                            }
-                        }
-                        _ => {
-                            continue;
-                        }
-                    }
-                } else {
-                    // Stop after silence or timeout, return
-                    self.status = Status::None;
-                    return Ok(None);
-                }
+            Ok(SerialMessage::NoResponse { .. }) | Ok(SerialMessage::NoResponseWithId { .. }) => {
+                self.metric(MetricEvent::Timeout);
+                None
             }
-        } else {
-            Err(SIError::SilenceMissing)
-        }
+            _ => None,
+        })
     }
 
 
@@ -1295,18 +5232,29 @@ impl SerialInterface {
         data: Vec<u8>,
         timeout: &Duration,
     ) -> Result<(), SIError> {
-
+        let request = data.clone();
+        let start = Instant::now();
         self.status = Status::Write;
         if let Err(e) = self.write(data) {
             self.status = Status::None;
+            if let SIError::FrameTooLarge = e {
+                // rejected before transmitting, the transaction is just dropped
+                return self.send_message(SerialMessage::Error(e));
+            }
             return Err(e);
         } else {
             self.status = Status::WaitingResponse;
         }
         match self.read_stream(timeout) {
             Ok(msg) => {
-                self.send_message(msg);
-                self.status = Status::None;
+                if let Some(discarded) = self.discarded.take() {
+                    let _ = self.send_message(SerialMessage::Discarded(discarded));
+                }
+                if let Some(buffer) = self.crc_error.take() {
+                    self.metric(MetricEvent::CrcError);
+                    let _ = self.send_message(SerialMessage::CrcError(buffer));
+                }
+                let _ = self.end_transaction(msg, &request, start);
                 Ok(())
             }
             Err(e) => {
@@ -1326,18 +5274,29 @@ impl SerialInterface {
         data: Vec<u8>,
         timeout: &Duration,
     ) -> Result<(), SIError> {
-
+        let request = data.clone();
+        let start = Instant::now();
         self.status = Status::Write;
         if let Err(e) = self.write(data).await {
             self.status = Status::None;
+            if let SIError::FrameTooLarge = e {
+                // rejected before transmitting, the transaction is just dropped
+                return self.send_message(SerialMessage::Error(e)).await;
+            }
             return Err(e);
         } else {
             self.status = Status::WaitingResponse;
         }
         match self.read_stream(timeout) {
             Ok(msg) => {
-                self.send_message(msg);
-                self.status = Status::None;
+                if let Some(discarded) = self.discarded.take() {
+                    let _ = self.send_message(SerialMessage::Discarded(discarded)).await;
+                }
+                if let Some(buffer) = self.crc_error.take() {
+                    self.metric(MetricEvent::CrcError);
+                    let _ = self.send_message(SerialMessage::CrcError(buffer)).await;
+                }
+                let _ = self.end_transaction(msg, &request, start).await;
                 Ok(())
             }
             Err(e) => {
@@ -1367,6 +5326,17 @@ impl SerialInterface {
                 };
                 if let Some(msg) = read {
                     match msg {
+                        SerialMessage::Receive(data) => {
+                            if self.accepts_frame(&data) {
+                                self.send_message(SerialMessage::Receive(data))?;
+                                return Ok(None);
+                            }
+                            if !self.check_frame(&data) {
+                                self.metric(MetricEvent::CrcError);
+                            }
+                            si_log!(debug, self, "SerialInterface::wait_for_request(): drop {:?}", data);
+                            continue;
+                        }
                         SerialMessage::Send(data) => {
                             return Ok(Some(SerialMessage::Send(data.clone())));
                         }
@@ -1412,6 +5382,17 @@ impl SerialInterface {
                 };
                 if let Some(msg) = read {
                     match msg {
+                        SerialMessage::Receive(data) => {
+                            if self.accepts_frame(&data) {
+                                self.send_message(SerialMessage::Receive(data)).await?;
+                                return Ok(None);
+                            }
+                            if !self.check_frame(&data) {
+                                self.metric(MetricEvent::CrcError);
+                            }
+                            si_log!(debug, self, "SerialInterface::wait_for_request(): drop {:?}", data);
+                            continue;
+                        }
                         SerialMessage::Send(data) => {
                             return Ok(Some(SerialMessage::Send(data.clone())));
                         }
@@ -1440,41 +5421,180 @@ impl SerialInterface {
     }
 
     
+    /// Master feature: write a request not expecting a response (e.g. a broadcast), then wait the
+    /// inter-frame silence only, so the next request is not sent too early, see
+    /// SerialMessage::SendNoResponse.
+    #[cfg(not(feature = "async-channel"))]
+    fn write_only(&mut self, data: Vec<u8>) -> Result<(), SIError> {
+        self.status = Status::Write;
+        let result = self.write(data);
+        self.status = Status::None;
+        match result {
+            // rejected before transmitting, the request is just dropped
+            Err(SIError::FrameTooLarge) => {
+                self.send_message(SerialMessage::Error(SIError::FrameTooLarge))
+            }
+            Err(e) => Err(e),
+            Ok(()) => {
+                std::thread::sleep(self.silence.unwrap_or_default());
+                Ok(())
+            }
+        }
+    }
+
+    /// Master feature: write a request not expecting a response (e.g. a broadcast), then wait the
+    /// inter-frame silence only, so the next request is not sent too early, see
+    /// SerialMessage::SendNoResponse.
+    #[cfg(feature = "async-channel")]
+    async fn write_only(&mut self, data: Vec<u8>) -> Result<(), SIError> {
+        self.status = Status::Write;
+        let result = self.write(data).await;
+        self.status = Status::None;
+        match result {
+            // rejected before transmitting, the request is just dropped
+            Err(SIError::FrameTooLarge) => {
+                self.send_message(SerialMessage::Error(SIError::FrameTooLarge))
+                    .await
+            }
+            Err(e) => Err(e),
+            Ok(()) => {
+                sleep(self.silence.unwrap_or_default()).await;
+                Ok(())
+            }
+        }
+    }
+
+    /// Returns the request (and its id, see SerialMessage::SendWithId) to be served now, or queue it
+    /// if the schedule policy need it.
+    fn schedule_request(&mut self, request: Request) -> Option<Request> {
+        match self.schedule {
+            Schedule::Fifo => Some(request),
+            Schedule::RoundRobinBySlave => {
+                self.requests.push_back(request);
+                None
+            }
+        }
+    }
+
+    /// Returns the request carried by a SerialMessage::Send(), SerialMessage::SendWithId(),
+    /// SerialMessage::SendWithTimeout() or SerialMessage::SendNoResponse().
+    fn queued_request(msg: SerialMessage) -> Option<Request> {
+        let (id, data, timeout, response) = match msg {
+            SerialMessage::Send(data) => (None, data, None, true),
+            SerialMessage::SendWithId { id, data } => (Some(id), data, None, true),
+            SerialMessage::SendWithTimeout { data, timeout } => (None, data, Some(timeout), true),
+            SerialMessage::SendNoResponse(data) => (None, data, None, false),
+            _ => return None,
+        };
+        Some(Request {
+            id,
+            data,
+            timeout,
+            response,
+        })
+    }
+
+    /// Pop the next queued request: the oldest request of the first slave id following the
+    /// last served one (in cyclic order).
+    fn next_request(&mut self) -> Option<Request> {
+        let last = self.last_slave;
+        let index = (0..self.requests.len()).min_by_key(|&i| {
+            let id = self.requests[i].data.first().copied().unwrap_or(0) as u16;
+            match last {
+                Some(last) if id <= last as u16 => (id + 256, i),
+                _ => (id, i),
+            }
+        })?;
+        let request = self.requests.remove(index)?;
+        self.last_slave = request.data.first().copied();
+        Some(request)
+    }
+
+    /// Answer the requests still queued when leaving a master mode (see Schedule::RoundRobinBySlave)
+    /// with SIError::RequestDropped, so the app doesn't wait for their response forever.
+    #[cfg(not(feature = "async-channel"))]
+    fn reject_queued(&mut self) {
+        for request in std::mem::take(&mut self.requests) {
+            si_log!(warn, self, "SerialInterface::reject_queued(): drop {:?}", request.data);
+            let _ =
+                self.send_message(SerialMessage::Error(SIError::RequestDropped(request.data)));
+        }
+    }
+
+    /// Answer the requests still queued when leaving a master mode (see Schedule::RoundRobinBySlave)
+    /// with SIError::RequestDropped, so the app doesn't wait for their response forever.
+    #[cfg(feature = "async-channel")]
+    async fn reject_queued(&mut self) {
+        for request in std::mem::take(&mut self.requests) {
+            si_log!(warn, self, "SerialInterface::reject_queued(): drop {:?}", request.data);
+            let _ = self
+                .send_message(SerialMessage::Error(SIError::RequestDropped(request.data)))
+                .await;
+        }
+    }
+
     /// Master loop
     #[cfg(not(feature = "async-channel"))]
     #[allow(unused)]
     fn run_master(&mut self) -> Result<Option<Mode>, SIError> {
-        log::debug!("SerialInterface::run_master()");
+        si_log!(debug, self, "SerialInterface::run_master()");
+        self.requests.clear();
         loop {
             match self.read_message() {
                 Ok(msg) => {
-                    if let Some(msg) = msg {
-                        match msg {
-                            SerialMessage::SetMode(mode) => {
-                                if mode == Mode::Stop {
+                    let request = match msg {
+                        Some(SerialMessage::SetMode(mode)) => {
+                            if mode == Mode::Stop {
+                                return Ok(Some(Mode::Stop));
+                            }
+                            None
+                        }
+                        Some(msg @ (SerialMessage::Send(_)
+                        | SerialMessage::SendWithId { .. }
+                        | SerialMessage::SendWithTimeout { .. }
+                        | SerialMessage::SendNoResponse(_))) => {
+                            Self::queued_request(msg)
+                                .and_then(|request| self.schedule_request(request))
+                        }
+                        Some(_) => continue,
+                        None => self.next_request(),
+                    };
+                    if request.is_none() {
+                        self.idle_backoff();
+                    }
+                    if let Some(request) = request {
+                        self.transaction_id = request.id;
+                        let timeout = request.timeout.unwrap_or(self.timeout);
+                        let deadline = self.deadline.map(|deadline| Instant::now() + deadline);
+                        let result = if request.response {
+                            self.write_read(request.data, &timeout, deadline)
+                        } else {
+                            self.write_only(request.data).map(|_| None)
+                        };
+                        match result {
+                            Ok(msg) => {
+                                if let Some(SerialMessage::SetMode(Mode::Stop)) = msg {
                                     return Ok(Some(Mode::Stop));
                                 }
                             }
-                            SerialMessage::Send(data) => {
-                                match self.write_read(data, &self.timeout.clone()) {
-                                    Ok(msg) => {
-                                        if let Some(SerialMessage::SetMode(Mode::Stop)) = msg {
-                                            return Ok(Some(Mode::Stop));
-                                        }
-                                    }
-                                    Err(e) => {
-                                        log::error!("{:?}", e);
+                            Err(e) => {
+                                si_log!(error, self, "{:?}", e);
+                                if self.should_reconnect(&e) {
+                                    if let Some(Mode::Stop) = self.recover()? {
+                                        return Ok(Some(Mode::Stop));
                                     }
                                 }
                             }
-                            _ => {
-                                continue;
-                            }
                         }
                     }
                 }
                 Err(e) => {
-                    log::error!("{:?}", e);
+                    si_log!(error, self, "{:?}", e);
+                    if self.should_reconnect(&e) {
+                        if let Some(Mode::Stop) = self.recover()? {
+                            return Ok(Some(Mode::Stop));
+                        }
+                    }
                 }
             }
         }
@@ -1484,37 +5604,64 @@ impl SerialInterface {
     #[cfg(feature = "async-channel")]
     #[allow(unused)]
     async fn run_master(&mut self) -> Result<Option<Mode>, SIError> {
-        log::debug!("SerialInterface::run_master()");
+        si_log!(debug, self, "SerialInterface::run_master()");
+        self.requests.clear();
         loop {
             match self.read_message().await {
                 Ok(msg) => {
-                    if let Some(msg) = msg {
-                        match msg {
-                            SerialMessage::SetMode(mode) => {
-                                if mode == Mode::Stop {
+                    let request = match msg {
+                        Some(SerialMessage::SetMode(mode)) => {
+                            if mode == Mode::Stop {
+                                return Ok(Some(Mode::Stop));
+                            }
+                            None
+                        }
+                        Some(msg @ (SerialMessage::Send(_)
+                        | SerialMessage::SendWithId { .. }
+                        | SerialMessage::SendWithTimeout { .. }
+                        | SerialMessage::SendNoResponse(_))) => {
+                            Self::queued_request(msg)
+                                .and_then(|request| self.schedule_request(request))
+                        }
+                        Some(_) => continue,
+                        None => self.next_request(),
+                    };
+                    if request.is_none() {
+                        self.idle_backoff().await;
+                    }
+                    if let Some(request) = request {
+                        self.transaction_id = request.id;
+                        let timeout = request.timeout.unwrap_or(self.timeout);
+                        let deadline = self.deadline.map(|deadline| Instant::now() + deadline);
+                        let result = if request.response {
+                            self.write_read(request.data, &timeout, deadline).await
+                        } else {
+                            self.write_only(request.data).await.map(|_| None)
+                        };
+                        match result {
+                            Ok(msg) => {
+                                if let Some(SerialMessage::SetMode(Mode::Stop)) = msg {
                                     return Ok(Some(Mode::Stop));
                                 }
                             }
-                            SerialMessage::Send(data) => {
-                                match self.write_read(data, &self.timeout.clone()).await {
-                                    Ok(msg) => {
-                                        if let Some(SerialMessage::SetMode(Mode::Stop)) = msg {
-                                            return Ok(Some(Mode::Stop));
-                                        }
-                                    }
-                                    Err(e) => {
-                                        log::error!("{:?}", e);
+                            Err(e) => {
+                                si_log!(error, self, "{:?}", e);
+                                if self.should_reconnect(&e) {
+                                    if let Some(Mode::Stop) = self.recover().await? {
+                                        return Ok(Some(Mode::Stop));
                                     }
                                 }
                             }
-                            _ => {
-                                continue;
-                            }
                         }
                     }
                 }
                 Err(e) => {
-                    log::error!("{:?}", e);
+                    si_log!(error, self, "{:?}", e);
+                    if self.should_reconnect(&e) {
+                        if let Some(Mode::Stop) = self.recover().await? {
+                            return Ok(Some(Mode::Stop));
+                        }
+                    }
                 }
             }
         }
@@ -1526,30 +5673,56 @@ impl SerialInterface {
     #[cfg(not(feature = "async-channel"))]
     #[allow(unused)]
     fn run_master_stream(&mut self) -> Result<Option<Mode>, SIError> {
-        log::debug!("SerialInterface::run_master_stream()");
+        si_log!(debug, self, "SerialInterface::run_master_stream()");
+        self.requests.clear();
         loop {
             match self.read_message() {
                 Ok(msg) => {
-                    if let Some(msg) = msg {
-                        match msg {
-                            SerialMessage::SetMode(mode) => {
-                                if mode == Mode::Stop {
-                                    return Ok(Some(Mode::Stop));
-                                }
+                    let request = match msg {
+                        Some(SerialMessage::SetMode(mode)) => {
+                            if mode == Mode::Stop {
+                                return Ok(Some(Mode::Stop));
                             }
-                            SerialMessage::Send(data) => {
-                                if let Err(e) = self.write_read_stream(data, &self.timeout.clone()) {
-                                    log::error!("{:?}", e);
+                            None
+                        }
+                        Some(msg @ (SerialMessage::Send(_)
+                        | SerialMessage::SendWithId { .. }
+                        | SerialMessage::SendWithTimeout { .. }
+                        | SerialMessage::SendNoResponse(_))) => {
+                            Self::queued_request(msg)
+                                .and_then(|request| self.schedule_request(request))
+                        }
+                        Some(_) => continue,
+                        None => self.next_request(),
+                    };
+                    if request.is_none() {
+                        self.idle_backoff();
+                    }
+                    if let Some(request) = request {
+                        self.transaction_id = request.id;
+                        let timeout = request.timeout.unwrap_or(self.timeout);
+                        let result = if request.response {
+                            self.write_read_stream(request.data, &timeout)
+                        } else {
+                            self.write_only(request.data)
+                        };
+                        if let Err(e) = result {
+                            si_log!(error, self, "{:?}", e);
+                            if self.should_reconnect(&e) {
+                                if let Some(Mode::Stop) = self.recover()? {
+                                    return Ok(Some(Mode::Stop));
                                 }
                             }
-                            _ => {
-                                continue;
-                            }
                         }
                     }
                 }
                 Err(e) => {
-                    log::error!("{:?}", e);
+                    si_log!(error, self, "{:?}", e);
+                    if self.should_reconnect(&e) {
+                        if let Some(Mode::Stop) = self.recover()? {
+                            return Ok(Some(Mode::Stop));
+                        }
+                    }
                 }
             }
         }
@@ -1559,30 +5732,56 @@ impl SerialInterface {
     #[cfg(feature = "async-channel")]
     #[allow(unused)]
     async fn run_master_stream(&mut self) -> Result<Option<Mode>, SIError> {
-        log::debug!("SerialInterface::run_master_stream()");
+        si_log!(debug, self, "SerialInterface::run_master_stream()");
+        self.requests.clear();
         loop {
             match self.read_message().await {
                 Ok(msg) => {
-                    if let Some(msg) = msg {
-                        match msg {
-                            SerialMessage::SetMode(mode) => {
-                                if mode == Mode::Stop {
-                                    return Ok(Some(Mode::Stop));
-                                }
+                    let request = match msg {
+                        Some(SerialMessage::SetMode(mode)) => {
+                            if mode == Mode::Stop {
+                                return Ok(Some(Mode::Stop));
                             }
-                            SerialMessage::Send(data) => {
-                                if let Err(e) = self.write_read_stream(data, &self.timeout.clone()).await {
-                                    log::error!("{:?}", e);
+                            None
+                        }
+                        Some(msg @ (SerialMessage::Send(_)
+                        | SerialMessage::SendWithId { .. }
+                        | SerialMessage::SendWithTimeout { .. }
+                        | SerialMessage::SendNoResponse(_))) => {
+                            Self::queued_request(msg)
+                                .and_then(|request| self.schedule_request(request))
+                        }
+                        Some(_) => continue,
+                        None => self.next_request(),
+                    };
+                    if request.is_none() {
+                        self.idle_backoff().await;
+                    }
+                    if let Some(request) = request {
+                        self.transaction_id = request.id;
+                        let timeout = request.timeout.unwrap_or(self.timeout);
+                        let result = if request.response {
+                            self.write_read_stream(request.data, &timeout).await
+                        } else {
+                            self.write_only(request.data).await
+                        };
+                        if let Err(e) = result {
+                            si_log!(error, self, "{:?}", e);
+                            if self.should_reconnect(&e) {
+                                if let Some(Mode::Stop) = self.recover().await? {
+                                    return Ok(Some(Mode::Stop));
                                 }
                             }
-                            _ => {
-                                continue;
-                            }
                         }
                     }
                 }
                 Err(e) => {
-                    log::error!("{:?}", e);
+                    si_log!(error, self, "{:?}", e);
+                    if self.should_reconnect(&e) {
+                        if let Some(Mode::Stop) = self.recover().await? {
+                            return Ok(Some(Mode::Stop));
+                        }
+                    }
                 }
             }
         }
@@ -1595,16 +5794,24 @@ impl SerialInterface {
     #[cfg(not(feature = "async-channel"))]
     #[allow(unused)]
     fn run_slave(&mut self) -> Result<Option<Mode>, SIError> {
-        log::debug!("SerialInterface::run_slave()");
+        si_log!(debug, self, "SerialInterface::run_slave()");
         loop {
             match self.wait_for_request() {
-                Ok(msg) => {
-                    if let Some(SerialMessage::SetMode(Mode::Stop)) = msg {
-                        return Ok(Some(Mode::Stop));
+                Ok(Some(SerialMessage::SetMode(Mode::Stop))) => return Ok(Some(Mode::Stop)),
+                // response of the app to a request
+                Ok(Some(SerialMessage::Send(data))) => {
+                    if let Err(e) = self.write(data) {
+                        self.send_message(SerialMessage::Error(e))?;
                     }
                 }
+                Ok(_) => {}
                 Err(e) => {
-                    log::error!("{:?}", e);
+                    si_log!(error, self, "{:?}", e);
+                    if self.should_reconnect(&e) {
+                        if let Some(Mode::Stop) = self.recover()? {
+                            return Ok(Some(Mode::Stop));
+                        }
+                    }
                 }
             }
         }
@@ -1614,27 +5821,113 @@ impl SerialInterface {
     #[cfg(feature = "async-channel")]
     #[allow(unused)]
     async fn run_slave(&mut self) -> Result<Option<Mode>, SIError> {
-        log::debug!("SerialInterface::run_slave()");
+        si_log!(debug, self, "SerialInterface::run_slave()");
         loop {
             match self.wait_for_request().await {
-                Ok(msg) => {
-                    if let Some(SerialMessage::SetMode(Mode::Stop)) = msg {
-                        return Ok(Some(Mode::Stop));
+                Ok(Some(SerialMessage::SetMode(Mode::Stop))) => return Ok(Some(Mode::Stop)),
+                // response of the app to a request
+                Ok(Some(SerialMessage::Send(data))) => {
+                    if let Err(e) = self.write(data).await {
+                        self.send_message(SerialMessage::Error(e)).await?;
                     }
                 }
+                Ok(_) => {}
                 Err(e) => {
-                    log::error!("{:?}", e);
+                    si_log!(error, self, "{:?}", e);
+                    if self.should_reconnect(&e) {
+                        if let Some(Mode::Stop) = self.recover().await? {
+                            return Ok(Some(Mode::Stop));
+                        }
+                    }
                 }
             }
         }
     }
     
     
+    /// Terminal loop: every SerialMessage::Send() is written as is, received bytes are emitted as
+    /// SerialMessage::Receive(), coalesced over self.terminal_window. No framing nor CRC is applied.
+    /// Stop if receive SerialMessage::SetMode(Stop).
+    #[cfg(not(feature = "async-channel"))]
+    fn run_terminal(&mut self) -> Result<Option<Mode>, SIError> {
+        si_log!(debug, self, "SerialInterface::run_terminal()");
+        let mut buffer = Vec::new();
+        let mut first_byte = Instant::now();
+        loop {
+            if let Some(data) = self.read_byte()? {
+                if buffer.is_empty() {
+                    first_byte = Instant::now();
+                }
+                buffer.push(data);
+            }
+            if !buffer.is_empty()
+                && Instant::now().duration_since(first_byte) >= self.terminal_window
+            {
+                self.send_message(SerialMessage::Receive(std::mem::take(&mut buffer)))?;
+            }
+            match self.read_message()? {
+                Some(SerialMessage::Send(data)) => {
+                    self.status = Status::Write;
+                    let write = self.write(data);
+                    self.status = Status::None;
+                    if let Err(e) = write {
+                        self.send_message(SerialMessage::Error(e))?;
+                    }
+                }
+                Some(SerialMessage::SetMode(Mode::Stop)) => return Ok(Some(Mode::Stop)),
+                Some(SerialMessage::SetMode(_)) => {
+                    self.send_message(SerialMessage::Error(SIError::StopModeBeforeChange))?;
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Terminal loop: every SerialMessage::Send() is written as is, received bytes are emitted as
+    /// SerialMessage::Receive(), coalesced over self.terminal_window. No framing nor CRC is applied.
+    /// Stop if receive SerialMessage::SetMode(Stop).
+    #[cfg(feature = "async-channel")]
+    async fn run_terminal(&mut self) -> Result<Option<Mode>, SIError> {
+        si_log!(debug, self, "SerialInterface::run_terminal()");
+        let mut buffer = Vec::new();
+        let mut first_byte = Instant::now();
+        loop {
+            if let Some(data) = self.read_byte().await? {
+                if buffer.is_empty() {
+                    first_byte = Instant::now();
+                }
+                buffer.push(data);
+            }
+            if !buffer.is_empty()
+                && Instant::now().duration_since(first_byte) >= self.terminal_window
+            {
+                self.send_message(SerialMessage::Receive(std::mem::take(&mut buffer)))
+                    .await?;
+            }
+            match self.read_message().await? {
+                Some(SerialMessage::Send(data)) => {
+                    self.status = Status::Write;
+                    let write = self.write(data).await;
+                    self.status = Status::None;
+                    if let Err(e) = write {
+                        self.send_message(SerialMessage::Error(e)).await?;
+                    }
+                }
+                Some(SerialMessage::SetMode(Mode::Stop)) => return Ok(Some(Mode::Stop)),
+                Some(SerialMessage::SetMode(_)) => {
+                    self.send_message(SerialMessage::Error(SIError::StopModeBeforeChange))
+                        .await?;
+                }
+                _ => {}
+            }
+        }
+    }
+
     /// Sniff loop
     #[cfg(not(feature = "async-channel"))]
     #[allow(unused)]
     fn run_sniff(&mut self) -> Result<Option<Mode>, SIError> {
-        log::debug!("SerialInterface::run_sniff()");
+        si_log!(debug, self, "SerialInterface::run_sniff()");
         loop {
             match self.listen() {
                 Ok(msg) => {
@@ -1643,8 +5936,13 @@ impl SerialInterface {
                     }
                 }
                 Err(e) => {
-                    log::error!("SerialInterface::run_sniff():{:?}", e.clone());
-                    return Err(e);
+                    si_log!(error, self, "SerialInterface::run_sniff():{:?}", e.clone());
+                    if !self.should_reconnect(&e) {
+                        return Err(e);
+                    }
+                    if let Some(Mode::Stop) = self.recover()? {
+                        return Ok(Some(Mode::Stop));
+                    }
                 }
             }
         }
@@ -1654,7 +5952,7 @@ impl SerialInterface {
     #[cfg(feature = "async-channel")]
     #[allow(unused)]
     async fn run_sniff(&mut self) -> Result<Option<Mode>, SIError> {
-        log::debug!("SerialInterface::run_sniff()");
+        si_log!(debug, self, "SerialInterface::run_sniff()");
         loop {
             match self.listen().await {
                 Ok(msg) => {
@@ -1663,8 +5961,13 @@ impl SerialInterface {
                     }
                 }
                 Err(e) => {
-                    log::error!("SerialInterface::run_sniff():{:?}", e.clone());
-                    return Err(e);
+                    si_log!(error, self, "SerialInterface::run_sniff():{:?}", e.clone());
+                    if !self.should_reconnect(&e) {
+                        return Err(e);
+                    }
+                    if let Some(Mode::Stop) = self.recover().await? {
+                        return Ok(Some(Mode::Stop));
+                    }
                 }
             }
         }
@@ -1672,41 +5975,97 @@ impl SerialInterface {
 
     
     
-    /// Main loop
+    /// Runs the main loop on a dedicated OS thread with its own single-threaded tokio runtime, so
+    /// the serial polling neither affects nor is affected by the app runtime.
+    /// The sender/receiver channels are created here (overriding the ones previously set) and their
+    /// app side is returned in the SerialHandle, as the channels are thread safe it can be used from
+    /// any thread or runtime. Send SerialMessage::Shutdown to stop the loop, then join the thread.
+    pub fn spawn_dedicated(mut self) -> (SerialHandle, std::thread::JoinHandle<()>) {
+        #[cfg(not(feature = "async-channel"))]
+        let ((app_sender, serial_receiver), (serial_sender, app_receiver)) =
+            (std::sync::mpsc::channel(), std::sync::mpsc::channel());
+        #[cfg(feature = "async-channel")]
+        let ((app_sender, serial_receiver), (serial_sender, app_receiver)) =
+            (async_channel::unbounded(), async_channel::unbounded());
+        self.sender = Some(serial_sender);
+        self.receiver = Some(serial_receiver);
+        let thread = std::thread::spawn(move || {
+            match tokio::runtime::Builder::new_current_thread()
+                .enable_time()
+                .build()
+            {
+                Ok(runtime) => runtime.block_on(self.start()),
+                Err(e) => si_log!(error, self, "SerialInterface::spawn_dedicated(): {:?}", e),
+            }
+        });
+        let handle = SerialHandle {
+            sender: app_sender,
+            receiver: app_receiver,
+        };
+        (handle, thread)
+    }
+
+    /// Main loop. Returns immediately, sending a SerialMessage::Error(), if self.validate() fails.
     #[cfg(not(feature = "async-channel"))]
     #[allow(unused)]
     pub async fn start(&mut self) {
-        log::debug!("SerialInterface::run()");
+        si_log!(debug, self, "SerialInterface::run()");
+        if let Err(e) = self.validate() {
+            si_log!(
+                error,
+                self,
+                "SerialInterface::start(): invalid configuration: {:?}, {}",
+                e,
+                Self::validation_hint(&e)
+            );
+            let _ = self.send_message(SerialMessage::Error(e));
+            return;
+        }
         loop {
-            sleep(Duration::from_nanos(10)).await;
+            if self.shutdown {
+                si_log!(info, self, "SerialInterface::shutdown");
+                let _ = self.close();
+                return;
+            }
+            sleep(self.next_idle_sleep()).await;
             match &self.mode {
                 Mode::Stop => {
-                    let result = self.read_message();
+                    // block on the channel instead of spinning while idle
+                    let result = self.wait_message(self.stop_wait);
                     match result {
                         Ok(msg) => {
                             if let Some(SerialMessage::SetMode(mode)) = msg {
-                                log::info!("SerialInterface::switch mode to {:?}", &mode);
-                                self.mode = mode;
+                                if let Err(e) = self
+                                    .check_silence_timeout(&mode)
+                                    .and_then(|_| self.check_char_size(&mode))
+                                {
+                                    si_log!(error, self, "Mode Stop: cannot switch to {:?}: {:?}", mode, e);
+                                    let _ = self.send_message(SerialMessage::Error(e));
+                                    continue;
+                                }
+                                si_log!(info, self, "SerialInterface::switch mode to {:?}", &mode);
+                                self.switch_mode(mode);
                             }
                         }
                         Err(e) => {
-                            log::error!("Mode Stop: {:?}", e);
+                            si_log!(error, self, "Mode Stop: {:?}", e);
                         }
                     }
                 }
                 Mode::Master => {
                     let result = self.run_master();
+                    self.reject_queued();
                     match result {
                         Ok(msg) => {
                             if let Some(Mode::Stop) = msg {
-                                log::info!("SerialInterface::switch mode to Mode::Stop");
-                                self.mode = Mode::Stop;
+                                si_log!(info, self, "SerialInterface::switch mode to Mode::Stop");
+                                self.switch_mode(Mode::Stop);
                             }
                         }
                         Err(e) => {
-                            log::error!("{:?}", e);
-                            log::info!("SerialInterface::switch mode to Mode::Stop");
-                            self.mode = Mode::Stop;
+                            si_log!(error, self, "{:?}", e);
+                            si_log!(info, self, "SerialInterface::switch mode to Mode::Stop");
+                            self.switch_mode(Mode::Stop);
                         }
                     }
                 }
@@ -1715,14 +6074,14 @@ impl SerialInterface {
                     match result {
                         Ok(msg) => {
                             if let Some(Mode::Stop) = msg {
-                                log::info!("SerialInterface::switch mode to Mode::Stop");
-                                self.mode = Mode::Stop;
+                                si_log!(info, self, "SerialInterface::switch mode to Mode::Stop");
+                                self.switch_mode(Mode::Stop);
                             }
                         }
                         Err(e) => {
-                            log::error!("{:?}", e);
-                            log::info!("SerialInterface::switch mode to Mode::Stop");
-                            self.mode = Mode::Stop;
+                            si_log!(error, self, "{:?}", e);
+                            si_log!(info, self, "SerialInterface::switch mode to Mode::Stop");
+                            self.switch_mode(Mode::Stop);
                         }
                     }
                 }
@@ -1731,30 +6090,47 @@ impl SerialInterface {
                     match result {
                         Ok(msg) => {
                             if let Some(Mode::Stop) = msg {
-                                log::info!("SerialInterface::switch mode to Mode::Stop");
-                                self.mode = Mode::Stop;
+                                si_log!(info, self, "SerialInterface::switch mode to Mode::Stop");
+                                self.switch_mode(Mode::Stop);
+                            }
+                        }
+                        Err(e) => {
+                            si_log!(error, self, "{:?}", e);
+                            si_log!(info, self, "SerialInterface::switch mode to Mode::Stop");
+                            self.switch_mode(Mode::Stop);
+                        }
+                    }
+                }
+                Mode::Terminal => {
+                    let result = self.run_terminal();
+                    match result {
+                        Ok(msg) => {
+                            if let Some(Mode::Stop) = msg {
+                                si_log!(info, self, "SerialInterface::switch mode to Mode::Stop");
+                                self.switch_mode(Mode::Stop);
                             }
                         }
                         Err(e) => {
-                            log::error!("{:?}", e);
-                            log::info!("SerialInterface::switch mode to Mode::Stop");
-                            self.mode = Mode::Stop;
+                            si_log!(error, self, "{:?}", e);
+                            si_log!(info, self, "SerialInterface::switch mode to Mode::Stop");
+                            self.switch_mode(Mode::Stop);
                         }
                     }
                 }
                 Mode::MasterStream => {
                     let result = self.run_master_stream();
+                    self.reject_queued();
                     match result {
                         Ok(msg) => {
                             if let Some(Mode::Stop) = msg {
-                                log::info!("SerialInterface::switch mode to Mode::Stop");
-                                self.mode = Mode::Stop;
+                                si_log!(info, self, "SerialInterface::switch mode to Mode::Stop");
+                                self.switch_mode(Mode::Stop);
                             }
                         }
                         Err(e) => {
-                            log::error!("{:?}", e);
-                            log::info!("SerialInterface::switch mode to Mode::Stop");
-                            self.mode = Mode::Stop;
+                            si_log!(error, self, "{:?}", e);
+                            si_log!(info, self, "SerialInterface::switch mode to Mode::Stop");
+                            self.switch_mode(Mode::Stop);
                         }
                     }
                 }
@@ -1762,41 +6138,67 @@ impl SerialInterface {
         }
     }
 
-    /// Main loop
+    /// Main loop. Returns immediately, sending a SerialMessage::Error(), if self.validate() fails.
     #[cfg(feature = "async-channel")]
     #[allow(unused)]
     pub async fn start(&mut self) {
-        log::debug!("SerialInterface::run()");
+        si_log!(debug, self, "SerialInterface::run()");
+        if let Err(e) = self.validate() {
+            si_log!(
+                error,
+                self,
+                "SerialInterface::start(): invalid configuration: {:?}, {}",
+                e,
+                Self::validation_hint(&e)
+            );
+            let _ = self.send_message(SerialMessage::Error(e)).await;
+            return;
+        }
         loop {
-            sleep(Duration::from_nanos(10)).await;
+            if self.shutdown {
+                si_log!(info, self, "SerialInterface::shutdown");
+                let _ = self.close();
+                return;
+            }
+            sleep(self.next_idle_sleep()).await;
             match &self.mode {
                 Mode::Stop => {
-                    let result = self.read_message().await;
+                    // block on the channel instead of spinning while idle
+                    let result = self.wait_message(self.stop_wait).await;
                     match result {
                         Ok(msg) => {
                             if let Some(SerialMessage::SetMode(mode)) = msg {
-                                log::info!("SerialInterface::switch mode to {:?}", &mode);
-                                self.mode = mode;
+                                if let Err(e) = self
+                                    .check_silence_timeout(&mode)
+                                    .and_then(|_| self.check_char_size(&mode))
+                                {
+                                    si_log!(error, self, "Mode Stop: cannot switch to {:?}: {:?}", mode, e);
+                                    let _ = self.send_message(SerialMessage::Error(e)).await;
+                                    continue;
+                                }
+                                si_log!(info, self, "SerialInterface::switch mode to {:?}", &mode);
+                                self.switch_mode(mode);
                             }
                         }
                         Err(e) => {
-                            log::error!("Mode Stop: {:?}", e);
+                            si_log!(error, self, "Mode Stop: {:?}", e);
                         }
                     }
                 }
                 Mode::Master => {
                     let result = self.run_master().await;
+                    self.reject_queued().await;
                     match result {
                         Ok(msg) => {
                             if let Some(Mode::Stop) = msg {
-                                log::info!("SerialInterface::switch mode to Mode::Stop");
-                                self.mode = Mode::Stop;
+                                si_log!(info, self, "SerialInterface::switch mode to Mode::Stop");
+                                self.switch_mode(Mode::Stop);
                             }
                         }
                         Err(e) => {
-                            log::error!("{:?}", e);
-                            log::info!("SerialInterface::switch mode to Mode::Stop");
-                            self.mode = Mode::Stop;
+                            si_log!(error, self, "{:?}", e);
+                            si_log!(info, self, "SerialInterface::switch mode to Mode::Stop");
+                            self.switch_mode(Mode::Stop);
                         }
                     }
                 }
@@ -1805,14 +6207,14 @@ impl SerialInterface {
                     match result {
                         Ok(msg) => {
                             if let Some(Mode::Stop) = msg {
-                                log::info!("SerialInterface::switch mode to Mode::Stop");
-                                self.mode = Mode::Stop;
+                                si_log!(info, self, "SerialInterface::switch mode to Mode::Stop");
+                                self.switch_mode(Mode::Stop);
                             }
                         }
                         Err(e) => {
-                            log::error!("{:?}", e);
-                            log::info!("SerialInterface::switch mode to Mode::Stop");
-                            self.mode = Mode::Stop;
+                            si_log!(error, self, "{:?}", e);
+                            si_log!(info, self, "SerialInterface::switch mode to Mode::Stop");
+                            self.switch_mode(Mode::Stop);
                         }
                     }
                 }
@@ -1821,30 +6223,47 @@ impl SerialInterface {
                     match result {
                         Ok(msg) => {
                             if let Some(Mode::Stop) = msg {
-                                log::info!("SerialInterface::switch mode to Mode::Stop");
-                                self.mode = Mode::Stop;
+                                si_log!(info, self, "SerialInterface::switch mode to Mode::Stop");
+                                self.switch_mode(Mode::Stop);
+                            }
+                        }
+                        Err(e) => {
+                            si_log!(error, self, "{:?}", e);
+                            si_log!(info, self, "SerialInterface::switch mode to Mode::Stop");
+                            self.switch_mode(Mode::Stop);
+                        }
+                    }
+                }
+                Mode::Terminal => {
+                    let result = self.run_terminal().await;
+                    match result {
+                        Ok(msg) => {
+                            if let Some(Mode::Stop) = msg {
+                                si_log!(info, self, "SerialInterface::switch mode to Mode::Stop");
+                                self.switch_mode(Mode::Stop);
                             }
                         }
                         Err(e) => {
-                            log::error!("{:?}", e);
-                            log::info!("SerialInterface::switch mode to Mode::Stop");
-                            self.mode = Mode::Stop;
+                            si_log!(error, self, "{:?}", e);
+                            si_log!(info, self, "SerialInterface::switch mode to Mode::Stop");
+                            self.switch_mode(Mode::Stop);
                         }
                     }
                 }
                 Mode::MasterStream => {
                     let result = self.run_master_stream().await;
+                    self.reject_queued().await;
                     match result {
                         Ok(msg) => {
                             if let Some(Mode::Stop) = msg {
-                                log::info!("SerialInterface::switch mode to Mode::Stop");
-                                self.mode = Mode::Stop;
+                                si_log!(info, self, "SerialInterface::switch mode to Mode::Stop");
+                                self.switch_mode(Mode::Stop);
                             }
                         }
                         Err(e) => {
-                            log::error!("{:?}", e);
-                            log::info!("SerialInterface::switch mode to Mode::Stop");
-                            self.mode = Mode::Stop;
+                            si_log!(error, self, "{:?}", e);
+                            si_log!(info, self, "SerialInterface::switch mode to Mode::Stop");
+                            self.switch_mode(Mode::Stop);
                         }
                     }
                 }
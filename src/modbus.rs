@@ -0,0 +1,581 @@
+//! Modbus RTU helpers, giving a typed view over the raw frames exchanged through
+//! `SerialMessage::Send` / `SerialMessage::Receive`.
+
+use crate::{CrcByteOrder, SIError, SerialInterface};
+
+/// Typed content of a Modbus response.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ModbusResponse {
+    /// Response to Read Coils (0x01) / Read Discrete Inputs (0x02). As the response only carry the
+    /// byte count, the trailing bits of the last byte can be padding.
+    ReadBits(Vec<bool>),
+    /// Response to Read Holding Registers (0x03) / Read Input Registers (0x04).
+    ReadRegisters(Vec<u16>),
+    /// Response to Write Single Coil (0x05) / Write Single Register (0x06), echoing the request.
+    WriteAck { addr: u16, value: u16 },
+    /// Response to Write Multiple Coils (0x0F) / Write Multiple Registers (0x10).
+    WriteMultipleAck { addr: u16, count: u16 },
+    /// Exception response to the request function code.
+    Exception { function: u8, code: u8 },
+}
+
+/// Typed content of a Modbus request.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ModbusRequest {
+    /// Read Coils (0x01).
+    ReadCoils { start: u16, count: u16 },
+    /// Read Discrete Inputs (0x02).
+    ReadDiscreteInputs { start: u16, count: u16 },
+    /// Read Holding Registers (0x03).
+    ReadHoldingRegisters { start: u16, count: u16 },
+    /// Read Input Registers (0x04).
+    ReadInputRegisters { start: u16, count: u16 },
+    /// Write Single Coil (0x05).
+    WriteSingleCoil { addr: u16, value: bool },
+    /// Write Single Register (0x06).
+    WriteSingleRegister { addr: u16, value: u16 },
+    /// Write Multiple Coils (0x0F).
+    WriteMultipleCoils { addr: u16, values: Vec<bool> },
+    /// Write Multiple Registers (0x10).
+    WriteMultipleRegisters { addr: u16, values: Vec<u16> },
+}
+
+/// A decoded Modbus RTU request frame: slave address and typed request.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModbusRequestPdu {
+    pub address: u8,
+    pub request: ModbusRequest,
+}
+
+/// A decoded Modbus RTU response frame: slave address and typed response.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModbusResponsePdu {
+    pub address: u8,
+    pub response: ModbusResponse,
+}
+
+fn u16_at(frame: &[u8], i: usize) -> u16 {
+    u16::from_be_bytes([frame[i], frame[i + 1]])
+}
+
+/// Decode a Modbus RTU request frame (id, function code, data, CRC), validating its length and CRC.
+/// Supported function codes are 0x01 to 0x06, 0x0F and 0x10.
+pub fn parse_request(frame: &[u8]) -> Result<ModbusRequestPdu, SIError> {
+    if frame.len() < 8 {
+        return Err(SIError::InvalidFrame);
+    }
+    if !SerialInterface::check_crc(frame, CrcByteOrder::LittleEndian) {
+        return Err(SIError::InvalidCrc);
+    }
+    let function = frame[1];
+    let addr = u16_at(frame, 2);
+    let value = u16_at(frame, 4);
+    let request = match function {
+        0x01..=0x06 if frame.len() != 8 => return Err(SIError::InvalidFrame),
+        0x01 => ModbusRequest::ReadCoils { start: addr, count: value },
+        0x02 => ModbusRequest::ReadDiscreteInputs { start: addr, count: value },
+        0x03 => ModbusRequest::ReadHoldingRegisters { start: addr, count: value },
+        0x04 => ModbusRequest::ReadInputRegisters { start: addr, count: value },
+        0x05 => ModbusRequest::WriteSingleCoil {
+            addr,
+            value: match value {
+                0xFF00 => true,
+                0x0000 => false,
+                _ => return Err(SIError::InvalidFrame),
+            },
+        },
+        0x06 => ModbusRequest::WriteSingleRegister { addr, value },
+        0x0F | 0x10 => {
+            let count = value as usize;
+            let byte_count = frame.get(6).copied().ok_or(SIError::InvalidFrame)? as usize;
+            let expected = if function == 0x0F {
+                count.div_ceil(8)
+            } else {
+                count * 2
+            };
+            if byte_count != expected || frame.len() != byte_count + 9 {
+                return Err(SIError::InvalidFrame);
+            }
+            let data = &frame[7..7 + byte_count];
+            if function == 0x0F {
+                ModbusRequest::WriteMultipleCoils {
+                    addr,
+                    values: (0..count).map(|i| data[i / 8] & (1 << (i % 8)) != 0).collect(),
+                }
+            } else {
+                ModbusRequest::WriteMultipleRegisters {
+                    addr,
+                    values: (0..byte_count).step_by(2).map(|i| u16_at(data, i)).collect(),
+                }
+            }
+        }
+        _ => return Err(SIError::UnsupportedFunction(function)),
+    };
+    Ok(ModbusRequestPdu {
+        address: frame[0],
+        request,
+    })
+}
+
+/// Decode a Modbus RTU response frame without knowing the request, the function code being taken
+/// from the response itself, see decode_response().
+pub fn parse_response(frame: &[u8]) -> Result<ModbusResponsePdu, SIError> {
+    let function = frame.get(1).ok_or(SIError::InvalidFrame)? & 0x7F;
+    Ok(ModbusResponsePdu {
+        address: frame[0],
+        response: decode_response(function, frame)?,
+    })
+}
+
+/// Decode a Modbus RTU response frame (id, function code, data, CRC) to the request of function
+/// code `request_fn`, validating its length and CRC. The registers are returned as received, see
+/// decode_response_with_order() for a device with another register order.
+pub fn decode_response(request_fn: u8, response: &[u8]) -> Result<ModbusResponse, SIError> {
+    decode_response_with_order(request_fn, response, WordOrder::HighFirst)
+}
+
+/// Same as decode_response(), for a device sending 32-bit values in `order`: the registers of a
+/// ModbusResponse::ReadRegisters are returned in the standard order (high register first), each
+/// pair being swapped for WordOrder::LowFirst (a trailing odd register is kept as is).
+pub fn decode_response_with_order(
+    request_fn: u8,
+    response: &[u8],
+    order: WordOrder,
+) -> Result<ModbusResponse, SIError> {
+    if response.len() < 5 {
+        return Err(SIError::InvalidFrame);
+    }
+    if !SerialInterface::check_crc(response, CrcByteOrder::LittleEndian) {
+        return Err(SIError::InvalidCrc);
+    }
+    let function = response[1];
+    if function == request_fn | 0x80 {
+        return if response.len() == 5 {
+            Ok(ModbusResponse::Exception {
+                function: request_fn,
+                code: response[2],
+            })
+        } else {
+            Err(SIError::InvalidFrame)
+        };
+    }
+    if function != request_fn {
+        return Err(SIError::UnexpectedFunction(function));
+    }
+    match function {
+        0x01..=0x04 => {
+            let count = response[2] as usize;
+            if response.len() != count + 5 {
+                return Err(SIError::InvalidFrame);
+            }
+            let data = &response[3..3 + count];
+            if function <= 0x02 {
+                Ok(ModbusResponse::ReadBits(
+                    data.iter()
+                        .flat_map(|byte| (0..8).map(move |bit| byte & (1 << bit) != 0))
+                        .collect(),
+                ))
+            } else if count.is_multiple_of(2) {
+                let mut registers: Vec<u16> =
+                    (0..count).step_by(2).map(|i| u16_at(data, i)).collect();
+                if order == WordOrder::LowFirst {
+                    registers.chunks_exact_mut(2).for_each(|pair| pair.swap(0, 1));
+                }
+                Ok(ModbusResponse::ReadRegisters(registers))
+            } else {
+                Err(SIError::InvalidFrame)
+            }
+        }
+        0x05 | 0x06 | 0x0F | 0x10 => {
+            if response.len() != 8 {
+                return Err(SIError::InvalidFrame);
+            }
+            let addr = u16_at(response, 2);
+            let value = u16_at(response, 4);
+            if function <= 0x06 {
+                Ok(ModbusResponse::WriteAck { addr, value })
+            } else {
+                Ok(ModbusResponse::WriteMultipleAck { addr, count: value })
+            }
+        }
+        _ => Err(SIError::UnsupportedFunction(function)),
+    }
+}
+
+/// Assemble a Modbus RTU frame from its id, function code and data, appending the CRC16.
+fn build_frame(id: u8, function: u8, data: &[u8]) -> Vec<u8> {
+    let mut frame = Vec::with_capacity(data.len() + 4);
+    frame.push(id);
+    frame.push(function);
+    frame.extend_from_slice(data);
+    SerialInterface::append_crc(&mut frame, CrcByteOrder::LittleEndian);
+    frame
+}
+
+fn build_read(id: u8, function: u8, start: u16, count: u16) -> Vec<u8> {
+    let [s0, s1] = start.to_be_bytes();
+    let [c0, c1] = count.to_be_bytes();
+    build_frame(id, function, &[s0, s1, c0, c1])
+}
+
+/// Build a Read Coils (0x01) request frame.
+pub fn build_read_coils(id: u8, start: u16, count: u16) -> Vec<u8> {
+    build_read(id, 0x01, start, count)
+}
+
+/// Build a Read Discrete Inputs (0x02) request frame.
+pub fn build_read_discrete_inputs(id: u8, start: u16, count: u16) -> Vec<u8> {
+    build_read(id, 0x02, start, count)
+}
+
+/// Build a Read Holding Registers (0x03) request frame, e.g. reading 10 registers from address 0
+/// of slave 1:
+///
+/// ```
+/// use serial_thread::modbus::*;
+///
+/// let frame = build_read_holding_registers(0x01, 0x0000, 0x000A);
+/// assert_eq!(frame, vec![0x01, 0x03, 0x00, 0x00, 0x00, 0x0A, 0xC5, 0xCD]);
+/// ```
+pub fn build_read_holding_registers(id: u8, start: u16, count: u16) -> Vec<u8> {
+    build_read(id, 0x03, start, count)
+}
+
+/// Build a Read Input Registers (0x04) request frame.
+pub fn build_read_input_registers(id: u8, start: u16, count: u16) -> Vec<u8> {
+    build_read(id, 0x04, start, count)
+}
+
+/// Build a Write Single Coil (0x05) request frame, `value` being sent as 0xFF00 (on) or 0x0000.
+pub fn build_write_single_coil(id: u8, addr: u16, value: bool) -> Vec<u8> {
+    build_read(id, 0x05, addr, if value { 0xFF00 } else { 0x0000 })
+}
+
+/// Build a Write Single Register (0x06) request frame, e.g. writing 3 to register 1 of slave 1:
+///
+/// ```
+/// use serial_thread::modbus::*;
+///
+/// let frame = build_write_single_register(0x01, 0x0001, 0x0003);
+/// assert_eq!(frame, vec![0x01, 0x06, 0x00, 0x01, 0x00, 0x03, 0x98, 0x0B]);
+/// ```
+pub fn build_write_single_register(id: u8, addr: u16, value: u16) -> Vec<u8> {
+    build_read(id, 0x06, addr, value)
+}
+
+/// Build a Write Multiple Coils (0x0F) request frame, the coils being packed LSB first.
+pub fn build_write_multiple_coils(id: u8, addr: u16, values: &[bool]) -> Vec<u8> {
+    let mut data = Vec::with_capacity(values.len().div_ceil(8) + 5);
+    data.extend_from_slice(&addr.to_be_bytes());
+    data.extend_from_slice(&(values.len() as u16).to_be_bytes());
+    data.push(values.len().div_ceil(8) as u8);
+    for chunk in values.chunks(8) {
+        data.push(
+            chunk
+                .iter()
+                .enumerate()
+                .fold(0u8, |byte, (i, v)| byte | ((*v as u8) << i)),
+        );
+    }
+    build_frame(id, 0x0F, &data)
+}
+
+/// Build a Write Multiple Registers (0x10) request frame.
+pub fn build_write_multiple_registers(id: u8, addr: u16, values: &[u16]) -> Vec<u8> {
+    let mut data = Vec::with_capacity(values.len() * 2 + 5);
+    data.extend_from_slice(&addr.to_be_bytes());
+    data.extend_from_slice(&(values.len() as u16).to_be_bytes());
+    data.push((values.len() * 2) as u8);
+    for value in values {
+        data.extend_from_slice(&value.to_be_bytes());
+    }
+    build_frame(id, 0x10, &data)
+}
+
+/// Order of the registers of a 32-bit value spanning two registers, each register being big-endian
+/// as per Modbus.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WordOrder {
+    /// The first register holds the high word (standard Modbus order).
+    HighFirst,
+    /// The first register holds the low word ("word swapped").
+    LowFirst,
+}
+
+/// Combine pairs of registers into 32-bit values, a trailing odd register is ignored.
+pub fn registers_to_u32(registers: &[u16], order: WordOrder) -> Vec<u32> {
+    registers
+        .chunks_exact(2)
+        .map(|pair| match order {
+            WordOrder::HighFirst => (pair[0] as u32) << 16 | pair[1] as u32,
+            WordOrder::LowFirst => (pair[1] as u32) << 16 | pair[0] as u32,
+        })
+        .collect()
+}
+
+/// Combine pairs of registers into IEEE 754 32-bit floats, a trailing odd register is ignored.
+pub fn registers_to_f32(registers: &[u16], order: WordOrder) -> Vec<f32> {
+    registers_to_u32(registers, order)
+        .into_iter()
+        .map(f32::from_bits)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_round_trip() {
+        for (function, build) in [
+            (0x01, build_read_coils as fn(u8, u16, u16) -> Vec<u8>),
+            (0x02, build_read_discrete_inputs),
+        ] {
+            let request = build(0x11, 0x0013, 10);
+            let pdu = parse_request(&request).unwrap();
+            assert_eq!(pdu.address, 0x11);
+            let response = build_frame(0x11, function, &[0x02, 0b1100_1101, 0b0000_0001]);
+            let mut bits = vec![true, false, true, true, false, false, true, true, true];
+            bits.extend([false; 7]);
+            assert_eq!(
+                decode_response(request[1], &response).unwrap(),
+                ModbusResponse::ReadBits(bits)
+            );
+        }
+        for (function, build) in [
+            (
+                0x03,
+                build_read_holding_registers as fn(u8, u16, u16) -> Vec<u8>,
+            ),
+            (0x04, build_read_input_registers),
+        ] {
+            let request = build(0x11, 0x006B, 2);
+            assert_eq!(request[1], function);
+            let response = build_frame(0x11, function, &[0x04, 0x02, 0x2B, 0x00, 0x64]);
+            assert_eq!(
+                decode_response(request[1], &response).unwrap(),
+                ModbusResponse::ReadRegisters(vec![0x022B, 0x0064])
+            );
+        }
+        assert_eq!(
+            parse_request(&build_read_coils(0x11, 0x0013, 10))
+                .unwrap()
+                .request,
+            ModbusRequest::ReadCoils {
+                start: 0x0013,
+                count: 10
+            }
+        );
+        assert_eq!(
+            parse_request(&build_read_input_registers(0x11, 0x0008, 1))
+                .unwrap()
+                .request,
+            ModbusRequest::ReadInputRegisters {
+                start: 0x0008,
+                count: 1
+            }
+        );
+    }
+
+    #[test]
+    fn write_single_round_trip() {
+        let request = build_write_single_coil(0x11, 0x00AC, true);
+        assert_eq!(
+            parse_request(&request).unwrap().request,
+            ModbusRequest::WriteSingleCoil {
+                addr: 0x00AC,
+                value: true
+            }
+        );
+        // the response to a single write echoes the request
+        assert_eq!(
+            decode_response(0x05, &request).unwrap(),
+            ModbusResponse::WriteAck {
+                addr: 0x00AC,
+                value: 0xFF00
+            }
+        );
+        let request = build_write_single_register(0x11, 0x0001, 0x0003);
+        assert_eq!(
+            parse_request(&request).unwrap().request,
+            ModbusRequest::WriteSingleRegister {
+                addr: 0x0001,
+                value: 0x0003
+            }
+        );
+        assert_eq!(
+            decode_response(0x06, &request).unwrap(),
+            ModbusResponse::WriteAck {
+                addr: 0x0001,
+                value: 0x0003
+            }
+        );
+    }
+
+    #[test]
+    fn write_multiple_round_trip() {
+        let coils = [
+            true, false, true, true, false, false, true, true, true, false,
+        ];
+        let request = build_write_multiple_coils(0x11, 0x0013, &coils);
+        assert_eq!(
+            parse_request(&request).unwrap().request,
+            ModbusRequest::WriteMultipleCoils {
+                addr: 0x0013,
+                values: coils.to_vec()
+            }
+        );
+        let response = build_frame(0x11, 0x0F, &[0x00, 0x13, 0x00, 0x0A]);
+        assert_eq!(
+            decode_response(request[1], &response).unwrap(),
+            ModbusResponse::WriteMultipleAck {
+                addr: 0x0013,
+                count: 10
+            }
+        );
+        let request = build_write_multiple_registers(0x11, 0x0001, &[0x000A, 0x0102]);
+        assert_eq!(
+            parse_request(&request).unwrap().request,
+            ModbusRequest::WriteMultipleRegisters {
+                addr: 0x0001,
+                values: vec![0x000A, 0x0102]
+            }
+        );
+        let response = build_frame(0x11, 0x10, &[0x00, 0x01, 0x00, 0x02]);
+        assert_eq!(
+            decode_response(request[1], &response).unwrap(),
+            ModbusResponse::WriteMultipleAck {
+                addr: 0x0001,
+                count: 2
+            }
+        );
+    }
+
+    #[test]
+    fn exception_response() {
+        let response = build_frame(0x0A, 0x81, &[0x02]);
+        assert_eq!(
+            decode_response(0x01, &response).unwrap(),
+            ModbusResponse::Exception {
+                function: 0x01,
+                code: 0x02
+            }
+        );
+        assert_eq!(
+            parse_response(&response).unwrap(),
+            ModbusResponsePdu {
+                address: 0x0A,
+                response: ModbusResponse::Exception {
+                    function: 0x01,
+                    code: 0x02
+                },
+            }
+        );
+        // an exception response carries a single code byte
+        let response = build_frame(0x0A, 0x81, &[0x02, 0x00]);
+        assert!(matches!(
+            decode_response(0x01, &response),
+            Err(SIError::InvalidFrame)
+        ));
+    }
+
+    #[test]
+    fn bad_crc() {
+        let mut response = build_frame(0x11, 0x03, &[0x02, 0x00, 0x2A]);
+        *response.last_mut().unwrap() ^= 0xFF;
+        assert!(matches!(
+            decode_response(0x03, &response),
+            Err(SIError::InvalidCrc)
+        ));
+        let mut request = build_read_holding_registers(0x11, 0, 1);
+        request[2] ^= 0x01;
+        assert!(matches!(parse_request(&request), Err(SIError::InvalidCrc)));
+    }
+
+    #[test]
+    fn length_mismatch() {
+        // byte count announcing more registers than the frame holds
+        let response = build_frame(0x11, 0x03, &[0x04, 0x00, 0x2A]);
+        assert!(matches!(
+            decode_response(0x03, &response),
+            Err(SIError::InvalidFrame)
+        ));
+        // odd byte count for registers
+        let response = build_frame(0x11, 0x03, &[0x03, 0x00, 0x2A, 0x00]);
+        assert!(matches!(
+            decode_response(0x03, &response),
+            Err(SIError::InvalidFrame)
+        ));
+        // write ack with a missing byte
+        let response = build_frame(0x11, 0x06, &[0x00, 0x01, 0x00]);
+        assert!(matches!(
+            decode_response(0x06, &response),
+            Err(SIError::InvalidFrame)
+        ));
+        // too short to hold a CRC
+        assert!(matches!(
+            decode_response(0x03, &[0x11, 0x03, 0x00]),
+            Err(SIError::InvalidFrame)
+        ));
+        // unexpected function code
+        let response = build_frame(0x11, 0x04, &[0x02, 0x00, 0x2A]);
+        assert!(matches!(
+            decode_response(0x03, &response),
+            Err(SIError::UnexpectedFunction(0x04))
+        ));
+        // request byte count not matching the register count
+        let mut request = build_write_multiple_registers(0x11, 0x0001, &[0x000A, 0x0102]);
+        request.truncate(request.len() - 2);
+        request[6] = 0x03;
+        request.truncate(request.len() - 1);
+        SerialInterface::append_crc(&mut request, CrcByteOrder::LittleEndian);
+        assert!(matches!(
+            parse_request(&request),
+            Err(SIError::InvalidFrame)
+        ));
+    }
+
+    #[test]
+    fn float_high_first() {
+        // 123.456 is 0x42F6E979
+        let registers = [0x42F6, 0xE979, 0x0001];
+        assert_eq!(registers_to_u32(&registers, WordOrder::HighFirst), [0x42F6_E979]);
+        assert_eq!(registers_to_f32(&registers, WordOrder::HighFirst), [123.456]);
+    }
+
+    #[test]
+    fn float_low_first() {
+        let registers = [0xE979, 0x42F6, 0x0001];
+        assert_eq!(registers_to_u32(&registers, WordOrder::LowFirst), [0x42F6_E979]);
+        assert_eq!(registers_to_f32(&registers, WordOrder::LowFirst), [123.456]);
+        let si = SerialInterface::new()
+            .unwrap()
+            .register_order(WordOrder::LowFirst);
+        assert_eq!(si.registers_to_f32(&registers), [123.456]);
+    }
+
+    #[test]
+    fn decode_float_in_register_order() {
+        for (order, registers) in [
+            (WordOrder::HighFirst, [0x42F6_u16, 0xE979, 0x0001]),
+            (WordOrder::LowFirst, [0xE979, 0x42F6, 0x0001]),
+        ] {
+            let mut response = vec![0x01, 0x03, 6];
+            for register in registers {
+                response.extend_from_slice(&register.to_be_bytes());
+            }
+            SerialInterface::append_crc(&mut response, CrcByteOrder::LittleEndian);
+            let si = SerialInterface::new().unwrap().register_order(order);
+            for decoded in [
+                decode_response_with_order(0x03, &response, order).unwrap(),
+                si.decode_response(0x03, &response).unwrap(),
+            ] {
+                let ModbusResponse::ReadRegisters(decoded) = decoded else {
+                    panic!("{:?}: {:?}", order, decoded);
+                };
+                assert_eq!(decoded, [0x42F6, 0xE979, 0x0001], "{:?}", order);
+                assert_eq!(registers_to_f32(&decoded, WordOrder::HighFirst), [123.456]);
+            }
+        }
+    }
+}
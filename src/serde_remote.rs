@@ -0,0 +1,220 @@
+//! Serde definitions for the `serial` crate types carried by SerialMessage (`serde` feature).
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serial::{BaudRate, CharSize, FlowControl, Parity, PortSettings, StopBits};
+
+/// BaudRate is (de)serialized as its raw value, a value rejected by
+/// `SerialInterface::baud_from_u32()` (i.e. 0) fails the deserialization.
+pub mod baud_rate {
+    use super::*;
+    use crate::SerialInterface;
+    use serde::de::Error;
+
+    pub fn serialize<S: Serializer>(
+        baud_rate: &BaudRate,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u32(baud_rate.speed() as u32)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<BaudRate, D::Error> {
+        SerialInterface::baud_from_u32(u32::deserialize(deserializer)?)
+            .map_err(|e| D::Error::custom(format!("{:?}", e)))
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(remote = "CharSize")]
+pub enum CharSizeDef {
+    Bits5,
+    Bits6,
+    Bits7,
+    Bits8,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(remote = "Parity")]
+#[allow(clippy::enum_variant_names)] // mirror the remote variants
+pub enum ParityDef {
+    ParityNone,
+    ParityOdd,
+    ParityEven,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(remote = "StopBits")]
+pub enum StopBitsDef {
+    Stop1,
+    Stop2,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(remote = "FlowControl")]
+#[allow(clippy::enum_variant_names)] // mirror the remote variants
+pub enum FlowControlDef {
+    FlowNone,
+    FlowSoftware,
+    FlowHardware,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(remote = "PortSettings")]
+pub struct PortSettingsDef {
+    #[serde(with = "baud_rate")]
+    pub baud_rate: BaudRate,
+    #[serde(with = "CharSizeDef")]
+    pub char_size: CharSize,
+    #[serde(with = "ParityDef")]
+    pub parity: Parity,
+    #[serde(with = "StopBitsDef")]
+    pub stop_bits: StopBits,
+    #[serde(with = "FlowControlDef")]
+    pub flow_control: FlowControl,
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod tests {
+    use crate::*;
+
+    fn port_settings() -> PortSettings {
+        PortSettings {
+            baud_rate: BaudRate::Baud9600,
+            char_size: CharSize::Bits7,
+            parity: Parity::ParityEven,
+            stop_bits: StopBits::Stop1,
+            flow_control: FlowControl::FlowHardware,
+        }
+    }
+
+    #[test]
+    fn message_round_trip() {
+        let data = vec![0x01, 0x03, 0x00, 0x00, 0x00, 0x01, 0x84, 0x0A];
+        let messages = vec![
+            SerialMessage::ListPorts,
+            SerialMessage::AvailablePorts(vec!["/dev/ttyUSB0".to_string()]),
+            SerialMessage::SetPort("/dev/ttyUSB0".to_string()),
+            SerialMessage::SetBauds(BaudRate::Baud19200),
+            SerialMessage::SetBauds(BaudRate::BaudOther(250_000)),
+            SerialMessage::SetBaudsRaw(250_000),
+            SerialMessage::SetCharSize(CharSize::Bits7),
+            SerialMessage::SetParity(Parity::ParityOdd),
+            SerialMessage::SetStopBits(StopBits::Stop2),
+            SerialMessage::SetFlowControl(FlowControl::FlowSoftware),
+            SerialMessage::SetTimeout(Duration::from_millis(150)),
+            SerialMessage::Connect,
+            SerialMessage::OpenSingle,
+            SerialMessage::Disconnect,
+            SerialMessage::Send(data.clone()),
+            SerialMessage::SendRaw(data.clone()),
+            SerialMessage::SendWithId { id: 7, data: data.clone() },
+            SerialMessage::DataSent(data.clone()),
+            SerialMessage::Receive(data.clone()),
+            SerialMessage::ReceiveBatch(vec![data.clone(), vec![0x55]]),
+            SerialMessage::Injected(data.clone()),
+            SerialMessage::ReceiveWithChecksum {
+                data: data.clone(),
+                algo: ChecksumAlgo::Bcc,
+            },
+            SerialMessage::Discarded(vec![0xFF]),
+            SerialMessage::NoResponse {
+                request: data.clone(),
+                waited: Duration::from_millis(100),
+            },
+            SerialMessage::ReceiveWithId { id: 7, data: data.clone() },
+            SerialMessage::NoResponseWithId { id: 7 },
+            SerialMessage::WrongSlaveResponse { expected: 1, got: 2 },
+            SerialMessage::SlaveOffline(3),
+            SerialMessage::SlaveOnline(3),
+            SerialMessage::GetStatus,
+            SerialMessage::Status(Status::WaitingResponse),
+            SerialMessage::GetConnectionStatus,
+            SerialMessage::Connected(true),
+            SerialMessage::SetMode(Mode::MasterStream),
+            SerialMessage::Mode(Mode::Sniff),
+            SerialMessage::Error(SIError::UnsupportedBaudRate(0)),
+            SerialMessage::Error(SIError::RequestDropped(data.clone())),
+            SerialMessage::PossibleBaudMismatch,
+            SerialMessage::SettingsQuantized {
+                requested: PortSettings {
+                    baud_rate: BaudRate::BaudOther(250_000),
+                    ..port_settings()
+                },
+                actual: port_settings(),
+            },
+            SerialMessage::Ping,
+            SerialMessage::Pong,
+            SerialMessage::Shutdown,
+            SerialMessage::FlushInput,
+            SerialMessage::FlushOutput,
+            SerialMessage::FlushAll,
+            SerialMessage::Flushed,
+            SerialMessage::GetSettings,
+            SerialMessage::Settings {
+                path: Some("/dev/ttyUSB0".to_string()),
+                baud_rate: BaudRate::Baud115200,
+                char_size: CharSize::Bits8,
+                parity: Parity::ParityNone,
+                stop_bits: StopBits::Stop1,
+                flow_control: FlowControl::FlowNone,
+                timeout: Duration::from_millis(100),
+                silence: None,
+                mode: Mode::Terminal,
+            },
+            SerialMessage::ReceiveTimed {
+                data: data.clone(),
+                at: SystemTime::UNIX_EPOCH + Duration::from_micros(1_700_000_000_123_456),
+                direction: Some(FrameDirection::Response),
+            },
+            SerialMessage::Reconnect,
+            SerialMessage::CrcError(data.clone()),
+            SerialMessage::SendWithTimeout {
+                data: data.clone(),
+                timeout: Duration::from_secs(2),
+            },
+            SerialMessage::SendNoResponse(data),
+            SerialMessage::SetRts(true),
+            SerialMessage::SetDtr(false),
+            SerialMessage::SendBreak(Duration::from_millis(250)),
+            SerialMessage::GetModemStatus,
+            SerialMessage::ModemStatus {
+                cts: true,
+                dsr: false,
+                cd: true,
+                ri: false,
+            },
+            SerialMessage::ListPortsDetailed,
+            SerialMessage::AvailablePortsDetailed(vec![PortInfo {
+                name: "/dev/ttyACM0".to_string(),
+                kind: PortKind::Usb,
+                vid: Some(0x2341),
+                pid: Some(0x0043),
+                serial_number: None,
+                manufacturer: Some("Arduino".to_string()),
+                product: None,
+            }]),
+            SerialMessage::SetTcp("192.168.1.10:4001".to_string()),
+        ];
+        for msg in messages {
+            let json = serde_json::to_string(&msg).unwrap();
+            let back: SerialMessage = serde_json::from_str(&json).unwrap();
+            assert_eq!(format!("{:?}", back), format!("{:?}", msg), "{}", json);
+        }
+    }
+
+    #[test]
+    fn external_representation() {
+        let json = serde_json::to_string(&SerialMessage::Ping).unwrap();
+        assert_eq!(json, r#""Ping""#);
+        let json = serde_json::to_string(&SerialMessage::SetBauds(BaudRate::Baud9600)).unwrap();
+        assert_eq!(json, r#"{"SetBauds":9600}"#);
+        let json = serde_json::to_string(&SerialMessage::SetMode(Mode::Master)).unwrap();
+        assert_eq!(json, r#"{"SetMode":"Master"}"#);
+    }
+
+    #[test]
+    fn unsupported_baud_rate() {
+        assert!(serde_json::from_str::<SerialMessage>(r#"{"SetBauds":0}"#).is_err());
+        let msg: SerialMessage = serde_json::from_str(r#"{"SetBauds":250000}"#).unwrap();
+        assert!(matches!(msg, SerialMessage::SetBauds(BaudRate::BaudOther(250_000))));
+    }
+}
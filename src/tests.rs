@@ -0,0 +1,2430 @@
+//! Unit tests, run without hardware against the backends of the `backend` module (and a few local
+//! ones), in both the sync and the async-channel builds.
+
+use super::*;
+use backend::MockBackend;
+use std::collections::VecDeque;
+
+/// Run a future to completion on a current thread runtime, e.g. start(), async in both builds.
+fn block_on<F: std::future::Future>(future: F) -> F::Output {
+    tokio::runtime::Builder::new_current_thread()
+        .enable_time()
+        .build()
+        .expect("runtime")
+        .block_on(future)
+}
+
+/// Call a method having a sync and an async-channel twin, awaiting it in the async-channel build.
+#[cfg(not(feature = "async-channel"))]
+macro_rules! call {
+    ($e:expr) => {
+        $e
+    };
+}
+
+/// Call a method having a sync and an async-channel twin, awaiting it in the async-channel build.
+#[cfg(feature = "async-channel")]
+macro_rules! call {
+    ($e:expr) => {
+        block_on(async { $e.await })
+    };
+}
+
+#[cfg(not(feature = "async-channel"))]
+fn channel() -> (Sender<SerialMessage>, Receiver<SerialMessage>) {
+    std::sync::mpsc::channel()
+}
+
+#[cfg(feature = "async-channel")]
+fn channel() -> (Sender<SerialMessage>, Receiver<SerialMessage>) {
+    async_channel::unbounded()
+}
+
+#[cfg(not(feature = "async-channel"))]
+fn send(sender: &Sender<SerialMessage>, msg: SerialMessage) {
+    sender.send(msg).expect("channel closed");
+}
+
+#[cfg(feature = "async-channel")]
+fn send(sender: &Sender<SerialMessage>, msg: SerialMessage) {
+    sender.send_blocking(msg).expect("channel closed");
+}
+
+/// Wait up to `timeout` for a message.
+fn recv(receiver: &Receiver<SerialMessage>, timeout: Duration) -> Option<SerialMessage> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        if let Ok(msg) = receiver.try_recv() {
+            return Some(msg);
+        }
+        if Instant::now() > deadline {
+            return None;
+        }
+        std::thread::sleep(Duration::from_micros(200));
+    }
+}
+
+/// Wait up to `timeout` for a message matching `pred`, the other messages are dropped.
+fn recv_until(
+    receiver: &Receiver<SerialMessage>,
+    timeout: Duration,
+    pred: impl Fn(&SerialMessage) -> bool,
+) -> Option<SerialMessage> {
+    let deadline = Instant::now() + timeout;
+    while let Some(msg) = recv(receiver, deadline.saturating_duration_since(Instant::now())) {
+        if pred(&msg) {
+            return Some(msg);
+        }
+    }
+    None
+}
+
+/// Returns the messages received within `wait`.
+fn collect(receiver: &Receiver<SerialMessage>, wait: Duration) -> Vec<SerialMessage> {
+    let deadline = Instant::now() + wait;
+    let mut msgs = Vec::new();
+    while let Some(msg) = recv(receiver, deadline.saturating_duration_since(Instant::now())) {
+        msgs.push(msg);
+    }
+    msgs
+}
+
+/// Returns `data` followed by its Modbus CRC16.
+fn frame(data: &[u8]) -> Vec<u8> {
+    let mut frame = data.to_vec();
+    SerialInterface::append_crc(&mut frame, CrcByteOrder::LittleEndian);
+    frame
+}
+
+/// Script `data` on the mock, the first byte `delay` after the previous one and the others right
+/// behind it. The delay makes the frame survive the clear of the read buffer preceding a read.
+fn feed_frame(mock: &MockBackend, data: &[u8], delay: Duration) {
+    if let Some((first, rest)) = data.split_first() {
+        mock.feed_byte(*first, delay);
+        mock.feed(rest, Duration::ZERO);
+    }
+}
+
+/// Returns an interface on `backend`, with its channels, the app side being returned.
+fn wired(
+    backend: impl SerialBackend + 'static,
+) -> (SerialInterface, Sender<SerialMessage>, Receiver<SerialMessage>) {
+    let (app_sender, receiver) = channel();
+    let (sender, app_receiver) = channel();
+    let si = SerialInterface::with_backend(Box::new(backend))
+        .unwrap()
+        .sender(sender)
+        .receiver(receiver);
+    (si, app_sender, app_receiver)
+}
+
+/// Spawn the main loop of `si` in `mode`, with a 100ms timeout.
+fn spawn(si: SerialInterface, mode: Mode) -> (SerialHandle, std::thread::JoinHandle<()>) {
+    let (handle, thread) = si.spawn_dedicated();
+    send(&handle.sender, SerialMessage::SetTimeout(Duration::from_millis(100)));
+    send(&handle.sender, SerialMessage::SetMode(mode));
+    (handle, thread)
+}
+
+/// Stop the main loop spawned by spawn().
+fn shutdown(handle: SerialHandle, thread: std::thread::JoinHandle<()>) {
+    send(&handle.sender, SerialMessage::Shutdown);
+    thread.join().expect("serial thread panicked");
+}
+
+#[test]
+fn mock_read_until_silence() {
+    let mock = MockBackend::new();
+    let request = modbus::build_read_holding_registers(1, 0, 1);
+    feed_frame(&mock, &request, Duration::from_millis(5));
+    let (mut si, _sender, _receiver) = wired(mock);
+    let msg = call!(si.read_until_silence(&Duration::from_millis(5))).unwrap();
+    assert!(matches!(msg, Some(SerialMessage::Receive(data)) if data == request));
+}
+
+#[test]
+fn mock_read_stream() {
+    let mock = MockBackend::new();
+    let response = frame(&[0x01, 0x03, 0x02, 0x00, 0x2A]);
+    mock.feed_byte(0xFF, Duration::from_millis(5));
+    mock.feed(&response, Duration::ZERO);
+    let (mut si, _sender, _receiver) = wired(mock);
+    let msg = si.read_stream(&Duration::from_millis(200)).unwrap();
+    assert!(matches!(msg, SerialMessage::Receive(data) if data == response));
+}
+
+#[test]
+fn mock_write_read() {
+    let mock = MockBackend::new();
+    let request = modbus::build_read_holding_registers(1, 0, 1);
+    let response = frame(&[0x01, 0x03, 0x02, 0x00, 0x2A]);
+    feed_frame(&mock, &response, Duration::from_millis(10));
+    let (si, _sender, receiver) = wired(mock.clone());
+    let mut si = si.silence(Duration::from_millis(5));
+    si.timeout = Duration::from_millis(200);
+    si.set_mode(Mode::Master).unwrap();
+    let timeout = Duration::from_millis(200);
+    let msg = call!(si.write_read(request.clone(), &timeout, None)).unwrap();
+    assert!(msg.is_none());
+    let msg = recv(&receiver, timeout);
+    assert!(matches!(msg, Some(SerialMessage::DataSent(data)) if data == request));
+    let msg = recv(&receiver, timeout);
+    assert!(matches!(msg, Some(SerialMessage::Receive(data)) if data == response));
+    assert_eq!(*mock.written().lock().unwrap(), request);
+}
+
+#[test]
+fn open_missing_port() {
+    let mut si = SerialInterface::new()
+        .unwrap()
+        .path("/dev/serial-thread-missing".to_string());
+    assert!(matches!(si.open(), Err(SIError::CannotOpenPort(_))));
+}
+
+#[test]
+fn open_error_mapping() {
+    use std::io::ErrorKind;
+    let map = |kind| SerialInterface::open_error_kind(kind, String::new());
+    assert!(matches!(map(Some(ErrorKind::ResourceBusy)), SIError::PortBusy(_)));
+    #[cfg(not(windows))]
+    assert!(matches!(
+        map(Some(ErrorKind::PermissionDenied)),
+        SIError::PortPermissionDenied(_)
+    ));
+    assert!(matches!(map(Some(ErrorKind::NotFound)), SIError::CannotOpenPort(_)));
+    assert!(matches!(map(None), SIError::CannotOpenPort(_)));
+}
+
+#[cfg(unix)]
+#[test]
+fn open_error_from_description() {
+    // the serial crate reports the strerror() message of the failing open
+    let open_error = |code| {
+        let message = std::io::Error::from_raw_os_error(code).to_string();
+        let (message, _) = message.rsplit_once(" (os error ").unwrap();
+        SerialInterface::open_error(serial::Error::new(serial::ErrorKind::NoDevice, message))
+    };
+    assert!(matches!(open_error(libc::EBUSY), SIError::PortBusy(_)));
+    assert!(matches!(open_error(libc::EACCES), SIError::PortPermissionDenied(_)));
+    assert!(matches!(open_error(libc::ENOENT), SIError::CannotOpenPort(_)));
+    let e = serial::Error::new(serial::ErrorKind::InvalidInput, "Device or resource busy");
+    assert!(matches!(SerialInterface::open_error(e), SIError::CannotOpenPort(_)));
+}
+
+#[test]
+fn round_robin_fair_order() {
+    let mut si = SerialInterface::new()
+        .unwrap()
+        .schedule(Schedule::RoundRobinBySlave);
+    for (n, id) in [1, 1, 1, 2, 2, 3].into_iter().enumerate() {
+        let request = Request {
+            id: None,
+            data: vec![id, n as u8],
+            timeout: None,
+            response: true,
+        };
+        assert!(si.schedule_request(request).is_none());
+    }
+    let order: Vec<Vec<u8>> = std::iter::from_fn(|| si.next_request())
+        .map(|request| request.data)
+        .collect();
+    assert_eq!(order, [[1, 0], [2, 3], [3, 5], [1, 1], [2, 4], [1, 2]]);
+}
+
+#[test]
+fn round_robin_queue_rejected_on_stop() {
+    let si = SerialInterface::with_backend(Box::new(MockBackend::new()))
+        .unwrap()
+        .schedule(Schedule::RoundRobinBySlave);
+    let (handle, thread) = spawn(si, Mode::Master);
+    send(&handle.sender, SerialMessage::SetTimeout(Duration::from_secs(2)));
+    let requests: Vec<Vec<u8>> = (1..=3)
+        .map(|id| modbus::build_read_holding_registers(id, 0, 1))
+        .collect();
+    send(&handle.sender, SerialMessage::Send(requests[0].clone()));
+    // the next requests are queued while waiting for the (missing) response to the first one
+    let sent = recv(&handle.receiver, Duration::from_secs(1));
+    assert!(matches!(sent, Some(SerialMessage::DataSent(data)) if data == requests[0]));
+    for request in &requests[1..] {
+        send(&handle.sender, SerialMessage::Send(request.clone()));
+    }
+    send(&handle.sender, SerialMessage::SetMode(Mode::Stop));
+    let dropped: Vec<Vec<u8>> = collect(&handle.receiver, Duration::from_millis(200))
+        .into_iter()
+        .filter_map(|msg| match msg {
+            SerialMessage::Error(SIError::RequestDropped(data)) => Some(data),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(dropped, requests[1..]);
+    shutdown(handle, thread);
+}
+
+#[test]
+fn crc_byte_orders() {
+    let data = [0x01, 0x03, 0x00, 0x00, 0x00, 0x0A];
+    assert_eq!(SerialInterface::crc_bytes(&data, CrcByteOrder::LittleEndian), [0xC5, 0xCD]);
+    assert_eq!(SerialInterface::crc_bytes(&data, CrcByteOrder::BigEndian), [0xCD, 0xC5]);
+    assert_eq!(SerialInterface::crc16(&data).to_be_bytes(), [0xC5, 0xCD]);
+    for (order, other) in [
+        (CrcByteOrder::LittleEndian, CrcByteOrder::BigEndian),
+        (CrcByteOrder::BigEndian, CrcByteOrder::LittleEndian),
+    ] {
+        let mut frame = data.to_vec();
+        SerialInterface::append_crc(&mut frame, order);
+        assert!(SerialInterface::check_crc(&frame, order));
+        assert!(!SerialInterface::check_crc(&frame, other));
+        let mut buffer = vec![0xAA, 0x55];
+        buffer.extend_from_slice(&frame);
+        let decoded = SerialInterface::try_decode_buffer(
+            &buffer,
+            order,
+            ScanPreference::ForwardFirst,
+            &[ChecksumAlgo::CrcModbus],
+            5,
+        );
+        assert_eq!(decoded, Some((2, frame.len(), ChecksumAlgo::CrcModbus)));
+    }
+}
+
+#[test]
+fn crc_big_endian_stream() {
+    let mut response = vec![0x01, 0x03, 0x02, 0x00, 0x2A];
+    SerialInterface::append_crc(&mut response, CrcByteOrder::BigEndian);
+    let mock = MockBackend::new();
+    feed_frame(&mock, &response, Duration::from_millis(5));
+    let (si, _sender, _receiver) = wired(mock);
+    let mut si = si.crc_byte_order(CrcByteOrder::BigEndian);
+    let msg = si.read_stream(&Duration::from_millis(200)).unwrap();
+    assert!(matches!(msg, SerialMessage::Receive(data) if data == response));
+}
+
+#[test]
+fn idle_wait_latency() {
+    let mock = MockBackend::new();
+    let si = SerialInterface::with_backend(Box::new(mock.clone()))
+        .unwrap()
+        .idle_wait(Duration::from_secs(1));
+    let (handle, thread) = spawn(si, Mode::Sniff);
+    // a message wakes the idle wait
+    std::thread::sleep(Duration::from_millis(50));
+    let start = Instant::now();
+    send(&handle.sender, SerialMessage::Ping);
+    let pong = recv_until(&handle.receiver, Duration::from_secs(1), |msg| {
+        matches!(msg, SerialMessage::Pong)
+    });
+    assert!(pong.is_some());
+    assert!(start.elapsed() < Duration::from_millis(200));
+    // so does a frame: the line is polled between slices of the idle wait
+    let request = modbus::build_read_holding_registers(1, 0, 1);
+    let start = Instant::now();
+    mock.feed(&request, Duration::ZERO);
+    let msg = recv_until(&handle.receiver, Duration::from_secs(1), |msg| {
+        matches!(msg, SerialMessage::Receive(_))
+    });
+    assert!(matches!(msg, Some(SerialMessage::Receive(data)) if data == request));
+    assert!(start.elapsed() < Duration::from_millis(200));
+    shutdown(handle, thread);
+}
+
+/// Backend failing its first `failures` writes with `kind`, then behaving as `inner`.
+struct Flaky {
+    failures: usize,
+    kind: std::io::ErrorKind,
+    inner: MockBackend,
+}
+
+impl Read for Flaky {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.inner.read(buf)
+    }
+}
+
+impl Write for Flaky {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        if self.failures > 0 {
+            self.failures -= 1;
+            return Err(self.kind.into());
+        }
+        self.inner.write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn write_retry_transient_error() {
+    let request = modbus::build_read_holding_registers(1, 0, 1);
+    for (retries, kind, written) in [
+        (1, std::io::ErrorKind::WouldBlock, true),
+        (0, std::io::ErrorKind::WouldBlock, false),
+        (3, std::io::ErrorKind::BrokenPipe, false),
+    ] {
+        let mock = MockBackend::new();
+        let flaky = Flaky {
+            failures: 1,
+            kind,
+            inner: mock.clone(),
+        };
+        let mut si = SerialInterface::with_backend(Box::new(flaky))
+            .unwrap()
+            .write_retries(retries)
+            .write_retry_delay(Duration::from_millis(1));
+        let result = call!(si.write_port(&request));
+        assert_eq!(result.is_ok(), written, "{:?} with {} retries", kind, retries);
+        if written {
+            assert_eq!(*mock.written().lock().unwrap(), request);
+        } else {
+            assert!(matches!(result, Err(SIError::CannotWritePort)));
+        }
+    }
+}
+
+#[test]
+fn wait_idle_after_burst() {
+    let mock = MockBackend::new().script(&[0x55; 10], Duration::from_millis(5));
+    let mut si = SerialInterface::with_backend(Box::new(mock.clone()))
+        .unwrap()
+        .silence(Duration::from_millis(20));
+    // the line is still busy when the timeout is reached
+    assert!(!call!(si.wait_idle(Duration::from_millis(15))).unwrap());
+    // then the backend stops emitting
+    let start = Instant::now();
+    assert!(call!(si.wait_idle(Duration::from_secs(1))).unwrap());
+    assert!(start.elapsed() >= Duration::from_millis(20));
+    assert!(start.elapsed() < Duration::from_millis(500));
+    mock.feed(&[0x55], Duration::ZERO);
+    assert!(call!(si.wait_idle(Duration::from_secs(1))).unwrap());
+    let mut rest = [0u8; 1];
+    assert_eq!(si.port.as_mut().unwrap().read(&mut rest).unwrap(), 0);
+}
+
+#[test]
+fn send_break_unsupported() {
+    let mut si = SerialInterface::with_backend(Box::new(MockBackend::new())).unwrap();
+    let result = call!(si.send_break(Duration::from_millis(1)));
+    assert!(matches!(result, Err(SIError::BreakUnsupported)));
+}
+
+#[test]
+fn control_lines_unsupported() {
+    let mut si = SerialInterface::with_backend(Box::new(MockBackend::new())).unwrap();
+    assert!(matches!(si.set_rts(true), Err(SIError::ControlLinesUnsupported)));
+    assert!(matches!(si.set_dtr(false), Err(SIError::ControlLinesUnsupported)));
+    let mut si = SerialInterface::new().unwrap();
+    assert!(matches!(si.set_rts(true), Err(SIError::PortNotOpened)));
+}
+
+/// Returns a Master on `mock` with a 5ms silence and a 200ms timeout, and its app receiver.
+fn master(mock: &MockBackend) -> (SerialInterface, Receiver<SerialMessage>) {
+    let (si, _sender, receiver) = wired(mock.clone());
+    let mut si = si.silence(Duration::from_millis(5));
+    si.timeout = Duration::from_millis(200);
+    si.set_mode(Mode::Master).unwrap();
+    (si, receiver)
+}
+
+#[test]
+fn deadline_cuts_retries() {
+    let mock = MockBackend::new();
+    let (si, receiver) = master(&mock);
+    let mut si = si.retries(5);
+    let request = modbus::build_read_holding_registers(1, 0, 1);
+    let start = Instant::now();
+    let deadline = Some(start + Duration::from_millis(120));
+    let timeout = Duration::from_millis(50);
+    call!(si.write_read(request.clone(), &timeout, deadline)).unwrap();
+    // 2 full attempts, then a third one cut at the deadline
+    assert_eq!(mock.written().lock().unwrap().len(), 3 * request.len());
+    assert!(start.elapsed() < Duration::from_millis(250));
+    let msg = recv_until(&receiver, timeout, |msg| {
+        matches!(msg, SerialMessage::NoResponse { .. })
+    });
+    assert!(msg.is_some());
+}
+
+#[test]
+fn foreign_frames_do_not_extend_attempt() {
+    let mock = MockBackend::new();
+    let foreign = frame(&[0x02, 0x03, 0x02, 0x00, 0x2A]);
+    for _ in 0..8 {
+        feed_frame(&mock, &foreign, Duration::from_millis(30));
+    }
+    let (si, receiver) = master(&mock);
+    let mut si = si.check_response_id(true).retry_on(RetryTrigger::NONE);
+    let request = modbus::build_read_holding_registers(1, 0, 1);
+    let start = Instant::now();
+    let timeout = Duration::from_millis(100);
+    call!(si.write_read(request, &timeout, None)).unwrap();
+    assert!(start.elapsed() < Duration::from_millis(200));
+    let msgs = collect(&receiver, Duration::from_millis(10));
+    assert!(msgs.iter().any(|msg| matches!(msg, SerialMessage::WrongSlaveResponse { .. })));
+    assert!(matches!(msgs.last(), Some(SerialMessage::NoResponse { .. })));
+}
+
+#[test]
+fn poll_frame_split() {
+    let mock = MockBackend::new();
+    let response = frame(&[0x01, 0x03, 0x02, 0x00, 0x2A]);
+    let silence = Duration::from_millis(20);
+    mock.feed(&response[..3], Duration::ZERO);
+    let mut si = SerialInterface::with_backend(Box::new(mock.clone())).unwrap();
+    assert_eq!(si.poll_frame(&silence).unwrap(), None);
+    // the rest of the frame is received before silence elapses
+    mock.feed(&response[3..], Duration::ZERO);
+    assert_eq!(si.poll_frame(&silence).unwrap(), None);
+    std::thread::sleep(silence * 2);
+    assert_eq!(si.poll_frame(&silence).unwrap(), Some(response.clone()));
+    assert_eq!(si.poll_frame(&silence).unwrap(), None);
+
+    // a partial frame kept by poll_frame() is completed by read_frame_into()
+    mock.feed(&response[..3], Duration::ZERO);
+    assert_eq!(si.poll_frame(&silence).unwrap(), None);
+    mock.feed(&response[3..], Duration::ZERO);
+    let mut buf = Vec::with_capacity(16);
+    let timeout = Duration::from_millis(200);
+    assert!(si.read_frame_into(&mut buf, &silence, &timeout).unwrap());
+    assert_eq!(buf, response);
+    // and does not leak into the next frame
+    mock.feed(&response, Duration::from_millis(5));
+    assert!(si.read_frame_into(&mut buf, &silence, &timeout).unwrap());
+    assert_eq!(buf, response);
+}
+
+#[test]
+fn stream_back_to_back_frames() {
+    let first = frame(&[0x01, 0x03, 0x02, 0x00, 0x2A]);
+    let second = frame(&[0x02, 0x03, 0x02, 0x00, 0x2B]);
+    let burst = [first.clone(), second.clone()].concat();
+    let mock = MockBackend::new();
+    feed_frame(&mock, &burst, Duration::from_millis(5));
+    let (mut si, _sender, _receiver) = wired(mock.clone());
+    let timeout = Duration::from_millis(200);
+    let msg = si.read_stream(&timeout).unwrap();
+    assert!(matches!(msg, SerialMessage::Receive(data) if data == first));
+    let msg = si.read_stream(&timeout).unwrap();
+    assert!(matches!(msg, SerialMessage::Receive(data) if data == second));
+
+    // the residual of a frame is dropped by FlushInput and by a mode change
+    for flush in [true, false] {
+        feed_frame(&mock, &burst, Duration::from_millis(5));
+        let msg = si.read_stream(&timeout).unwrap();
+        assert!(matches!(msg, SerialMessage::Receive(data) if data == first));
+        if flush {
+            si.flush_buffers(true, false).unwrap();
+        } else {
+            si.switch_mode(Mode::Sniff);
+            si.switch_mode(Mode::Stop);
+        }
+        let msg = si.read_stream(&Duration::from_millis(20)).unwrap();
+        assert!(matches!(msg, SerialMessage::NoResponse { .. }));
+    }
+}
+
+#[cfg(feature = "async-channel")]
+#[test]
+fn oneshot_response_not_shared() {
+    let mock = MockBackend::new();
+    let request = modbus::build_read_holding_registers(1, 0, 1);
+    let response = frame(&[0x01, 0x03, 0x02, 0x00, 0x2A]);
+    feed_frame(&mock, &response, Duration::from_millis(10));
+    let (mut si, receiver) = master(&mock);
+    let result = block_on(si.write_read_oneshot(request, Duration::from_millis(200)));
+    assert_eq!(result.unwrap(), Some(response));
+    let msgs = collect(&receiver, Duration::from_millis(20));
+    assert!(msgs.iter().any(|msg| matches!(msg, SerialMessage::DataSent(_))));
+    assert!(!msgs.iter().any(|msg| matches!(
+        msg,
+        SerialMessage::Receive(_) | SerialMessage::ReceiveWithId { .. }
+    )));
+}
+
+#[test]
+fn loopback_master_slave() {
+    let (a, b) = backend::loopback_pair();
+    let master = SerialInterface::with_backend(Box::new(a)).unwrap();
+    let slave = SerialInterface::with_backend(Box::new(b))
+        .unwrap()
+        .modbus_id(1);
+    let (master, master_thread) = spawn(master, Mode::Master);
+    let (slave, slave_thread) = spawn(slave, Mode::Slave);
+    send(&master.sender, SerialMessage::SetTimeout(Duration::from_millis(500)));
+    // both ends are in their mode once they answered a ping, so the request is not drained by the
+    // mode change of the slave
+    for handle in [&master, &slave] {
+        send(&handle.sender, SerialMessage::Ping);
+        let msg = recv_until(&handle.receiver, Duration::from_secs(1), |msg| {
+            matches!(msg, SerialMessage::Pong)
+        });
+        assert!(msg.is_some());
+    }
+    let request = modbus::build_read_holding_registers(1, 0x0010, 2);
+    send(&master.sender, SerialMessage::Send(request.clone()));
+
+    // the slave app answers the request it receives
+    let msg = recv_until(&slave.receiver, Duration::from_secs(1), |msg| {
+        matches!(msg, SerialMessage::Receive(_))
+    });
+    let Some(SerialMessage::Receive(data)) = msg else {
+        panic!("no request received by the slave");
+    };
+    let pdu = modbus::parse_request(&data).unwrap();
+    assert_eq!(
+        pdu.request,
+        modbus::ModbusRequest::ReadHoldingRegisters {
+            start: 0x0010,
+            count: 2
+        }
+    );
+    let response = frame(&[pdu.address, 0x03, 0x04, 0x00, 0x2A, 0x01, 0x00]);
+    send(&slave.sender, SerialMessage::Send(response));
+
+    let msg = recv_until(&master.receiver, Duration::from_secs(1), |msg| {
+        matches!(msg, SerialMessage::Receive(_))
+    });
+    let Some(SerialMessage::Receive(data)) = msg else {
+        panic!("no response received by the master");
+    };
+    assert_eq!(
+        modbus::decode_response(request[1], &data).unwrap(),
+        modbus::ModbusResponse::ReadRegisters(vec![0x002A, 0x0100])
+    );
+    shutdown(master, master_thread);
+    shutdown(slave, slave_thread);
+}
+
+/// Returns a path in the temp directory, unique to this process and `name`.
+fn temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("serial-thread-{}-{}", std::process::id(), name))
+}
+
+#[test]
+fn capture_line_round_trip() {
+    for (elapsed, data) in [
+        (Duration::ZERO, vec![]),
+        (Duration::from_micros(1520), frame(&[0x01, 0x03, 0x00, 0x0A, 0x00, 0x01])),
+        (Duration::from_secs(3600), vec![0x00, 0xFF, 0x7F]),
+    ] {
+        let line = capture::format_line(elapsed, &data);
+        assert_eq!(capture::parse_line(&line), Some((elapsed, data)));
+    }
+    assert_eq!(capture::format_line(Duration::from_micros(1520), &[0x01, 0xAB]), "1520 01AB");
+    assert_eq!(
+        capture::parse_line("1520 01ab\n"),
+        Some((Duration::from_micros(1520), vec![0x01, 0xAB]))
+    );
+}
+
+#[test]
+fn capture_malformed_line() {
+    for line in [
+        "",
+        "1520",
+        "01AB 1520",
+        "-1 01AB",
+        "1.5 01AB",
+        "1520 01A",
+        "1520 01AG",
+        "1520 01 AB",
+        "1520 +1AB",
+    ] {
+        assert_eq!(capture::parse_line(line), None, "{:?}", line);
+    }
+}
+
+#[test]
+fn capture_replay() {
+    let path = temp_path("replay.cap");
+    let frames = [vec![0x01, 0x03, 0x00], vec![0x02], vec![0x03, 0x04]];
+    let content: String = frames
+        .iter()
+        .enumerate()
+        // one hour apart: any delay honored by the replay would time the test out
+        .map(|(i, data)| capture::format_line(Duration::from_secs(3600 * i as u64), data) + "\n")
+        .collect();
+    std::fs::write(&path, content).unwrap();
+    let (sender, receiver) = channel();
+    let start = Instant::now();
+    call!(capture::replay(path.clone(), sender.clone(), f64::INFINITY)).unwrap();
+    assert!(start.elapsed() < Duration::from_secs(1));
+    for data in &frames {
+        let msg = receiver.try_recv().unwrap();
+        assert!(matches!(msg, SerialMessage::Receive(d) if &d == data));
+    }
+    assert!(receiver.try_recv().is_err());
+
+    let result = call!(capture::replay(path.clone(), sender.clone(), 0.0));
+    assert!(matches!(result, Err(SIError::WrongReadArguments)));
+    let result = call!(capture::replay(path.clone(), sender.clone(), f64::NAN));
+    assert!(matches!(result, Err(SIError::WrongReadArguments)));
+
+    // a malformed line fails the replay before anything is sent
+    std::fs::write(&path, "0 0103\nnot a frame\n").unwrap();
+    let result = call!(capture::replay(path.clone(), sender.clone(), f64::INFINITY));
+    assert!(matches!(result, Err(SIError::CannotReadCapture(_))));
+    assert!(receiver.try_recv().is_err());
+    std::fs::remove_file(&path).unwrap();
+    let result = call!(capture::replay(path, sender, f64::INFINITY));
+    assert!(matches!(result, Err(SIError::CannotReadCapture(_))));
+}
+
+#[test]
+fn capture_writer_replay_capture() {
+    let path = temp_path("writer.cap");
+    let _ = std::fs::remove_file(&path);
+    let mut writer = capture::CaptureWriter::open(&path).unwrap();
+    writer.write_frame(&[0x01, 0x03]).unwrap();
+    std::thread::sleep(Duration::from_millis(10));
+    writer.write_frame(&[]).unwrap();
+    drop(writer);
+    // reopening appends to the file
+    let mut writer = capture::CaptureWriter::open(&path).unwrap();
+    writer.write_frame(&[0xFF]).unwrap();
+    drop(writer);
+
+    let frames: Vec<_> = capture::replay_capture(path.clone()).unwrap().collect();
+    let data: Vec<_> = frames.iter().map(|(_, data)| data.clone()).collect();
+    assert_eq!(data, vec![vec![0x01, 0x03], vec![], vec![0xFF]]);
+    assert!(frames[1].0 >= frames[0].0 + Duration::from_millis(10));
+    // the elapsed time restarts with the second writer
+    assert!(frames[2].0 < frames[1].0);
+
+    std::fs::write(&path, "0 01\n\n10 0\n").unwrap();
+    assert!(matches!(
+        capture::replay_capture(path.clone()),
+        Err(SIError::CannotReadCapture(_))
+    ));
+    std::fs::remove_file(&path).unwrap();
+    assert!(matches!(
+        capture::replay_capture(path),
+        Err(SIError::CannotReadCapture(_))
+    ));
+}
+
+#[test]
+fn sniff_capture_to() {
+    let path = temp_path("sniff.cap");
+    let _ = std::fs::remove_file(&path);
+    let request = modbus::build_read_holding_registers(1, 0, 1);
+    let response = frame(&[0x01, 0x03, 0x02, 0x00, 0x2A]);
+    let mock = MockBackend::new();
+    feed_frame(&mock, &request, Duration::from_millis(50));
+    feed_frame(&mock, &response, Duration::from_millis(20));
+    let (si, _sender, _receiver) = wired(mock.clone());
+    let si = si.silence(Duration::from_millis(5)).capture_to(path.clone());
+    let (handle, thread) = spawn(si, Mode::Sniff);
+    for _ in 0..2 {
+        let msg = recv_until(&handle.receiver, Duration::from_secs(1), |msg| {
+            matches!(msg, SerialMessage::Receive(_))
+        });
+        assert!(msg.is_some());
+    }
+    shutdown(handle, thread);
+
+    let frames: Vec<_> = capture::replay_capture(path.clone()).unwrap().collect();
+    assert_eq!(frames.len(), 2);
+    assert_eq!(frames[0].1, request);
+    assert_eq!(frames[1].1, response);
+    assert!(frames[1].0 > frames[0].0);
+    std::fs::remove_file(&path).unwrap();
+}
+
+/// Returns a read holding registers response of `count` registers from slave 1.
+fn registers_response(count: u16) -> Vec<u8> {
+    let mut pdu = vec![0x01, 0x03, (count * 2) as u8];
+    for i in 0..count {
+        pdu.extend_from_slice(&i.to_be_bytes());
+    }
+    frame(&pdu)
+}
+
+#[test]
+fn length_field_register_counts() {
+    let timeout = Duration::from_millis(200);
+    for count in [1, 2, 10, 125] {
+        let response = registers_response(count);
+        let mock = MockBackend::new();
+        // a mid-frame gap longer than silence: the frame end comes from the length field
+        feed_frame(&mock, &response[..3], Duration::from_millis(10));
+        feed_frame(&mock, &response[3..], Duration::from_millis(20));
+        let (si, receiver) = master(&mock);
+        let mut si = si.length_field(3, 2, 2);
+        let request = modbus::build_read_holding_registers(1, 0, count);
+        call!(si.write_read(request.clone(), &timeout, None)).unwrap();
+        let msg = recv_until(&receiver, timeout, |msg| {
+            matches!(msg, SerialMessage::Receive(_) | SerialMessage::NoResponse { .. })
+        });
+        assert!(matches!(msg, Some(SerialMessage::Receive(data)) if data == response));
+        assert_eq!(
+            modbus::decode_response(request[1], &response).unwrap(),
+            modbus::ModbusResponse::ReadRegisters((0..count).collect())
+        );
+    }
+}
+
+#[test]
+fn length_field_successive_frames() {
+    let first = registers_response(1);
+    let second = registers_response(3);
+    let mock = MockBackend::new();
+    feed_frame(&mock, &first, Duration::from_millis(10));
+    feed_frame(&mock, &second, Duration::from_millis(10));
+    let (mut si, _sender, _receiver) = wired(mock.clone());
+    let timeout = Duration::from_millis(100);
+    let msg = si.read_with_length_field(3, 2, 2, &timeout).unwrap();
+    assert!(matches!(msg, SerialMessage::Receive(data) if data == first));
+    let msg = si.read_with_length_field(3, 2, 2, &timeout).unwrap();
+    assert!(matches!(msg, SerialMessage::Receive(data) if data == second));
+    // a truncated frame is returned as is at timeout, nothing at all as NoResponse
+    mock.feed(&second[..5], Duration::from_millis(10));
+    let msg = si.read_with_length_field(3, 2, 2, &timeout).unwrap();
+    assert!(matches!(msg, SerialMessage::Receive(data) if data == second[..5]));
+    let msg = si.read_with_length_field(3, 2, 2, &timeout).unwrap();
+    assert!(matches!(msg, SerialMessage::NoResponse { .. }));
+    let result = si.read_with_length_field(2, 2, 2, &timeout);
+    assert!(matches!(result, Err(SIError::WrongReadArguments)));
+}
+
+#[test]
+fn auto_crc_bare_and_framed() {
+    let bare = vec![0x01, 0x03, 0x00, 0x00, 0x00, 0x01];
+    let framed = frame(&bare);
+    let (si, _sender, _receiver) = wired(MockBackend::new());
+    let mut si = si.auto_crc(true);
+    si.timeout = Duration::from_millis(100);
+    si.set_mode(Mode::Master).unwrap();
+    assert_eq!(si.with_auto_crc(bare.clone()), framed);
+    assert_eq!(si.with_auto_crc(framed.clone()), framed);
+    let mut si = si.crc_byte_order(CrcByteOrder::BigEndian);
+    let mut big_endian = bare.clone();
+    SerialInterface::append_crc(&mut big_endian, CrcByteOrder::BigEndian);
+    assert_eq!(si.with_auto_crc(bare.clone()), big_endian);
+    assert_eq!(si.with_auto_crc(big_endian.clone()), big_endian);
+    // only in Master modes
+    si.switch_mode(Mode::Slave);
+    assert_eq!(si.with_auto_crc(bare.clone()), bare);
+    let (si, _sender, _receiver) = wired(MockBackend::new());
+    let mut si = si.auto_crc(false);
+    si.timeout = Duration::from_millis(100);
+    si.set_mode(Mode::Master).unwrap();
+    assert_eq!(si.with_auto_crc(bare.clone()), bare);
+
+    // through the channel: Send gets its CRC, SendRaw is written as is
+    let mock = MockBackend::new();
+    let (si, _sender, _receiver) = wired(mock.clone());
+    let si = si.auto_crc(true).silence(Duration::from_millis(5));
+    let (handle, thread) = spawn(si, Mode::Master);
+    for (msg, written) in [
+        (SerialMessage::Send(bare.clone()), framed.clone()),
+        (SerialMessage::Send(framed.clone()), framed.clone()),
+        (SerialMessage::SendRaw(bare.clone()), bare.clone()),
+    ] {
+        mock.written().lock().unwrap().clear();
+        send(&handle.sender, msg);
+        let msg = recv_until(&handle.receiver, Duration::from_secs(1), |msg| {
+            matches!(msg, SerialMessage::NoResponse { .. })
+        });
+        assert!(msg.is_some());
+        assert_eq!(*mock.written().lock().unwrap(), written);
+    }
+    shutdown(handle, thread);
+}
+
+#[test]
+fn multi_checksum_stream() {
+    assert_eq!(SerialInterface::crc16_ccitt(b"123456789"), 0x29B1);
+    let data = [0x10, 0x20, 0x30, 0x41, 0x52];
+    let crc_modbus = frame(&data);
+    let mut crc_ccitt = data.to_vec();
+    crc_ccitt.extend_from_slice(&SerialInterface::crc16_ccitt(&data).to_be_bytes());
+    let mut bcc = data.to_vec();
+    bcc.push(data.iter().fold(0, |acc, b| acc ^ b));
+    let mut lrc = data.to_vec();
+    lrc.push(SerialInterface::lrc(&data));
+    let frames = [
+        (crc_modbus, ChecksumAlgo::CrcModbus),
+        (crc_ccitt, ChecksumAlgo::CrcCcitt),
+        (bcc, ChecksumAlgo::Bcc),
+        (lrc, ChecksumAlgo::Lrc),
+    ];
+    let timeout = Duration::from_millis(100);
+    let mock = MockBackend::new();
+    let (si, _sender, _receiver) = wired(mock.clone());
+    let mut si = si.multi_checksum(true);
+    for (frame, algo) in &frames {
+        feed_frame(&mock, frame, Duration::from_millis(10));
+        let msg = si.read_stream(&timeout).unwrap();
+        assert!(
+            matches!(
+                &msg,
+                SerialMessage::ReceiveWithChecksum { data, algo: a } if data == frame && a == algo
+            ),
+            "{:?}",
+            msg
+        );
+    }
+    // without multi_checksum only the Modbus CRC is accepted
+    let mut si = si.multi_checksum(false);
+    for (frame, algo) in &frames {
+        feed_frame(&mock, frame, Duration::from_millis(10));
+        let msg = si.read_stream(&timeout).unwrap();
+        if *algo == ChecksumAlgo::CrcModbus {
+            assert!(matches!(msg, SerialMessage::Receive(data) if &data == frame));
+        } else {
+            assert!(matches!(msg, SerialMessage::NoResponse { .. }), "{:?}", msg);
+        }
+    }
+}
+
+#[test]
+fn foreign_frame_then_response() {
+    let mock = MockBackend::new();
+    let foreign = frame(&[0x02, 0x03, 0x02, 0x00, 0x2B]);
+    let response = frame(&[0x01, 0x03, 0x02, 0x00, 0x2A]);
+    feed_frame(&mock, &foreign, Duration::from_millis(10));
+    feed_frame(&mock, &response, Duration::from_millis(20));
+    let (si, receiver) = master(&mock);
+    let mut si = si.check_response_id(true).retry_on(RetryTrigger::NONE);
+    let request = modbus::build_read_holding_registers(1, 0, 1);
+    call!(si.write_read(request.clone(), &Duration::from_millis(200), None)).unwrap();
+    assert_eq!(*mock.written().lock().unwrap(), request);
+    let msgs: Vec<_> = collect(&receiver, Duration::from_millis(10))
+        .into_iter()
+        .filter(|msg| !matches!(msg, SerialMessage::DataSent(_)))
+        .collect();
+    assert_eq!(msgs.len(), 2, "{:?}", msgs);
+    assert!(matches!(msgs[0], SerialMessage::WrongSlaveResponse { expected: 1, got: 2 }));
+    assert!(matches!(&msgs[1], SerialMessage::Receive(data) if data == &response));
+
+    // without the check, the foreign frame is taken as the response
+    let mock = MockBackend::new();
+    feed_frame(&mock, &foreign, Duration::from_millis(10));
+    feed_frame(&mock, &response, Duration::from_millis(20));
+    let (si, receiver) = master(&mock);
+    let mut si = si.check_response_id(false);
+    call!(si.write_read(request, &Duration::from_millis(200), None)).unwrap();
+    let msg = recv_until(&receiver, Duration::from_millis(10), |msg| {
+        !matches!(msg, SerialMessage::DataSent(_))
+    });
+    assert!(matches!(msg, Some(SerialMessage::Receive(data)) if data == foreign));
+}
+
+/// Backend answering each write with the next of `replies` (nothing for None), as a slave would.
+struct Responder {
+    replies: VecDeque<Option<Vec<u8>>>,
+    inner: MockBackend,
+}
+
+impl Read for Responder {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.inner.read(buf)
+    }
+}
+
+impl Write for Responder {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        if let Some(Some(reply)) = self.replies.pop_front() {
+            feed_frame(&self.inner, &reply, Duration::from_millis(2));
+        }
+        self.inner.write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Returns a Master as master() does, on a Responder answering with `replies`, and the mock behind
+/// it.
+fn responder(
+    replies: Vec<Option<Vec<u8>>>,
+) -> (SerialInterface, Receiver<SerialMessage>, MockBackend) {
+    let mock = MockBackend::new();
+    let (si, _sender, receiver) = wired(Responder {
+        replies: replies.into(),
+        inner: mock.clone(),
+    });
+    let mut si = si.silence(Duration::from_millis(5));
+    si.timeout = Duration::from_millis(200);
+    si.set_mode(Mode::Master).unwrap();
+    (si, receiver, mock)
+}
+
+#[test]
+fn retry_per_trigger() {
+    let request = modbus::build_read_holding_registers(1, 0, 1);
+    let response = frame(&[0x01, 0x03, 0x02, 0x00, 0x2A]);
+    let mut bad_crc = response.clone();
+    *bad_crc.last_mut().unwrap() ^= 0xFF;
+    let foreign = frame(&[0x02, 0x03, 0x02, 0x00, 0x2A]);
+    let triggers = [
+        (RetryTrigger::TIMEOUT, None),
+        (RetryTrigger::CRC_ERROR, Some(bad_crc)),
+        (RetryTrigger::WRONG_SLAVE, Some(foreign)),
+    ];
+    for (trigger, failure) in &triggers {
+        // a foreign frame not retried leaves the attempt without response: it ends on a timeout
+        let others = triggers
+            .iter()
+            .map(|(t, _)| *t)
+            .filter(|t| t != trigger)
+            .filter(|t| *trigger != RetryTrigger::WRONG_SLAVE || *t != RetryTrigger::TIMEOUT)
+            .fold(RetryTrigger::NONE, |acc, t| acc | t);
+        assert!(RetryTrigger::ALL.contains(*trigger) && !others.contains(*trigger));
+        for (retry_on, attempts) in [(*trigger, 2), (others, 1), (RetryTrigger::NONE, 1)] {
+            let (si, receiver, mock) =
+                responder(vec![failure.clone(), Some(response.clone())]);
+            let mut si = si.retries(1).retry_on(retry_on).check_response_id(true);
+            let timeout = Duration::from_millis(100);
+            call!(si.write_read(request.clone(), &timeout, None)).unwrap();
+            let written = mock.written().lock().unwrap().len();
+            assert_eq!(written, attempts * request.len(), "{:?} on {:?}", trigger, retry_on);
+            let msgs = collect(&receiver, Duration::from_millis(10));
+            let retried =
+                matches!(msgs.last(), Some(SerialMessage::Receive(data)) if data == &response);
+            assert_eq!(retried, attempts == 2, "{:?} on {:?}: {:?}", trigger, retry_on, msgs);
+            // a request is reported once whatever the number of attempts
+            let sent = msgs.iter().filter(|msg| matches!(msg, SerialMessage::DataSent(_)));
+            assert_eq!(sent.count(), 1);
+        }
+    }
+}
+
+#[test]
+fn retry_third_attempt() {
+    let request = modbus::build_read_holding_registers(1, 0, 1);
+    let response = frame(&[0x01, 0x03, 0x02, 0x00, 0x2A]);
+    let timeout = Duration::from_millis(50);
+    for (retries, answered) in [(2, true), (1, false)] {
+        let (si, receiver, mock) = responder(vec![None, None, Some(response.clone())]);
+        let mut si = si.retries(retries).retry_delay(Duration::from_millis(5));
+        call!(si.write_read(request.clone(), &timeout, None)).unwrap();
+        let attempts = retries as usize + 1;
+        assert_eq!(*mock.written().lock().unwrap(), request.repeat(attempts));
+        let msgs = collect(&receiver, Duration::from_millis(10));
+        assert!(matches!(&msgs[0], SerialMessage::DataSent(data) if data == &request));
+        assert_eq!(msgs.len(), 2, "{:?}", msgs);
+        if answered {
+            assert!(matches!(&msgs[1], SerialMessage::Receive(data) if data == &response));
+        } else {
+            assert!(matches!(
+                &msgs[1],
+                SerialMessage::NoResponse { request: data, .. } if data == &request
+            ));
+        }
+    }
+}
+
+#[test]
+fn slave_offline_online() {
+    let request = modbus::build_read_holding_registers(1, 0, 1);
+    let response = frame(&[0x01, 0x03, 0x02, 0x00, 0x2A]);
+    let (si, receiver, _mock) = responder(vec![None, None, None, None, Some(response.clone())]);
+    let mut si = si.offline_threshold(3).retry_on(RetryTrigger::NONE);
+    let timeout = Duration::from_millis(30);
+    let health = |msgs: &[SerialMessage]| -> Vec<String> {
+        msgs.iter()
+            .filter(|msg| {
+                matches!(msg, SerialMessage::SlaveOffline(_) | SerialMessage::SlaveOnline(_))
+            })
+            .map(|msg| format!("{:?}", msg))
+            .collect()
+    };
+    for (i, expected) in [vec![], vec![], vec!["SlaveOffline(1)"], vec![]].iter().enumerate() {
+        call!(si.write_read(request.clone(), &timeout, None)).unwrap();
+        let msgs = collect(&receiver, Duration::from_millis(10));
+        assert!(msgs.iter().any(|msg| matches!(msg, SerialMessage::NoResponse { .. })));
+        assert_eq!(&health(&msgs), expected, "timeout {}", i + 1);
+    }
+    call!(si.write_read(request.clone(), &timeout, None)).unwrap();
+    let msgs = collect(&receiver, Duration::from_millis(10));
+    let received =
+        |msg: &SerialMessage| matches!(msg, SerialMessage::Receive(data) if data == &response);
+    assert!(msgs.iter().any(received));
+    assert_eq!(health(&msgs), vec!["SlaveOnline(1)"]);
+}
+
+/// Backend always having bytes to read, as a continuously busy line, counting the bytes read.
+struct Streaming(Arc<AtomicUsize>);
+
+impl Read for Streaming {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        buf.fill(0x55);
+        self.0.fetch_add(buf.len(), Ordering::Relaxed);
+        Ok(buf.len())
+    }
+}
+
+impl Write for Streaming {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn drain_busy_line() {
+    for (max_drain, bound) in [(None, 4096), (Some(100), 100), (Some(0), 0)] {
+        let read = Arc::new(AtomicUsize::new(0));
+        let si = SerialInterface::with_backend(Box::new(Streaming(read.clone()))).unwrap();
+        let mut si = match max_drain {
+            Some(max_drain) => si.max_drain(max_drain),
+            None => si,
+        };
+        let start = Instant::now();
+        si.clear_read_buffer().unwrap();
+        assert!(start.elapsed() < Duration::from_secs(1));
+        let read = read.load(Ordering::Relaxed);
+        // the drain stops at the first read reaching the bound
+        assert!(read >= bound && read < bound + 24, "{:?}: {} bytes", max_drain, read);
+    }
+
+    // a master transaction on the busy line still ends at its timeout
+    let read = Arc::new(AtomicUsize::new(0));
+    let (si, _sender, receiver) = wired(Streaming(read));
+    let mut si = si.silence(Duration::from_millis(5));
+    si.timeout = Duration::from_millis(200);
+    si.set_mode(Mode::Master).unwrap();
+    let request = modbus::build_read_holding_registers(1, 0, 1);
+    let start = Instant::now();
+    call!(si.write_read(request, &Duration::from_millis(50), None)).unwrap();
+    assert!(start.elapsed() < Duration::from_secs(1));
+    let msg = recv_until(&receiver, Duration::from_millis(10), |msg| {
+        !matches!(msg, SerialMessage::DataSent(_))
+    });
+    assert!(msg.is_some());
+}
+
+#[test]
+fn accepts_frame_filtering() {
+    let matching = frame(&[0x01, 0x03, 0x00, 0x00, 0x00, 0x01]);
+    let broadcast = frame(&[0x00, 0x06, 0x00, 0x01, 0x00, 0x2A]);
+    let other = frame(&[0x02, 0x03, 0x00, 0x00, 0x00, 0x01]);
+    let mut bad_crc = matching.clone();
+    *bad_crc.last_mut().unwrap() ^= 0xFF;
+    let si = SerialInterface::new().unwrap().modbus_id(1);
+    assert!(si.accepts_frame(&matching));
+    assert!(si.accepts_frame(&broadcast));
+    assert!(!si.accepts_frame(&other));
+    assert!(!si.accepts_frame(&bad_crc));
+    assert!(!si.accepts_frame(&[]));
+    // promiscuous
+    let si = si.filter_by_id(false);
+    for frame in [&matching, &broadcast, &other, &bad_crc] {
+        assert!(si.accepts_frame(frame));
+    }
+    // no id: only broadcasts are accepted
+    let si = SerialInterface::new().unwrap();
+    assert!(si.accepts_frame(&broadcast));
+    assert!(!si.accepts_frame(&matching));
+}
+
+/// Returns a Slave of id 1 on `mock`, with a 5ms silence, its app sender and receiver. Stop is sent
+/// after 500ms so a wait_for_request() missing its frame fails instead of blocking forever.
+fn slave(
+    mock: &MockBackend,
+    filter_by_id: bool,
+) -> (SerialInterface, Sender<SerialMessage>, Receiver<SerialMessage>) {
+    let (si, sender, receiver) = wired(mock.clone());
+    let mut si = si
+        .modbus_id(1)
+        .filter_by_id(filter_by_id)
+        .silence(Duration::from_millis(5));
+    si.switch_mode(Mode::Slave);
+    let stop = sender.clone();
+    std::thread::spawn(move || {
+        std::thread::sleep(Duration::from_millis(500));
+        send(&stop, SerialMessage::SetMode(Mode::Stop));
+    });
+    (si, sender, receiver)
+}
+
+#[test]
+fn wait_for_request_filtering() {
+    let matching = frame(&[0x01, 0x03, 0x00, 0x00, 0x00, 0x01]);
+    let broadcast = frame(&[0x00, 0x06, 0x00, 0x01, 0x00, 0x2A]);
+    let other = frame(&[0x02, 0x03, 0x00, 0x00, 0x00, 0x01]);
+    let mut bad_crc = matching.clone();
+    *bad_crc.last_mut().unwrap() ^= 0xFF;
+    let gap = Duration::from_millis(20);
+    for (filter_by_id, forwarded) in [
+        (true, vec![&matching, &broadcast]),
+        (false, vec![&other, &bad_crc, &matching, &broadcast]),
+    ] {
+        let mock = MockBackend::new();
+        for frame in [&other, &bad_crc, &matching, &broadcast] {
+            feed_frame(&mock, frame, gap);
+        }
+        let (mut si, _sender, receiver) = slave(&mock, filter_by_id);
+        for expected in forwarded {
+            assert!(call!(si.wait_for_request()).unwrap().is_none());
+            let msg = receiver.try_recv().unwrap();
+            assert!(matches!(msg, SerialMessage::Receive(data) if &data == expected));
+            assert!(receiver.try_recv().is_err());
+        }
+        let msg = call!(si.wait_for_request()).unwrap();
+        assert!(matches!(msg, Some(SerialMessage::SetMode(Mode::Stop))));
+        assert!(receiver.try_recv().is_err());
+    }
+}
+
+#[test]
+fn ascii_frame_vectors() {
+    // read holding registers requests, from the Modbus over serial line specification
+    let vectors: [(&[u8], &[u8]); 3] = [
+        (b":010300000001FB\r\n", &[0x01, 0x03, 0x00, 0x00, 0x00, 0x01, 0xFB]),
+        (b":1103006B00037E\r\n", &[0x11, 0x03, 0x00, 0x6B, 0x00, 0x03, 0x7E]),
+        (b":F7031389000A60\r\n", &[0xF7, 0x03, 0x13, 0x89, 0x00, 0x0A, 0x60]),
+    ];
+    for (ascii, bytes) in vectors {
+        assert_eq!(SerialInterface::decode_ascii(ascii).as_deref(), Some(bytes));
+        assert!(SerialInterface::check_ascii(ascii));
+    }
+    assert_eq!(
+        SerialInterface::decode_ascii(b":1103006b00037e\r\n").as_deref(),
+        Some(vectors[1].1)
+    );
+    for malformed in [
+        &b"010300000001FB\r\n"[..],
+        b":010300000001FB",
+        b":010300000001FB\n",
+        b":010300000001F\r\n",
+        b":0103000000G1FB\r\n",
+    ] {
+        assert_eq!(SerialInterface::decode_ascii(malformed), None);
+        assert!(!SerialInterface::check_ascii(malformed));
+    }
+    // well formed, wrong LRC
+    assert!(!SerialInterface::check_ascii(b":010300000001FC\r\n"));
+    assert!(!SerialInterface::check_ascii(b":\r\n"));
+
+    // scanning a buffer with noise around the frames
+    let buffer = b"\x00:01\r\n:010300000001FB\r\n:1103006B00037E\r\n:01";
+    let forward = SerialInterface::try_decode_ascii(buffer, ScanPreference::ForwardFirst);
+    assert_eq!(forward, Some((6, 17, ChecksumAlgo::Lrc)));
+    let reverse = SerialInterface::try_decode_ascii(buffer, ScanPreference::ReverseFirst);
+    assert_eq!(reverse, Some((23, 17, ChecksumAlgo::Lrc)));
+    assert_eq!(
+        SerialInterface::try_decode_ascii(b":010300000001FB\r", ScanPreference::ForwardFirst),
+        None
+    );
+}
+
+#[test]
+fn ascii_read_path() {
+    let request = b":010300000001FB\r\n".to_vec();
+    // 1 register of value 0x002A: 01 03 02 00 2A, LRC 0xD0
+    let response = b":010302002AD0\r\n".to_vec();
+    assert!(SerialInterface::check_ascii(&response));
+
+    // the CRLF ends the frame without waiting for silence
+    let mock = MockBackend::new();
+    feed_frame(&mock, &response, Duration::from_millis(10));
+    let (si, _sender, receiver) = wired(mock.clone());
+    let mut si = si
+        .frame_format(FrameFormat::Ascii)
+        .silence(Duration::from_millis(100));
+    si.timeout = Duration::from_millis(500);
+    si.set_mode(Mode::Master).unwrap();
+    let start = Instant::now();
+    call!(si.write_read(request.clone(), &Duration::from_millis(500), None)).unwrap();
+    assert!(start.elapsed() < Duration::from_millis(100));
+    assert_eq!(*mock.written().lock().unwrap(), request);
+    let msg = recv_until(&receiver, Duration::from_millis(10), |msg| {
+        !matches!(msg, SerialMessage::DataSent(_))
+    });
+    assert!(matches!(msg, Some(SerialMessage::Receive(data)) if data == response));
+
+    // the stream scanner skips the noise before the frame and keeps the next one
+    let mock = MockBackend::new();
+    let burst = [&b"\xFF\x00:01"[..], &response, &request].concat();
+    feed_frame(&mock, &burst, Duration::from_millis(10));
+    let (si, _sender, _receiver) = wired(mock.clone());
+    let mut si = si.frame_format(FrameFormat::Ascii);
+    let timeout = Duration::from_millis(100);
+    let msg = si.read_stream(&timeout).unwrap();
+    assert!(matches!(msg, SerialMessage::Receive(data) if data == response));
+    let msg = si.read_stream(&timeout).unwrap();
+    assert!(matches!(msg, SerialMessage::Receive(data) if data == request));
+}
+
+#[test]
+fn lrc_vectors() {
+    assert_eq!(SerialInterface::lrc(&[0x01, 0x03, 0x00, 0x00, 0x00, 0x01]), 0xFB);
+    assert_eq!(SerialInterface::lrc(&[0x11, 0x03, 0x00, 0x6B, 0x00, 0x03]), 0x7E);
+    assert_eq!(SerialInterface::lrc(&[0xF7, 0x03, 0x13, 0x89, 0x00, 0x0A]), 0x60);
+    assert_eq!(SerialInterface::lrc(&[]), 0x00);
+    // the sum wraps around
+    assert_eq!(SerialInterface::lrc(&[0xFF, 0x01]), 0x00);
+    assert_eq!(SerialInterface::lrc(&[0xFF; 3]), 0x03);
+    // the bytes followed by their LRC sum to 0
+    let data = [0x01, 0x10, 0x00, 0x01, 0x00, 0x02, 0x04, 0x00, 0x0A, 0x01, 0x02];
+    let sum = data.iter().fold(SerialInterface::lrc(&data), |acc, b| acc.wrapping_add(*b));
+    assert_eq!(sum, 0);
+}
+
+#[test]
+fn strip_leading_nulls() {
+    // a response holding 0x00 bytes, preceded by the spurious nulls of a transceiver
+    let response = frame(&[0x01, 0x03, 0x02, 0x00, 0x00]);
+    let noisy = [&[0x00, 0x00][..], &response].concat();
+    let request = modbus::build_read_holding_registers(1, 0, 1);
+    for (strip, expected) in [(true, &response), (false, &noisy)] {
+        let mock = MockBackend::new();
+        feed_frame(&mock, &noisy, Duration::from_millis(10));
+        let (si, receiver) = master(&mock);
+        let mut si = si.strip_leading_nulls(strip).retry_on(RetryTrigger::NONE);
+        call!(si.write_read(request.clone(), &Duration::from_millis(200), None)).unwrap();
+        let msg = recv_until(&receiver, Duration::from_millis(10), |msg| {
+            !matches!(msg, SerialMessage::DataSent(_))
+        });
+        assert!(
+            matches!(&msg, Some(SerialMessage::Receive(data)) if data == expected),
+            "strip {}: {:?}",
+            strip,
+            msg
+        );
+
+        // same in the stream scanner, where the nulls are not even reported as discarded
+        let mock = MockBackend::new();
+        feed_frame(&mock, &noisy, Duration::from_millis(10));
+        let (si, _sender, _receiver) = wired(mock.clone());
+        let mut si = si.strip_leading_nulls(strip).report_discarded(true);
+        let msg = si.read_stream(&Duration::from_millis(100)).unwrap();
+        assert!(matches!(msg, SerialMessage::Receive(data) if data == response));
+        assert_eq!(si.discarded.is_some(), !strip);
+    }
+}
+
+#[test]
+fn max_tx_frame() {
+    let large = vec![0x01; 300];
+    for (max_tx_frame, written) in [(None, false), (Some(512), true)] {
+        let mock = MockBackend::new();
+        let (si, _sender, _receiver) = wired(mock.clone());
+        let si = si.silence(Duration::from_millis(5)).auto_crc(false);
+        let si = match max_tx_frame {
+            Some(max) => si.max_tx_frame(max),
+            None => si,
+        };
+        let (handle, thread) = spawn(si, Mode::Master);
+        send(&handle.sender, SerialMessage::Send(large.clone()));
+        let msg = recv_until(&handle.receiver, Duration::from_secs(1), |msg| {
+            matches!(msg, SerialMessage::Error(_) | SerialMessage::NoResponse { .. })
+        });
+        if written {
+            assert!(matches!(msg, Some(SerialMessage::NoResponse { .. })), "{:?}", msg);
+            assert_eq!(*mock.written().lock().unwrap(), large);
+        } else {
+            assert!(matches!(msg, Some(SerialMessage::Error(SIError::FrameTooLarge))));
+            assert!(mock.written().lock().unwrap().is_empty());
+            // the transaction is dropped, the next request goes through
+            send(&handle.sender, SerialMessage::Send(large[..256].to_vec()));
+            let msg = recv_until(&handle.receiver, Duration::from_secs(1), |msg| {
+                matches!(msg, SerialMessage::Error(_) | SerialMessage::NoResponse { .. })
+            });
+            assert!(matches!(msg, Some(SerialMessage::NoResponse { .. })), "{:?}", msg);
+            assert_eq!(mock.written().lock().unwrap().len(), 256);
+        }
+        shutdown(handle, thread);
+    }
+}
+
+#[test]
+fn min_bytes_before_silence() {
+    let response = frame(&[0x01, 0x03, 0x02, 0x00, 0x2A]);
+    let silence = Duration::from_millis(10);
+    for min_bytes in [0, 3] {
+        let mock = MockBackend::new();
+        // a gap larger than silence between bytes 1 and 2
+        mock.feed_byte(response[0], Duration::from_millis(10));
+        feed_frame(&mock, &response[1..], silence * 3);
+        let (si, _sender, _receiver) = wired(mock.clone());
+        let mut si = si.min_bytes_before_silence(min_bytes);
+        let msg = call!(si.read_until_silence(&silence)).unwrap();
+        if min_bytes == 0 {
+            assert!(matches!(msg, Some(SerialMessage::Receive(data)) if data == response[..1]));
+            let msg = call!(si.read_until_silence(&silence)).unwrap();
+            assert!(matches!(msg, Some(SerialMessage::Receive(data)) if data == response[1..]));
+        } else {
+            assert!(matches!(msg, Some(SerialMessage::Receive(data)) if data == response));
+        }
+    }
+}
+
+#[test]
+fn size_known_frame_gap() {
+    let response = frame(&[0x01, 0x03, 0x02, 0x00, 0x2A]);
+    let silence = Duration::from_millis(10);
+    let timeout = Duration::from_millis(200);
+    let mock = MockBackend::new();
+    let (mut si, _sender, _receiver) = wired(mock.clone());
+    // a mid-frame gap larger than silence
+    let feed_gap = |mock: &MockBackend| {
+        feed_frame(mock, &response[..3], Duration::from_millis(10));
+        feed_frame(mock, &response[3..], silence * 3);
+    };
+
+    feed_gap(&mock);
+    let start = Instant::now();
+    let msg = call!(si.read_until_size_or_silence_or_timeout_or_message(
+        Some(response.len()),
+        Some(&silence),
+        Some(&timeout)
+    ))
+    .unwrap();
+    assert!(matches!(msg, Some(SerialMessage::Receive(data)) if data == response));
+    // completed at size, neither at silence nor at timeout
+    assert!(start.elapsed() < timeout);
+
+    // a frame shorter than expected ends at timeout
+    feed_frame(&mock, &response[..3], Duration::from_millis(10));
+    let start = Instant::now();
+    let msg = call!(si.read_until_size_or_silence_or_timeout_or_message(
+        Some(response.len()),
+        Some(&silence),
+        Some(&timeout)
+    ))
+    .unwrap();
+    assert!(matches!(msg, Some(SerialMessage::Receive(data)) if data == response[..3]));
+    assert!(start.elapsed() >= timeout);
+
+    // without timeout, silence stays the fallback end of frame
+    feed_gap(&mock);
+    let msg = call!(si.read_until_size_or_silence(response.len(), &silence)).unwrap();
+    assert!(matches!(msg, Some(SerialMessage::Receive(data)) if data == response[..3]));
+}
+
+#[test]
+fn stale_send_across_mode_change() {
+    let stale = frame(&[0x01, 0x06, 0x00, 0x01, 0x00, 0x2A]);
+    for drain in [true, false] {
+        let mock = MockBackend::new();
+        let (si, sender, receiver) = wired(mock.clone());
+        let mut si = si
+            .silence(Duration::from_millis(5))
+            .drain_on_mode_change(drain);
+        // queued before the loop runs, so the Send is pending when Sniff is entered
+        for msg in [
+            SerialMessage::SetMode(Mode::Sniff),
+            SerialMessage::Send(stale.clone()),
+            SerialMessage::Ping,
+            SerialMessage::SetMode(Mode::Stop),
+            SerialMessage::Shutdown,
+        ] {
+            send(&sender, msg);
+        }
+        block_on(si.start());
+        let written = mock.written().lock().unwrap().clone();
+        if drain {
+            assert!(written.is_empty());
+        } else {
+            assert_eq!(written, stale);
+        }
+        // control messages are preserved
+        let msgs = collect(&receiver, Duration::from_millis(10));
+        assert!(msgs.iter().any(|msg| matches!(msg, SerialMessage::Pong)), "{:?}", msgs);
+    }
+}
+
+#[test]
+fn send_after_mode_change_kept() {
+    let request = frame(&[0x01, 0x06, 0x00, 0x01, 0x00, 0x2A]);
+    let mock = MockBackend::new();
+    let (si, sender, receiver) = wired(mock.clone());
+    // sent as soon as Sniff is entered, i.e. after the SetMode() is dequeued
+    let app_sender = sender.clone();
+    let data = request.clone();
+    let mut si = si
+        .silence(Duration::from_millis(5))
+        .drain_on_mode_change(true)
+        .on_enter_mode(Arc::new(move |mode: &Mode| {
+            if *mode == Mode::Sniff {
+                for msg in [
+                    SerialMessage::Send(data.clone()),
+                    SerialMessage::Ping,
+                    SerialMessage::SetMode(Mode::Stop),
+                    SerialMessage::Shutdown,
+                ] {
+                    send(&app_sender, msg);
+                }
+            }
+        }));
+    send(&sender, SerialMessage::SetMode(Mode::Sniff));
+    block_on(si.start());
+    assert_eq!(*mock.written().lock().unwrap(), request);
+    let msgs = collect(&receiver, Duration::from_millis(10));
+    assert!(msgs.iter().any(|msg| matches!(msg, SerialMessage::Pong)), "{:?}", msgs);
+}
+
+#[test]
+fn scan_preference_stream() {
+    let first = frame(&[0x01, 0x03, 0x02, 0x00, 0x2A]);
+    let second = frame(&[0x02, 0x03, 0x02, 0x00, 0x2B]);
+    let burst = [&[0xFF, 0x13][..], &first, &second].concat();
+    for (scan, expected) in [
+        (ScanPreference::ForwardFirst, &first),
+        (ScanPreference::ReverseFirst, &second),
+    ] {
+        let mock = MockBackend::new();
+        feed_frame(&mock, &burst, Duration::from_millis(10));
+        let (si, _sender, _receiver) = wired(mock.clone());
+        let mut si = si.scan_preference(scan);
+        let msg = si.read_stream(&Duration::from_millis(100)).unwrap();
+        assert!(matches!(&msg, SerialMessage::Receive(data) if data == expected), "{:?}", msg);
+    }
+}
+
+#[test]
+fn scan_two_valid_frames() {
+    let first = frame(&[0x01, 0x03, 0x02, 0x00, 0x2A]);
+    let second = frame(&[0x02, 0x03, 0x02, 0x00, 0x2B]);
+    let decode = |buffer: &[u8], scan| {
+        SerialInterface::try_decode_buffer(
+            buffer,
+            CrcByteOrder::LittleEndian,
+            scan,
+            &[ChecksumAlgo::CrcModbus],
+            4,
+        )
+    };
+    let buffer = [&first[..], &second].concat();
+    let len = first.len();
+    let forward = Some((0, len, ChecksumAlgo::CrcModbus));
+    let reverse = Some((len, len, ChecksumAlgo::CrcModbus));
+    assert_eq!(decode(&buffer, ScanPreference::ForwardFirst), forward);
+    assert_eq!(decode(&buffer, ScanPreference::ReverseFirst), reverse);
+    // garbage around the frames does not change the selection
+    let noisy = [&[0x13, 0x37][..], &first, &[0x00], &second, &[0xFF]].concat();
+    let forward = Some((2, len, ChecksumAlgo::CrcModbus));
+    let reverse = Some((3 + len, len, ChecksumAlgo::CrcModbus));
+    assert_eq!(decode(&noisy, ScanPreference::ForwardFirst), forward);
+    assert_eq!(decode(&noisy, ScanPreference::ReverseFirst), reverse);
+    // the smallest frame wins over the preference
+    let short = frame(&[0x03, 0x06]);
+    let buffer = [&first[..], &short].concat();
+    let smallest = Some((len, short.len(), ChecksumAlgo::CrcModbus));
+    assert_eq!(decode(&buffer, ScanPreference::ForwardFirst), smallest);
+    assert_eq!(decode(&buffer, ScanPreference::ReverseFirst), smallest);
+}
+
+#[test]
+fn sniff_injection_echo() {
+    let (mut bus, b) = backend::loopback_pair();
+    let (si, _sender, _receiver) = wired(b);
+    let si = si.silence(Duration::from_millis(5));
+    let (handle, thread) = spawn(si, Mode::Sniff);
+    send(&handle.sender, SerialMessage::Ping);
+    let pong = recv_until(&handle.receiver, Duration::from_secs(1), |msg| {
+        matches!(msg, SerialMessage::Pong)
+    });
+    assert!(pong.is_some());
+
+    let request = modbus::build_read_holding_registers(1, 0, 1);
+    let response = frame(&[0x01, 0x03, 0x02, 0x00, 0x2A]);
+    send(&handle.sender, SerialMessage::Send(request.clone()));
+    let sent = recv_until(&handle.receiver, Duration::from_secs(1), |msg| {
+        matches!(msg, SerialMessage::DataSent(_))
+    });
+    assert!(sent.is_some());
+    let mut injected = vec![0u8; request.len()];
+    bus.read_exact(&mut injected).unwrap();
+    assert_eq!(injected, request);
+    // the bus echoes the injection, the slave answers right behind it
+    bus.write_all(&[injected, response.clone()].concat()).unwrap();
+    let is_frame = |msg: &SerialMessage| {
+        matches!(msg, SerialMessage::Receive(_) | SerialMessage::Injected(_))
+    };
+    let msg = recv_until(&handle.receiver, Duration::from_secs(1), is_frame);
+    assert!(matches!(msg, Some(SerialMessage::Injected(data)) if data == request));
+    let msg = recv_until(&handle.receiver, Duration::from_secs(1), is_frame);
+    assert!(matches!(msg, Some(SerialMessage::Receive(data)) if data == response));
+
+    // the same frame from another master is bus traffic
+    bus.write_all(&request).unwrap();
+    let msg = recv_until(&handle.receiver, Duration::from_secs(1), is_frame);
+    assert!(matches!(msg, Some(SerialMessage::Receive(data)) if data == request));
+    shutdown(handle, thread);
+}
+
+#[test]
+fn unhandled_data_message_in_stop() {
+    for policy in [UnhandledPolicy::Ignore, UnhandledPolicy::Log, UnhandledPolicy::Error] {
+        let mock = MockBackend::new();
+        let (si, sender, receiver) = wired(mock.clone());
+        let mut si = si.on_unhandled(policy);
+        for msg in [
+            SerialMessage::Send(vec![0x01, 0x02]),
+            SerialMessage::Ping,
+            SerialMessage::Shutdown,
+        ] {
+            send(&sender, msg);
+        }
+        block_on(si.start());
+        assert!(mock.written().lock().unwrap().is_empty());
+        let msgs = collect(&receiver, Duration::from_millis(10));
+        let wrong_mode = msgs
+            .iter()
+            .any(|msg| matches!(msg, SerialMessage::Error(SIError::WrongMode)));
+        assert_eq!(wrong_mode, policy == UnhandledPolicy::Error, "{:?}: {:?}", policy, msgs);
+        // the loop keeps handling the next messages
+        assert!(matches!(msgs.last(), Some(SerialMessage::Pong)), "{:?}: {:?}", policy, msgs);
+    }
+}
+
+#[test]
+fn report_discarded_garbage() {
+    let response = frame(&[0x01, 0x03, 0x02, 0x00, 0x2A]);
+    let garbage = vec![0xFF, 0x13, 0x37];
+    for report in [true, false] {
+        let mock = MockBackend::new();
+        let (si, _sender, _receiver) = wired(Responder {
+            replies: vec![Some([&garbage[..], &response].concat())].into(),
+            inner: mock.clone(),
+        });
+        let si = si.report_discarded(report);
+        let (handle, thread) = spawn(si, Mode::MasterStream);
+        send(&handle.sender, SerialMessage::Send(modbus::build_read_holding_registers(1, 0, 1)));
+        let msgs: Vec<_> = collect(&handle.receiver, Duration::from_millis(150))
+            .into_iter()
+            .filter(|msg| matches!(msg, SerialMessage::Discarded(_) | SerialMessage::Receive(_)))
+            .collect();
+        if report {
+            assert_eq!(msgs.len(), 2, "{:?}", msgs);
+            assert!(matches!(&msgs[0], SerialMessage::Discarded(data) if data == &garbage));
+        } else {
+            assert_eq!(msgs.len(), 1, "{:?}", msgs);
+        }
+        assert!(matches!(msgs.last(), Some(SerialMessage::Receive(data)) if data == &response));
+        shutdown(handle, thread);
+    }
+}
+
+#[test]
+fn post_frame_guard() {
+    let first = frame(&[0x01, 0x03, 0x02, 0x00, 0x2A]);
+    let second = frame(&[0x01, 0x03, 0x02, 0x00, 0x2B]);
+    let ringing = [0x55, 0xAA];
+    for (guard, expected) in [
+        (Duration::from_millis(50), vec![&first[..], &second]),
+        (Duration::ZERO, vec![&first[..], &ringing, &second]),
+    ] {
+        let mock = MockBackend::new();
+        feed_frame(&mock, &first, Duration::from_millis(10));
+        // a few bytes of ringing shortly after the frame, still separated by more than silence
+        feed_frame(&mock, &ringing, Duration::from_millis(15));
+        feed_frame(&mock, &second, Duration::from_millis(100));
+        let (si, _sender, receiver) = wired(mock.clone());
+        let mut si = si
+            .silence(Duration::from_millis(5))
+            .post_frame_guard(guard);
+        si.switch_mode(Mode::Sniff);
+        for expected in expected {
+            assert!(call!(si.listen()).unwrap().is_none());
+            let msg = receiver.try_recv().unwrap();
+            assert!(
+                matches!(&msg, SerialMessage::Receive(data) if data == expected),
+                "guard {:?}: {:?}",
+                guard,
+                msg
+            );
+        }
+    }
+}
+
+#[test]
+fn stream_buffer_bounded() {
+    // a junk flood: the scanned buffer never exceeds max_buffer_size
+    let read = Arc::new(AtomicUsize::new(0));
+    let (si, _sender, _receiver) = wired(Streaming(read.clone()));
+    let mut si = si.max_buffer_size(64).report_crc_errors(true);
+    let msg = si.read_stream(&Duration::from_millis(50)).unwrap();
+    assert!(matches!(msg, SerialMessage::NoResponse { .. }));
+    assert!(read.load(Ordering::Relaxed) > 1000);
+    assert_eq!(si.crc_error.as_ref().map(Vec::len), Some(64));
+
+    // a frame at the end of the flood is still decoded, the oldest junk being dropped
+    let response = frame(&[0x01, 0x03, 0x02, 0x00, 0x2A]);
+    let mock = MockBackend::new();
+    feed_frame(&mock, &[&[0x55; 1000][..], &response].concat(), Duration::from_millis(10));
+    let (si, _sender, _receiver) = wired(mock.clone());
+    let mut si = si.max_buffer_size(64).report_discarded(true);
+    let msg = si.read_stream(&Duration::from_millis(200)).unwrap();
+    assert!(matches!(msg, SerialMessage::Receive(data) if data == response));
+    assert_eq!(si.discarded.as_ref().map(Vec::len), Some(64 - response.len()));
+}
+
+#[test]
+fn set_timeout_reaches_port() {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let mut si = SerialInterface::connect_tcp(addr).unwrap();
+    let (mut gateway, _) = listener.accept().unwrap();
+    // unblocks a read left without timeout, failing the test instead of hanging it
+    std::thread::spawn(move || {
+        std::thread::sleep(Duration::from_secs(2));
+        let _ = gateway.write_all(&[0x55]);
+    });
+    // slow enough for the read timeout to be bounded by the configured timeout, see read_timeout()
+    si.baud_rate = BaudRate::Baud110;
+    // time of a read on the idle line, i.e. of the read timeout of the port
+    let timed_read = |si: &mut SerialInterface| {
+        let start = Instant::now();
+        let read = si.port.as_mut().unwrap().read(&mut [0u8; 1]);
+        assert!(SerialInterface::is_read_timeout(&read.unwrap_err()));
+        start.elapsed()
+    };
+    for timeout in [Duration::from_millis(60), Duration::from_millis(5)] {
+        let msg = call!(si.handle_message(SerialMessage::SetTimeout(timeout))).unwrap();
+        assert!(msg.is_none());
+        assert_eq!(si.timeout, timeout);
+        let elapsed = timed_read(&mut si);
+        assert!(elapsed >= timeout, "{:?}: {:?}", timeout, elapsed);
+        assert!(elapsed < timeout + Duration::from_millis(40), "{:?}: {:?}", timeout, elapsed);
+    }
+}
+
+#[test]
+fn auto_reconnect_after_port_error() {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let si = SerialInterface::connect_tcp(listener.local_addr().unwrap())
+        .unwrap()
+        .silence(Duration::from_millis(5))
+        .auto_reconnect(true);
+    let (mut gateway, _) = listener.accept().unwrap();
+    let (handle, thread) = spawn(si, Mode::Sniff);
+    let request = modbus::build_read_holding_registers(1, 0, 1);
+    let received = |handle: &SerialHandle| {
+        recv_until(&handle.receiver, Duration::from_secs(1), |msg| {
+            matches!(msg, SerialMessage::Receive(_))
+        })
+    };
+    let connected = |handle: &SerialHandle| {
+        recv_until(&handle.receiver, Duration::from_secs(2), |msg| {
+            matches!(msg, SerialMessage::Connected(_))
+        })
+    };
+    // the loop is reading once a ping is answered, so the frame is not drained when it resumes
+    let synced = |handle: &SerialHandle| {
+        send(&handle.sender, SerialMessage::Ping);
+        recv_until(&handle.receiver, Duration::from_secs(1), |msg| {
+            matches!(msg, SerialMessage::Pong)
+        })
+        .is_some()
+    };
+    assert!(synced(&handle));
+    gateway.write_all(&request).unwrap();
+    assert!(matches!(received(&handle), Some(SerialMessage::Receive(data)) if data == request));
+
+    // the gateway drops the connection: the read error triggers a reconnection
+    drop(gateway);
+    assert!(matches!(connected(&handle), Some(SerialMessage::Connected(false))));
+    let (mut gateway, _) = listener.accept().unwrap();
+    assert!(matches!(connected(&handle), Some(SerialMessage::Connected(true))));
+    assert!(synced(&handle));
+    gateway.write_all(&request).unwrap();
+    assert!(matches!(received(&handle), Some(SerialMessage::Receive(data)) if data == request));
+
+    // and on request
+    send(&handle.sender, SerialMessage::Reconnect);
+    let (mut gateway, _) = listener.accept().unwrap();
+    assert!(matches!(connected(&handle), Some(SerialMessage::Connected(true))));
+    assert!(synced(&handle));
+    gateway.write_all(&request).unwrap();
+    assert!(matches!(received(&handle), Some(SerialMessage::Receive(data)) if data == request));
+    shutdown(handle, thread);
+}
+
+/// Backend whose reads always fail with `kind`.
+struct ReadError(std::io::ErrorKind);
+
+impl Read for ReadError {
+    fn read(&mut self, _buf: &mut [u8]) -> std::io::Result<usize> {
+        Err(self.0.into())
+    }
+}
+
+impl Write for ReadError {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn read_timeout_vs_port_error() {
+    // a timeout only means no data
+    for kind in [std::io::ErrorKind::TimedOut, std::io::ErrorKind::WouldBlock] {
+        let mut si = SerialInterface::with_backend(Box::new(ReadError(kind))).unwrap();
+        assert!(matches!(call!(si.read_byte()), Ok(None)), "{:?}", kind);
+        assert!(si.clear_read_buffer().is_ok(), "{:?}", kind);
+    }
+
+    // any other error propagates
+    let mut si =
+        SerialInterface::with_backend(Box::new(ReadError(std::io::ErrorKind::BrokenPipe))).unwrap();
+    assert!(matches!(call!(si.read_byte()), Err(SIError::CannotReadPort(Some(_)))));
+    assert!(matches!(si.clear_read_buffer(), Err(SIError::CannotReadPort(Some(_)))));
+}
+
+#[test]
+fn broadcast_returns_within_silence() {
+    let mock = MockBackend::new();
+    let (si, _sender, _receiver) = wired(mock.clone());
+    let si = si.silence(Duration::from_millis(20));
+    let (handle, thread) = spawn(si, Mode::Master);
+    let timeout = Duration::from_secs(2);
+    send(&handle.sender, SerialMessage::SetTimeout(timeout));
+    let broadcast = modbus::build_read_holding_registers(0, 0, 1);
+    let start = Instant::now();
+    send(&handle.sender, SerialMessage::SendNoResponse(broadcast.clone()));
+    let sent = recv_until(&handle.receiver, timeout, |msg| {
+        matches!(msg, SerialMessage::DataSent(_))
+    });
+    assert!(matches!(sent, Some(SerialMessage::DataSent(data)) if data == broadcast));
+
+    // the loop is free again after the silence, without waiting for a response
+    send(&handle.sender, SerialMessage::Ping);
+    let pong = recv_until(&handle.receiver, timeout, |msg| {
+        matches!(msg, SerialMessage::Pong | SerialMessage::NoResponse { .. })
+    });
+    assert!(matches!(pong, Some(SerialMessage::Pong)));
+    assert!(start.elapsed() < Duration::from_millis(300), "{:?}", start.elapsed());
+    let msgs = collect(&handle.receiver, Duration::from_millis(100));
+    assert!(!msgs.iter().any(|msg| matches!(msg, SerialMessage::NoResponse { .. })));
+    assert_eq!(*mock.written().lock().unwrap(), broadcast);
+    shutdown(handle, thread);
+}
+
+#[test]
+fn validate_missing_fields() {
+    let valid = |mode: Mode| {
+        let (si, sender, receiver) = wired(MockBackend::new());
+        let mut si = si.silence(Duration::from_millis(5)).modbus_id(1);
+        si.timeout = Duration::from_millis(100);
+        si.mode = mode;
+        (si, sender, receiver)
+    };
+    for mode in [Mode::Stop, Mode::Master, Mode::MasterStream, Mode::Slave, Mode::Sniff] {
+        assert!(valid(mode.clone()).0.validate().is_ok(), "{:?}", mode);
+    }
+
+    let (si, _, _) = valid(Mode::Stop);
+    let si = SerialInterface { sender: None, ..si };
+    assert!(matches!(si.validate(), Err(SIError::SenderMissing)));
+
+    let (si, _, _) = valid(Mode::Stop);
+    let si = SerialInterface { receiver: None, ..si };
+    assert!(matches!(si.validate(), Err(SIError::ReceiverMissing)));
+
+    let (si, _, _) = valid(Mode::Slave);
+    let si = SerialInterface { modbus_id: None, ..si };
+    assert!(matches!(si.validate(), Err(SIError::SlaveModeNeedModbusID)));
+
+    for mode in [Mode::Master, Mode::Slave, Mode::Sniff] {
+        let (si, _, _) = valid(mode.clone());
+        let si = SerialInterface { silence: None, ..si };
+        assert!(matches!(si.validate(), Err(SIError::SilenceMissing)), "{:?}", mode);
+    }
+    // the sniff gap is enough to sniff
+    let (si, _, _) = valid(Mode::Sniff);
+    let si = SerialInterface { silence: None, ..si }.sniff_gap(Duration::from_millis(5));
+    assert!(si.validate().is_ok());
+
+    let (mut si, _, _) = valid(Mode::Master);
+    si.timeout = Duration::from_millis(5);
+    assert!(matches!(si.validate(), Err(SIError::SilenceExceedsTimeout)));
+
+    let (si, _, _) = valid(Mode::Master);
+    let si = si.char_size(CharSize::Bits7);
+    assert!(matches!(si.validate(), Err(SIError::IncompatibleCharSize)));
+
+    let (si, _, _) = valid(Mode::Stop);
+    let si = si.min_frame_size(3);
+    assert!(matches!(si.validate(), Err(SIError::MinFrameSizeTooSmall)));
+
+    let (si, _, _) = valid(Mode::Master);
+    let si = SerialInterface { port: None, ..si };
+    assert!(matches!(si.validate(), Err(SIError::PathMissing)));
+    let si = si.path("/dev/ttyUSB0".into());
+    assert!(matches!(si.validate(), Err(SIError::PortNotOpened)));
+
+    // start() returns at once, reporting the error
+    let (si, _, receiver) = valid(Mode::Slave);
+    let mut si = SerialInterface { modbus_id: None, ..si };
+    block_on(si.start());
+    let msg = recv(&receiver, Duration::from_millis(100));
+    assert!(matches!(msg, Some(SerialMessage::Error(SIError::SlaveModeNeedModbusID))));
+}
+
+#[test]
+fn baud_rate_conversions() {
+    for (n, baud_rate) in [
+        (110, BaudRate::Baud110),
+        (300, BaudRate::Baud300),
+        (600, BaudRate::Baud600),
+        (1200, BaudRate::Baud1200),
+        (2400, BaudRate::Baud2400),
+        (4800, BaudRate::Baud4800),
+        (9600, BaudRate::Baud9600),
+        (19200, BaudRate::Baud19200),
+        (38400, BaudRate::Baud38400),
+        (57600, BaudRate::Baud57600),
+        (115200, BaudRate::Baud115200),
+    ] {
+        assert_eq!(SerialInterface::baud_from_u32(n).unwrap(), baud_rate);
+        assert_eq!(SerialInterface::baud_to_u32(baud_rate), n);
+    }
+    // non-standard rates go through BaudOther
+    for n in [250000, 31250, 14400] {
+        assert_eq!(SerialInterface::baud_from_u32(n).unwrap(), BaudRate::BaudOther(n as usize));
+        assert_eq!(SerialInterface::baud_to_u32(BaudRate::BaudOther(n as usize)), n);
+    }
+
+    let (mut si, _sender, _receiver) = wired(MockBackend::new());
+    for (n, baud_rate) in [(19200, BaudRate::Baud19200), (250000, BaudRate::BaudOther(250000))] {
+        let msg = call!(si.handle_message(SerialMessage::SetBaudsRaw(n))).unwrap();
+        assert!(msg.is_none());
+        assert_eq!(si.baud_rate, baud_rate);
+    }
+}
+
+#[test]
+fn unsupported_baud_rate() {
+    assert!(matches!(SerialInterface::baud_from_u32(0), Err(SIError::UnsupportedBaudRate(0))));
+
+    // SetBaudsRaw(0) is reported, the baud rate is kept
+    let (si, _sender, receiver) = wired(MockBackend::new());
+    let mut si = si.bauds(BaudRate::Baud9600);
+    let msg = call!(si.handle_message(SerialMessage::SetBaudsRaw(0))).unwrap();
+    assert!(msg.is_none());
+    assert_eq!(si.baud_rate, BaudRate::Baud9600);
+    let msg = recv(&receiver, Duration::from_millis(100));
+    assert!(matches!(msg, Some(SerialMessage::Error(SIError::UnsupportedBaudRate(0)))));
+}
+
+#[cfg(feature = "async-channel")]
+#[test]
+fn async_idle_backoff_does_not_block() {
+    for mode in [Mode::Master, Mode::Sniff] {
+        let (si, sender, receiver) = wired(MockBackend::new());
+        let mut si = si
+            .silence(Duration::from_millis(5))
+            .max_idle_sleep(Duration::from_millis(100));
+        si.timeout = Duration::from_millis(200);
+        si.set_mode(mode.clone()).unwrap();
+        let app = async move {
+            // long enough for the backoff to reach max_idle_sleep
+            sleep(Duration::from_millis(300)).await;
+            // the other tasks of the runtime keep running while the interface is idle
+            let start = Instant::now();
+            for _ in 0..10 {
+                sleep(Duration::from_millis(1)).await;
+            }
+            let ticks = start.elapsed();
+            let start = Instant::now();
+            sender.send(SerialMessage::Ping).await.unwrap();
+            while !matches!(receiver.recv().await, Ok(SerialMessage::Pong)) {}
+            let pong = start.elapsed();
+            sender.send(SerialMessage::Shutdown).await.unwrap();
+            (ticks, pong)
+        };
+        // on its own thread, as a loop blocking the runtime would never let `app` run
+        let (done, result) = std::sync::mpsc::channel();
+        std::thread::spawn(move || {
+            let _ = done.send(block_on(async { tokio::join!(app, si.start()) }).0);
+        });
+        let (ticks, pong) = result.recv_timeout(Duration::from_secs(5)).expect("runtime blocked");
+        assert!(ticks < Duration::from_millis(50), "{:?}: {:?}", mode, ticks);
+        assert!(pong < Duration::from_millis(150), "{:?}: {:?}", mode, pong);
+    }
+}
+
+#[test]
+fn query_registers_in_register_order() {
+    // 123.456 (0x42F6E979) from a word swapped device
+    let response = frame(&[0x01, 0x03, 0x04, 0xE9, 0x79, 0x42, 0xF6]);
+    let (si, _receiver, mock) = responder(vec![Some(response), None]);
+    let mut si = si.register_order(modbus::WordOrder::LowFirst);
+    let timeout = Duration::from_millis(100);
+    let decoded = call!(si.query_registers(1, 0, 2, timeout)).unwrap();
+    let modbus::ModbusResponse::ReadRegisters(registers) = decoded else {
+        panic!("{:?}", decoded);
+    };
+    assert_eq!(registers, [0x42F6, 0xE979]);
+    assert_eq!(modbus::registers_to_f32(&registers, modbus::WordOrder::HighFirst), [123.456]);
+    assert_eq!(*mock.written().lock().unwrap(), modbus::build_read_holding_registers(1, 0, 2));
+
+    let result = call!(si.query_registers(1, 0, 2, timeout));
+    assert!(matches!(result, Err(SIError::NoResponse)));
+}
+
+#[test]
+fn sniff_idle_line_emits_nothing() {
+    let gap = Duration::from_millis(5);
+    for timestamped in [false, true] {
+        let (si, _sender, _receiver) = wired(MockBackend::new());
+        let si = si.sniff_gap(gap).timestamped(timestamped);
+        let (handle, thread) = spawn(si, Mode::Sniff);
+        // many sniff gaps without any byte on the line
+        let msgs = collect(&handle.receiver, gap * 40);
+        assert!(
+            !msgs.iter().any(|msg| matches!(
+                msg,
+                SerialMessage::Receive(_) | SerialMessage::ReceiveTimed { .. }
+            )),
+            "{:?}",
+            msgs
+        );
+        // still sniffing
+        send(&handle.sender, SerialMessage::Ping);
+        let pong = recv_until(&handle.receiver, Duration::from_secs(1), |msg| {
+            matches!(msg, SerialMessage::Pong)
+        });
+        assert!(pong.is_some());
+        shutdown(handle, thread);
+    }
+}
+
+#[test]
+fn write_confirmed_responses() {
+    let request = modbus::build_write_single_register(1, 0x0010, 42);
+    let multiple = modbus::build_write_multiple_registers(1, 0x0010, &[1, 2, 3]);
+    let exception = frame(&[0x01, 0x86, 0x02]);
+    let mismatch = modbus::build_write_single_register(1, 0x0010, 43);
+    let multiple_ack = frame(&[0x01, 0x10, 0x00, 0x10, 0x00, 0x03]);
+    let (mut si, _receiver, _mock) = responder(vec![
+        Some(request.clone()),
+        Some(multiple_ack),
+        Some(exception),
+        Some(mismatch),
+        None,
+    ]);
+    let timeout = Duration::from_millis(100);
+    // the echo of a single write, the address and count of a multiple one
+    assert!(call!(si.write_confirmed(request.clone(), timeout)).unwrap());
+    assert!(call!(si.write_confirmed(multiple, timeout)).unwrap());
+    // exception response
+    assert!(!call!(si.write_confirmed(request.clone(), timeout)).unwrap());
+    // echo of another value
+    assert!(!call!(si.write_confirmed(request.clone(), timeout)).unwrap());
+    let result = call!(si.write_confirmed(request, timeout));
+    assert!(matches!(result, Err(SIError::NoResponse)));
+}
+
+#[test]
+fn poll_many_mixed_responses() {
+    let requests: Vec<(u64, Vec<u8>)> = (1..=4)
+        .map(|id| (id as u64 * 10, modbus::build_read_holding_registers(id, 0, 1)))
+        .collect();
+    let response = |id: u8| frame(&[id, 0x03, 0x02, 0x00, id]);
+    // slaves 2 and 4 do not answer
+    let (mut si, _receiver, mock) =
+        responder(vec![Some(response(1)), None, Some(response(3)), None]);
+    let results = call!(si.poll_many(requests.clone(), Duration::from_millis(50)));
+    assert_eq!(results.len(), 4);
+    assert!(matches!(&results[&10], Ok(data) if *data == response(1)));
+    assert!(matches!(results[&20], Err(SIError::NoResponse)));
+    assert!(matches!(&results[&30], Ok(data) if *data == response(3)));
+    assert!(matches!(results[&40], Err(SIError::NoResponse)));
+    // the requests are sent in order, each response matching its own request
+    let sent: Vec<u8> = requests.into_iter().flat_map(|(_, request)| request).collect();
+    assert_eq!(*mock.written().lock().unwrap(), sent);
+}
+
+#[test]
+fn baud_mismatch_reported_once() {
+    // frames of framing-error bytes, typical of a wrong baud rate, then of plain data
+    for (byte, reported) in [(0xFF, 1), (0x55, 0)] {
+        let mock = MockBackend::new();
+        let (si, _sender, _receiver) = wired(mock.clone());
+        let si = si.silence(Duration::from_millis(5)).detect_baud_mismatch(true);
+        let (handle, thread) = spawn(si, Mode::Sniff);
+        send(&handle.sender, SerialMessage::Ping);
+        let pong = recv_until(&handle.receiver, Duration::from_secs(1), |msg| {
+            matches!(msg, SerialMessage::Pong)
+        });
+        assert!(pong.is_some());
+        // more than BAUD_CHECK_BYTES bytes in all
+        for _ in 0..6 {
+            feed_frame(&mock, &[byte; 20], Duration::from_millis(20));
+        }
+        let msgs = collect(&handle.receiver, Duration::from_millis(400));
+        let frames = msgs.iter().filter(|msg| matches!(msg, SerialMessage::Receive(_))).count();
+        assert_eq!(frames, 6, "{:?}", msgs);
+        let mismatches = msgs
+            .iter()
+            .filter(|msg| matches!(msg, SerialMessage::PossibleBaudMismatch))
+            .count();
+        assert_eq!(mismatches, reported, "{:#04X}: {:?}", byte, msgs);
+        shutdown(handle, thread);
+    }
+}
+
+#[test]
+fn char_size_per_frame_format() {
+    for (format, char_size, compatible) in [
+        (FrameFormat::Rtu, CharSize::Bits5, false),
+        (FrameFormat::Rtu, CharSize::Bits6, false),
+        (FrameFormat::Rtu, CharSize::Bits7, false),
+        (FrameFormat::Rtu, CharSize::Bits8, true),
+        (FrameFormat::Ascii, CharSize::Bits5, false),
+        (FrameFormat::Ascii, CharSize::Bits6, false),
+        (FrameFormat::Ascii, CharSize::Bits7, true),
+        (FrameFormat::Ascii, CharSize::Bits8, true),
+    ] {
+        let si = SerialInterface::new()
+            .unwrap()
+            .frame_format(format)
+            .char_size(char_size);
+        for mode in [Mode::Master, Mode::MasterStream, Mode::Slave] {
+            let result = si.check_char_size(&mode);
+            let case = format!("{:?} {:?} {:?}", format, char_size, mode);
+            if compatible {
+                assert!(result.is_ok(), "{}", case);
+            } else {
+                assert!(matches!(result, Err(SIError::IncompatibleCharSize)), "{}", case);
+            }
+        }
+        // not checked out of the Modbus modes
+        for mode in [Mode::Stop, Mode::Sniff, Mode::Terminal] {
+            assert!(si.check_char_size(&mode).is_ok(), "{:?} {:?} {:?}", format, char_size, mode);
+        }
+    }
+}
+
+#[test]
+fn silence_jitter_margin_tolerates_late_bytes() {
+    let request = modbus::build_read_holding_registers(1, 0, 1);
+    let silence = Duration::from_millis(10);
+    // an inter-byte gap over silence, but under silence + margin
+    let gap = Duration::from_millis(20);
+    for (margin, whole) in [(Duration::from_millis(15), true), (Duration::ZERO, false)] {
+        let mock = MockBackend::new();
+        feed_frame(&mock, &request[..4], Duration::from_millis(5));
+        mock.feed_byte(request[4], gap);
+        mock.feed(&request[5..], Duration::ZERO);
+        let (si, _sender, _receiver) = wired(mock);
+        let mut si = si.silence_jitter_margin(margin);
+        let msg = call!(si.read_until_silence(&silence)).unwrap();
+        let expected = if whole { &request[..] } else { &request[..4] };
+        assert!(
+            matches!(&msg, Some(SerialMessage::Receive(data)) if data == expected),
+            "{:?}: {:?}",
+            margin,
+            msg
+        );
+    }
+}
+
+#[test]
+fn swap_channels_in_stop_mode() {
+    let (mut si, _old_sender, old_receiver) = wired(MockBackend::new());
+    let (app_sender, receiver) = channel();
+    let (sender, app_receiver) = channel();
+    si.set_sender(sender).unwrap();
+    si.set_receiver(receiver).unwrap();
+    send(&app_sender, SerialMessage::Ping);
+    send(&app_sender, SerialMessage::Shutdown);
+    block_on(si.start());
+    // the reply goes to the new sender, the old receiver is no longer polled
+    assert!(matches!(recv(&app_receiver, Duration::from_millis(100)), Some(SerialMessage::Pong)));
+    assert!(recv(&old_receiver, Duration::from_millis(10)).is_none());
+
+    // not outside Stop mode
+    let (_app_sender, receiver) = channel();
+    let (sender, _app_receiver) = channel();
+    si.mode = Mode::Master;
+    assert!(matches!(si.set_sender(sender), Err(SIError::StopToChangeSettings)));
+    assert!(matches!(si.set_receiver(receiver), Err(SIError::StopToChangeSettings)));
+}
+
+#[test]
+fn max_poll_rate_per_baud_rate() {
+    // 8N2 (default), 8 bytes request and 7 bytes response: 88 and 77 bits, 10ms turnaround, 5ms
+    // silence
+    for (baud_rate, cycle_us) in [
+        // 9166 + 10000 + 8020 + 5000
+        (BaudRate::Baud9600, 32_186.0),
+        // 763 + 10000 + 668 + 5000
+        (BaudRate::Baud115200, 16_431.0),
+    ] {
+        let si = SerialInterface::new()
+            .unwrap()
+            .bauds(baud_rate)
+            .turnaround(Duration::from_millis(10))
+            .silence(Duration::from_millis(5));
+        let rate = si.max_poll_rate(8, 7);
+        let expected = 1_000_000.0 / cycle_us;
+        assert!((rate - expected).abs() < 1e-6, "{:?}: {} != {}", baud_rate, rate, expected);
+    }
+}
+
+/// Backend recording the level of a DE/RE GPIO value file at each write.
+#[cfg(feature = "gpio")]
+struct DeProbe {
+    value: PathBuf,
+    levels: Arc<std::sync::Mutex<Vec<String>>>,
+}
+
+#[cfg(feature = "gpio")]
+impl Read for DeProbe {
+    fn read(&mut self, _buf: &mut [u8]) -> std::io::Result<usize> {
+        Ok(0)
+    }
+}
+
+#[cfg(feature = "gpio")]
+impl Write for DeProbe {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let level = std::fs::read_to_string(&self.value).unwrap_or_default();
+        self.levels.lock().unwrap().push(level);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(feature = "gpio")]
+#[test]
+fn de_gpio_around_write() {
+    let request = modbus::build_read_holding_registers(1, 0, 1);
+    for (active_high, transmit, idle) in [(true, "1", "0"), (false, "0", "1")] {
+        // stands in for /sys/class/gpio, the line directory being created on export by the kernel
+        let root = temp_path(&format!("gpio-{}", active_high));
+        std::fs::create_dir_all(root.join("gpio17")).unwrap();
+        let value = root.join("gpio17").join("value");
+        let levels = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let probe = DeProbe {
+            value: value.clone(),
+            levels: levels.clone(),
+        };
+        let (si, _sender, _receiver) = wired(probe);
+        let mut si = si.de_gpio_line(gpio::DeGpio::with_root("17", active_high, &root));
+        call!(si.write_port(&request)).unwrap();
+        // exported as output on first use
+        assert_eq!(std::fs::read_to_string(root.join("export")).unwrap(), "17");
+        assert_eq!(std::fs::read_to_string(root.join("gpio17").join("direction")).unwrap(), "out");
+        // asserted while writing, released after
+        let levels = levels.lock().unwrap().clone();
+        assert!(!levels.is_empty() && levels.iter().all(|level| level == transmit), "{:?}", levels);
+        assert_eq!(std::fs::read_to_string(&value).unwrap(), idle);
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
+
+#[test]
+fn settings_quantized_by_driver() {
+    let mut si = SerialInterface::new()
+        .unwrap()
+        .bauds(BaudRate::BaudOther(250000));
+    // not opened
+    assert!(matches!(si.actual_settings(), Err(SIError::PortNotOpened)));
+    assert!(si.settings_quantized().is_none());
+
+    let requested = si.requested_settings();
+    si.actual_settings = Some(PortSettings {
+        baud_rate: BaudRate::BaudOther(230400),
+        ..requested
+    });
+    let msg = si.settings_quantized();
+    assert!(
+        matches!(
+            &msg,
+            Some(SerialMessage::SettingsQuantized { requested, actual })
+                if requested.baud_rate.speed() == 250000 && actual.baud_rate.speed() == 230400
+        ),
+        "{:?}",
+        msg
+    );
+
+    // applied as requested
+    si.actual_settings = Some(requested);
+    assert!(matches!(si.actual_settings(), Ok(actual) if actual == requested));
+    assert!(si.settings_quantized().is_none());
+}
+
+/// Logger keeping the log lines of the interfaces named `log-name-*` only, as it's shared by all the
+/// tests of the process.
+struct CaptureLogger(std::sync::Mutex<Vec<String>>);
+
+impl log::Log for CaptureLogger {
+    fn enabled(&self, _metadata: &log::Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &log::Record) {
+        let line = record.args().to_string();
+        if line.starts_with("[log-name-") {
+            self.0.lock().unwrap().push(line);
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+static LOGS: CaptureLogger = CaptureLogger(std::sync::Mutex::new(Vec::new()));
+
+#[test]
+fn log_name_prefix() {
+    let _ = log::set_logger(&LOGS);
+    log::set_max_level(log::LevelFilter::Debug);
+    for (si, name) in [
+        (SerialInterface::new().unwrap().name("log-name-bus-A".into()), "log-name-bus-A"),
+        // defaults to the port path
+        (SerialInterface::new().unwrap().path("log-name-ttyUSB7".into()), "log-name-ttyUSB7"),
+    ] {
+        let (sender, _app_receiver) = channel();
+        let mut si = si.sender(sender);
+        call!(si.handle_message(SerialMessage::Ping)).unwrap();
+        let logs = LOGS.0.lock().unwrap().clone();
+        let prefix = format!("[{}] SerialInterface::", name);
+        assert!(logs.iter().any(|line| line.starts_with(&prefix)), "{:?}", logs);
+    }
+}
+
+#[test]
+fn receive_transform_applies_to_received_data() {
+    let data = vec![0x01, 0x02, 0x03];
+    let transformed = vec![0x03, 0x02, 0x01];
+    let reverse: ReceiveTransform = Arc::new(|mut data: Vec<u8>| {
+        data.reverse();
+        data
+    });
+    let (si, _sender, receiver) = wired(MockBackend::new());
+    let mut si = si.receive_transform(reverse.clone());
+    for msg in [
+        SerialMessage::Receive(data.clone()),
+        SerialMessage::ReceiveWithId {
+            id: 7,
+            data: data.clone(),
+        },
+        SerialMessage::ReceiveTimed {
+            data: data.clone(),
+            at: SystemTime::now(),
+            direction: None,
+        },
+        SerialMessage::DataSent(data.clone()),
+    ] {
+        call!(si.send_message(msg)).unwrap();
+    }
+    let msgs = collect(&receiver, Duration::from_millis(10));
+    assert!(matches!(&msgs[0], SerialMessage::Receive(d) if *d == transformed));
+    assert!(
+        matches!(&msgs[1], SerialMessage::ReceiveWithId { id: 7, data: d } if *d == transformed)
+    );
+    assert!(matches!(&msgs[2], SerialMessage::ReceiveTimed { data: d, .. } if *d == transformed));
+    // other messages are not transformed
+    assert!(matches!(&msgs[3], SerialMessage::DataSent(d) if *d == data));
+    assert_eq!(msgs.len(), 4);
+
+    // inside batches, each frame is transformed
+    let (si, _sender, receiver) = wired(MockBackend::new());
+    let mut si = si
+        .receive_transform(reverse)
+        .batch_receives(Duration::from_secs(1))
+        .batch_max(2);
+    for frame in [data.clone(), vec![0x04, 0x05]] {
+        call!(si.send_message(SerialMessage::Receive(frame))).unwrap();
+    }
+    let msgs = collect(&receiver, Duration::from_millis(10));
+    assert!(
+        matches!(&msgs[..], [SerialMessage::ReceiveBatch(batch)]
+            if *batch == vec![transformed.clone(), vec![0x05, 0x04]]),
+        "{:?}",
+        msgs
+    );
+}
+
+#[test]
+fn capabilities_match_features() {
+    let caps = capabilities();
+    assert!(caps.capture);
+    assert!(caps.modbus_helpers);
+
+    #[cfg(feature = "async-channel")]
+    assert!(caps.async_channel);
+    #[cfg(not(feature = "async-channel"))]
+    assert!(!caps.async_channel);
+
+    #[cfg(feature = "serde")]
+    assert!(caps.serde);
+    #[cfg(not(feature = "serde"))]
+    assert!(!caps.serde);
+
+    #[cfg(feature = "gpio")]
+    assert!(caps.gpio);
+    #[cfg(not(feature = "gpio"))]
+    assert!(!caps.gpio);
+
+    #[cfg(feature = "test-util")]
+    assert!(caps.test_util);
+    #[cfg(not(feature = "test-util"))]
+    assert!(!caps.test_util);
+}
+
+/// Number of times the calling thread has been woken up so far.
+#[cfg(target_os = "linux")]
+fn thread_wakeups() -> u64 {
+    let status = std::fs::read_to_string("/proc/thread-self/status").unwrap();
+    status
+        .lines()
+        .filter_map(|line| line.strip_prefix("voluntary_ctxt_switches:"))
+        .map(|count| count.trim().parse::<u64>().unwrap())
+        .sum()
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn stop_mode_blocks_waiting_for_messages() {
+    let (si, sender, receiver) = wired(MockBackend::new());
+    // a loop polling the channel would wake up every max_idle_sleep (1ms)
+    let mut si = si.stop_wait(Duration::from_secs(10));
+    let (done, result) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        let start = thread_wakeups();
+        block_on(si.start());
+        let _ = done.send(thread_wakeups() - start);
+    });
+    // let the loop reach wait_message()
+    std::thread::sleep(Duration::from_millis(300));
+    let start = Instant::now();
+    send(&sender, SerialMessage::Ping);
+    assert!(recv_until(&receiver, Duration::from_secs(1), |msg| {
+        matches!(msg, SerialMessage::Pong)
+    })
+    .is_some());
+    // answered long before stop_wait elapses
+    assert!(start.elapsed() < Duration::from_millis(50), "{:?}", start.elapsed());
+    std::thread::sleep(Duration::from_millis(300));
+    send(&sender, SerialMessage::Shutdown);
+    let wakeups = result.recv_timeout(Duration::from_secs(1)).expect("no shutdown");
+    // hundreds if polling for 600ms
+    assert!(wakeups < 50, "{} wakeups", wakeups);
+}
+
+#[test]
+fn default_silence_from_baud_rate() {
+    // 115200 bauds by default, fixed 1.75ms above 19200 bauds
+    let si = SerialInterface::new().unwrap();
+    assert_eq!(si.get_silence(), Some(Duration::from_micros(1750)));
+    // 3.5 chars of 11 bits (8N2) at 9600 bauds
+    let si = SerialInterface::new().unwrap().bauds(BaudRate::Baud9600);
+    assert_eq!(si.get_silence(), Some(Duration::from_secs_f64(3.5 * 11.0 / 9600.0)));
+    // an explicit silence is kept
+    let si = SerialInterface::new()
+        .unwrap()
+        .silence(Duration::from_millis(5))
+        .bauds(BaudRate::Baud9600);
+    assert_eq!(si.get_silence(), Some(Duration::from_millis(5)));
+}